@@ -22,6 +22,14 @@ struct Element {
     id: String,
     #[serde(rename(deserialize = "type"))]
     variant: String,
+    #[serde(rename(deserialize = "minOccurs"))]
+    min_occurs: Option<usize>,
+    #[serde(rename(deserialize = "maxOccurs"))]
+    max_occurs: Option<usize>,
+    range: Option<String>,
+    default: Option<String>,
+    minver: Option<u64>,
+    maxver: Option<u64>,
     #[serde(rename(deserialize = "$value"))]
     details: Option<Vec<ElementDetail>>,
 }
@@ -76,9 +84,9 @@ fn get_elements() -> Vec<Element> {
     for element in ebml_schema
         .elements
         .into_iter()
-        .chain(ebml_matroska_schema.elements.into_iter())
+        .chain(ebml_matroska_schema.elements)
     {
-        if known_elements.get(&element.name).is_none() {
+        if !known_elements.contains(&element.name) {
             known_elements.insert(element.name.clone());
             elements.push(element);
         }
@@ -136,7 +144,13 @@ fn create_elements_file(elements: &[Element]) -> std::io::Result<()> {
         name,
         id,
         variant,
-        path: _,
+        path,
+        min_occurs,
+        max_occurs,
+        range,
+        default,
+        minver,
+        maxver,
         details,
     } in elements
     {
@@ -158,9 +172,18 @@ fn create_elements_file(elements: &[Element]) -> std::io::Result<()> {
         }
 
         let enum_name = name.to_case(Case::Pascal);
+        let min_occurs = min_occurs.unwrap_or(0);
+        let max_occurs = format!("{:?}", max_occurs);
+        let range = format!("{:?}", range);
+        let default = format!("{:?}", default);
+        // Elements are part of all versions from 1 unless stated otherwise
+        let minver = minver.unwrap_or(1);
+        let maxver = format!("{:?}", maxver);
         writeln!(
             file,
-            "    name = {enum_name}, original_name = \"{name}\", id = {id}, variant = {variant};"
+            "    name = {enum_name}, original_name = \"{name}\", id = {id}, variant = {variant}, \
+             path = {path:?}, min_occurs = {min_occurs}, max_occurs = {max_occurs}, \
+             range = {range}, default = {default}, minver = {minver}, maxver = {maxver};"
         )?;
     }
     writeln!(file, "}}")?;
@@ -0,0 +1,128 @@
+//! Asynchronous parsing of elements from [tokio](https://tokio.rs) readers
+//!
+//! This is the async counterpart of the [`iter`](crate::iter) module, so
+//! that inputs can be inspected from within an async runtime without
+//! blocking one of its threads.
+use std::io::SeekFrom;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::{iter::Decoder, Element, ParserOptions, Result};
+
+const DEFAULT_BUFFER_SIZE: usize = 8192;
+
+/// Reads elements one by one from an async reader.
+///
+/// Like [`ElementIterator`](crate::iter::ElementIterator), corrupt regions are
+/// reported as `Corrupted` elements and every element has its `position` set.
+/// Bytes from binary bodies that are not needed are seeked over.
+pub struct AsyncElementReader<R> {
+    reader: R,
+    decoder: Decoder,
+    chunk: Vec<u8>,
+    finished: bool,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncElementReader<R> {
+    /// Create a new element reader
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decoder: Decoder::default(),
+            chunk: vec![0; DEFAULT_BUFFER_SIZE],
+            finished: false,
+        }
+    }
+
+    /// Read the next element. Returns `None` once the input is exhausted.
+    pub async fn next_element(&mut self) -> Option<Result<Element>> {
+        loop {
+            if let Some(element) = self.decoder.next_element() {
+                return Some(element);
+            }
+            if self.finished {
+                return None;
+            }
+
+            let skip = self.decoder.take_skip();
+            if skip > 0 {
+                if let Err(e) = self.reader.seek(SeekFrom::Current(skip as i64)).await {
+                    self.finished = true;
+                    return Some(Err(e.into()));
+                }
+            }
+
+            self.chunk.resize(self.decoder.chunk_size(), 0);
+            match self.reader.read(&mut self.chunk).await {
+                Ok(0) => {
+                    self.finished = true;
+                    self.decoder.finish();
+                }
+                Ok(num_read) => self.decoder.feed(&self.chunk[..num_read]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+    }
+
+    /// Use the given options for parsing
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.decoder.set_options(options);
+        self
+    }
+
+    /// Consume the element reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Parse all elements from an async reader
+pub async fn parse_elements<R: AsyncRead + AsyncSeek + Unpin>(reader: R) -> Result<Vec<Element>> {
+    let mut element_reader = AsyncElementReader::new(reader);
+    let mut elements = Vec::new();
+    while let Some(element) = element_reader.next_element().await {
+        elements.push(element?);
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{elements::Id, Binary, Body, Header};
+
+    #[tokio::test]
+    async fn test_parse_elements() {
+        // Void with a 16-bytes body followed by a DocType
+        let mut input = vec![0xEC, 0x90];
+        input.extend_from_slice(&[0; 16]);
+        input.extend_from_slice(&[0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D]);
+
+        let elements = parse_elements(Cursor::new(input)).await.unwrap();
+        assert_eq!(
+            elements,
+            vec![
+                Element {
+                    header: Header {
+                        position: Some(0),
+                        ..Header::new(Id::Void, 2, 16)
+                    },
+                    body: Body::Binary(Binary::Void(16)),
+                },
+                Element {
+                    header: Header {
+                        position: Some(18),
+                        ..Header::new(Id::DocType, 3, 4)
+                    },
+                    body: Body::String("webm".to_string()),
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Table of the files attached to a file, such as fonts or cover art
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    model::{AttachedFile, Document},
+    table::write_table,
+    Element,
+};
+
+/// Attached files of a file, see [`attachment_table`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttachmentTable {
+    /// Attached files in the order of the Attachments element
+    pub attachments: Vec<AttachedFile>,
+}
+
+impl fmt::Display for AttachmentTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .attachments
+            .iter()
+            .map(|attachment| {
+                vec![
+                    attachment.file_name.clone(),
+                    attachment.mime_type.clone(),
+                    attachment
+                        .data_size
+                        .map(|size| size.to_string())
+                        .unwrap_or_default(),
+                    attachment
+                        .uid
+                        .map(|uid| uid.to_string())
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        write_table(f, &["name", "type", "size", "uid"], &rows)
+    }
+}
+
+/// List the attached files of the first Segment of parsed elements
+pub fn attachment_table(elements: &[Element]) -> AttachmentTable {
+    AttachmentTable {
+        attachments: Document::from_elements(elements).attachments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_attachment_table() {
+        // Attachments with a font of 4 bytes and UID 9
+        const INPUT: &[u8] = &[
+            0x19, 0x41, 0xA4, 0x69, 0xA4, 0x61, 0xA7, 0xA1, 0x46, 0x6E, 0x88, 0x66, 0x6F, 0x6E,
+            0x74, 0x2E, 0x74, 0x74, 0x66, 0x46, 0x60, 0x88, 0x66, 0x6F, 0x6E, 0x74, 0x2F, 0x74,
+            0x74, 0x66, 0x46, 0xAE, 0x81, 0x09, 0x46, 0x5C, 0x84, 0x00, 0x01, 0x00, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let table = attachment_table(&elements);
+        assert_eq!(table.attachments[0].data_position, Some(37));
+        assert_eq!(
+            table.to_string(),
+            "name      type      size  uid\n\
+             font.ttf  font/ttf  4     9"
+        );
+    }
+}
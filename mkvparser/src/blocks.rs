@@ -0,0 +1,825 @@
+//! Helpers to walk the blocks of Clusters
+//!
+//! These work on the stream of parsed elements, e.g. from an
+//! [`ElementIterator`](crate::iter::ElementIterator), so that files don't
+//! need to be fully parsed in memory first, except for
+//! [`compose_block_groups`] which works on element trees.
+//! Elements may be owned or borrowed, such as with `elements.iter().map(Ok)`
+//! for elements already parsed in memory.
+use std::{borrow::Borrow, collections::BTreeSet, ops::Range};
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    elements::Id, tree::ElementTree, Binary, Block, Body, DiscardPadding, Element, Header, Lacing,
+    Result,
+};
+
+/// A SimpleBlock or BlockGroup that starts with a keyframe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Keyframe {
+    /// Track of the block
+    pub track_number: usize,
+    /// Absolute timestamp of the block in nanoseconds, if the Timestamp of
+    /// its Cluster is known
+    pub time_ns: Option<i64>,
+    /// Position of the SimpleBlock or BlockGroup in the input
+    pub position: Option<usize>,
+    /// Size of the SimpleBlock or BlockGroup
+    pub size: Option<usize>,
+}
+
+impl Keyframe {
+    fn new(header: &Header, binary: &Binary) -> Option<Self> {
+        Some(Self {
+            track_number: binary.track_number()?,
+            time_ns: binary.absolute_time_ns(),
+            position: header.position,
+            size: header.size,
+        })
+    }
+}
+
+/// A SimpleBlock or Block, see [`blocks`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockRecord {
+    /// Track of the block
+    pub track_number: usize,
+    /// Absolute timestamp of the block in nanoseconds, if the Timestamp of
+    /// its Cluster is known
+    pub time_ns: Option<i64>,
+    /// Whether the block is a keyframe, i.e. a SimpleBlock with the
+    /// keyframe flag or the Block of a BlockGroup without ReferenceBlock
+    pub keyframe: bool,
+    /// Lacing of the frames of the block, if any
+    pub lacing: Option<Lacing>,
+    /// Position of the SimpleBlock or Block in the input
+    pub position: Option<usize>,
+    /// Size of the SimpleBlock or Block
+    pub size: Option<usize>,
+    /// Sizes of the frames of the block, one per frame
+    pub frame_sizes: Vec<usize>,
+}
+
+impl BlockRecord {
+    fn new(header: &Header, binary: &Binary) -> Option<Self> {
+        Some(Self {
+            track_number: binary.track_number()?,
+            time_ns: binary.absolute_time_ns(),
+            keyframe: matches!(binary, Binary::SimpleBlock(block) if block.keyframe()),
+            lacing: binary.lacing(),
+            position: header.position,
+            size: header.size,
+            frame_sizes: binary.frame_sizes().unwrap_or_default(),
+        })
+    }
+}
+
+// A BlockGroup whose children are still being read
+struct PendingGroup {
+    header: Header,
+    keyframe: Option<Keyframe>,
+    block: Option<BlockRecord>,
+    referenced: bool,
+}
+
+// Whether an element following a BlockGroup is part of it
+fn block_group_contains(group: &Header, element: &Element) -> bool {
+    match (group.position, group.size, element.header.position) {
+        (Some(start), Some(size), Some(position)) => position < start + size,
+        _ => element
+            .header
+            .id
+            .schema()
+            .is_some_and(|schema| schema.path.starts_with("\\Segment\\Cluster\\BlockGroup\\")),
+    }
+}
+
+impl PendingGroup {
+    fn contains(&self, element: &Element) -> bool {
+        block_group_contains(&self.header, element)
+    }
+
+    fn inspect(&mut self, element: &Element) {
+        match (&element.header.id, &element.body) {
+            (Id::Block, Body::Binary(binary)) => {
+                self.keyframe = Keyframe::new(&self.header, binary);
+                self.block = BlockRecord::new(&element.header, binary);
+            }
+            (Id::ReferenceBlock, _) => self.referenced = true,
+            _ => (),
+        }
+    }
+
+    // Blocks of BlockGroups without ReferenceBlock are keyframes
+    fn into_keyframe(self) -> Option<Keyframe> {
+        self.keyframe.filter(|_| !self.referenced)
+    }
+
+    fn into_block(self) -> Option<BlockRecord> {
+        let keyframe = !self.referenced;
+        self.block.map(|block| BlockRecord { keyframe, ..block })
+    }
+
+    fn new(header: Header) -> Self {
+        Self {
+            header,
+            keyframe: None,
+            block: None,
+            referenced: false,
+        }
+    }
+}
+
+// A SimpleBlock, or a BlockGroup once all its children are read
+enum WalkedBlock<E> {
+    SimpleBlock(E),
+    BlockGroup(Box<PendingGroup>),
+}
+
+// Walk parsed elements and yield their SimpleBlocks and BlockGroups
+struct BlockWalker<I: Iterator> {
+    elements: I,
+    group: Option<PendingGroup>,
+    next: Option<I::Item>,
+}
+
+impl<I: Iterator> BlockWalker<I> {
+    fn new(elements: I) -> Self {
+        Self {
+            elements,
+            group: None,
+            next: None,
+        }
+    }
+}
+
+impl<I, E> Iterator for BlockWalker<I>
+where
+    I: Iterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    type Item = Result<WalkedBlock<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = match self.next.take().or_else(|| self.elements.next()) {
+                Some(Ok(item)) => item,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    return self
+                        .group
+                        .take()
+                        .map(|group| Ok(WalkedBlock::BlockGroup(Box::new(group))))
+                }
+            };
+
+            let element = item.borrow();
+
+            if let Some(group) = &mut self.group {
+                if group.contains(element) {
+                    group.inspect(element);
+                    continue;
+                }
+                // The element is looked at again once the group is done
+                self.next = Some(Ok(item));
+                return self
+                    .group
+                    .take()
+                    .map(|group| Ok(WalkedBlock::BlockGroup(Box::new(group))));
+            }
+
+            match (&element.header.id, &element.body) {
+                (Id::SimpleBlock, Body::Binary(Binary::SimpleBlock(_))) => {
+                    return Some(Ok(WalkedBlock::SimpleBlock(item)));
+                }
+                (Id::BlockGroup, Body::Master) => {
+                    self.group = Some(PendingGroup::new(element.header.clone()));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// An iterator over the keyframes of parsed elements, see [`keyframes`]
+pub struct Keyframes<I: Iterator> {
+    walker: BlockWalker<I>,
+}
+
+impl<I, E> Iterator for Keyframes<I>
+where
+    I: Iterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    type Item = Result<Keyframe>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let keyframe = match self.walker.next()? {
+                Ok(WalkedBlock::SimpleBlock(element)) => match &element.borrow().body {
+                    Body::Binary(binary @ Binary::SimpleBlock(block)) if block.keyframe() => {
+                        Keyframe::new(&element.borrow().header, binary)
+                    }
+                    _ => None,
+                },
+                Ok(WalkedBlock::BlockGroup(group)) => group.into_keyframe(),
+                Err(e) => return Some(Err(e)),
+            };
+            if let Some(keyframe) = keyframe {
+                return Some(Ok(keyframe));
+            }
+        }
+    }
+}
+
+/// Walk parsed elements and only yield the keyframes of Clusters: the
+/// SimpleBlocks with the keyframe flag and the BlockGroups without any
+/// ReferenceBlock.
+///
+/// Elements must be in the order they are parsed. A BlockGroup is yielded
+/// once the first element past it is found.
+pub fn keyframes<I, E>(elements: I) -> Keyframes<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    Keyframes {
+        walker: BlockWalker::new(elements.into_iter()),
+    }
+}
+
+/// An iterator over the blocks of parsed elements, see [`blocks`]
+pub struct Blocks<I: Iterator> {
+    walker: BlockWalker<I>,
+}
+
+impl<I, E> Iterator for Blocks<I>
+where
+    I: Iterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    type Item = Result<BlockRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let block = match self.walker.next()? {
+                Ok(WalkedBlock::SimpleBlock(element)) => match &element.borrow().body {
+                    Body::Binary(binary) => BlockRecord::new(&element.borrow().header, binary),
+                    _ => None,
+                },
+                Ok(WalkedBlock::BlockGroup(group)) => group.into_block(),
+                Err(e) => return Some(Err(e)),
+            };
+            if let Some(block) = block {
+                return Some(Ok(block));
+            }
+        }
+    }
+}
+
+/// Walk parsed elements and yield a record of each SimpleBlock and Block of
+/// Clusters, such as to list them as a table.
+///
+/// Elements must be in the order they are parsed. The Block of a BlockGroup
+/// is yielded once the first element past the BlockGroup is found.
+pub fn blocks<I, E>(elements: I) -> Blocks<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    Blocks {
+        walker: BlockWalker::new(elements.into_iter()),
+    }
+}
+
+/// Where a Cluster is and what it contains, see [`clusters`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClusterSummary {
+    /// Absolute timestamp of the Cluster in nanoseconds, if it has a
+    /// Timestamp
+    pub time_ns: Option<i64>,
+    /// Position of the Cluster in the input
+    pub position: Option<usize>,
+    /// Size of the Cluster, if known
+    pub size: Option<usize>,
+    /// Number of SimpleBlocks and Blocks in the Cluster
+    pub block_count: usize,
+    /// Tracks of the blocks of the Cluster, in ascending order
+    pub track_numbers: Vec<usize>,
+}
+
+// A Cluster whose children are still being read
+struct PendingCluster {
+    summary: ClusterSummary,
+    track_numbers: BTreeSet<usize>,
+    // Absolute timestamp of the last block
+    last_time_ns: Option<i64>,
+}
+
+impl PendingCluster {
+    fn new(header: &Header) -> Self {
+        Self {
+            summary: ClusterSummary {
+                time_ns: None,
+                position: header.position,
+                size: header.size.or(header.computed_size),
+                block_count: 0,
+                track_numbers: Vec::new(),
+            },
+            track_numbers: BTreeSet::new(),
+            last_time_ns: None,
+        }
+    }
+
+    fn contains(&self, element: &Element) -> bool {
+        match (
+            self.summary.position,
+            self.summary.size,
+            element.header.position,
+        ) {
+            (Some(start), Some(size), Some(position)) => position < start + size,
+            // Clusters with unknown size end at the first element that
+            // can't be part of them, global elements such as Void included
+            _ => element.header.id.schema().is_some_and(|schema| {
+                schema.path.starts_with("\\Segment\\Cluster\\") || schema.path.starts_with("\\(")
+            }),
+        }
+    }
+
+    fn inspect(&mut self, element: &Element, timestamp_scale: u64) {
+        match (&element.header.id, &element.body) {
+            (Id::Timestamp, Body::Unsigned(timestamp)) => {
+                // Timestamps too large for nanoseconds are left unknown
+                self.summary.time_ns = timestamp
+                    .value()
+                    .checked_mul(timestamp_scale)
+                    .and_then(|time_ns| i64::try_from(time_ns).ok());
+            }
+            (Id::SimpleBlock | Id::Block, Body::Binary(binary)) => {
+                self.summary.block_count += 1;
+                self.track_numbers.extend(binary.track_number());
+                self.last_time_ns = binary.absolute_time_ns().or(self.last_time_ns);
+            }
+            _ => (),
+        }
+    }
+
+    fn into_summary(self) -> ClusterSummary {
+        ClusterSummary {
+            track_numbers: self.track_numbers.into_iter().collect(),
+            ..self.summary
+        }
+    }
+}
+
+/// An iterator over the Clusters of parsed elements, see [`clusters`]
+pub struct Clusters<I: Iterator> {
+    elements: I,
+    timestamp_scale: u64,
+    cluster: Option<PendingCluster>,
+    next: Option<I::Item>,
+}
+
+impl<I, E> Iterator for Clusters<I>
+where
+    I: Iterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    type Item = Result<ClusterSummary>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = match self.next.take().or_else(|| self.elements.next()) {
+                Some(Ok(item)) => item,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    return self
+                        .cluster
+                        .take()
+                        .map(|cluster| Ok(cluster.into_summary()))
+                }
+            };
+
+            let element = item.borrow();
+
+            if let Some(cluster) = &mut self.cluster {
+                if element.header.id != Id::Cluster && cluster.contains(element) {
+                    cluster.inspect(element, self.timestamp_scale);
+                    continue;
+                }
+                // The element is looked at again once the Cluster is done
+                self.next = Some(Ok(item));
+                return self
+                    .cluster
+                    .take()
+                    .map(|cluster| Ok(cluster.into_summary()));
+            }
+
+            match (&element.header.id, &element.body) {
+                (Id::TimestampScale, Body::Unsigned(timestamp_scale)) => {
+                    self.timestamp_scale = timestamp_scale.value();
+                }
+                (Id::Cluster, Body::Master) => {
+                    self.cluster = Some(PendingCluster::new(&element.header));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Walk parsed elements and yield a summary of each Cluster: its absolute
+/// timestamp, where it is, and the number and tracks of its blocks.
+///
+/// Elements must be in the order they are parsed. Timestamps are scaled by
+/// the last TimestampScale found, which defaults to 1 millisecond. A
+/// Cluster is yielded once the first element past it is found.
+pub fn clusters<I, E>(elements: I) -> Clusters<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<E>>,
+    E: Borrow<Element>,
+{
+    Clusters {
+        elements: elements.into_iter(),
+        timestamp_scale: 1_000_000,
+        cluster: None,
+        next: None,
+    }
+}
+
+/// Remove the Clusters, along with their descendants, that are entirely
+/// outside of a time range in nanoseconds.
+///
+/// A Cluster is considered to last until the next one starts, or until its
+/// last block for the last Cluster. Clusters without a Timestamp are kept.
+pub fn retain_clusters_in_time_range(elements: &mut Vec<Element>, time_range: Range<i64>) {
+    // Indices, start time and last block time of each Cluster
+    let mut spans = Vec::new();
+    let mut timestamp_scale = 1_000_000;
+    let mut index = 0;
+    while index < elements.len() {
+        let element = &elements[index];
+        match (&element.header.id, &element.body) {
+            (Id::TimestampScale, Body::Unsigned(value)) => timestamp_scale = value.value(),
+            (Id::Cluster, Body::Master) => {
+                let start = index;
+                let mut cluster = PendingCluster::new(&element.header);
+                index += 1;
+                while let Some(element) = elements.get(index) {
+                    if element.header.id == Id::Cluster || !cluster.contains(element) {
+                        break;
+                    }
+                    cluster.inspect(element, timestamp_scale);
+                    index += 1;
+                }
+                spans.push((start..index, cluster.summary.time_ns, cluster.last_time_ns));
+                continue;
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+
+    let mut keep = vec![true; elements.len()];
+    let next_starts = spans
+        .iter()
+        .skip(1)
+        .map(|(_, start, _)| *start)
+        .chain([None]);
+    for ((indices, start, last_time), next_start) in spans.iter().zip(next_starts) {
+        let Some(start) = start else {
+            continue;
+        };
+        let overlaps = match (next_start, last_time) {
+            (Some(next_start), _) => next_start > time_range.start,
+            (None, Some(last_time)) => *last_time >= time_range.start,
+            (None, None) => true,
+        };
+        if !overlaps || *start >= time_range.end {
+            keep[indices.clone()].fill(false);
+        }
+    }
+    let mut keep = keep.into_iter();
+    elements.retain(|_| keep.next().unwrap_or(true));
+}
+
+/// Remove the SimpleBlocks and BlockGroups, along with their descendants,
+/// of other tracks than the given ones
+pub fn retain_blocks_of_tracks(elements: &mut Vec<Element>, track_numbers: &[usize]) {
+    let is_retained = |binary: &Binary| {
+        binary
+            .track_number()
+            .is_none_or(|track_number| track_numbers.contains(&track_number))
+    };
+    let mut keep = vec![true; elements.len()];
+    let mut index = 0;
+    while index < elements.len() {
+        let element = &elements[index];
+        match (&element.header.id, &element.body) {
+            (Id::SimpleBlock, Body::Binary(binary)) => keep[index] = is_retained(binary),
+            (Id::BlockGroup, Body::Master) => {
+                let start = index;
+                let mut retained = true;
+                index += 1;
+                while let Some(child) = elements.get(index) {
+                    if !block_group_contains(&element.header, child) {
+                        break;
+                    }
+                    if let (Id::Block, Body::Binary(binary)) = (&child.header.id, &child.body) {
+                        retained = is_retained(binary);
+                    }
+                    index += 1;
+                }
+                keep[start..index].fill(retained);
+                continue;
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+    let mut keep = keep.into_iter();
+    elements.retain(|_| keep.next().unwrap_or(true));
+}
+
+/// A BlockGroup whose Block, BlockDuration, ReferenceBlocks and
+/// DiscardPadding are combined into a single record
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComposedBlockGroup {
+    /// The Block of the group
+    #[serde(flatten)]
+    pub block: Option<Block>,
+    /// Value of the BlockDuration, in Track ticks
+    pub duration: Option<u64>,
+    /// Values of the ReferenceBlocks, relative to the Block timestamp
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<i64>,
+    /// Duration of the audio discarded by the DiscardPadding
+    pub discard_padding: Option<DiscardPadding>,
+    /// Any other element of the group, such as BlockAdditions
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ElementTree>,
+}
+
+impl ComposedBlockGroup {
+    fn new(children: Vec<ElementTree>) -> Self {
+        let mut group = Self {
+            block: None,
+            duration: None,
+            references: Vec::new(),
+            discard_padding: None,
+            children: Vec::new(),
+        };
+        for child in children {
+            let ElementTree::Normal(Element { header, body }) = child else {
+                group.children.push(child);
+                continue;
+            };
+            match (header.id.clone(), body) {
+                (Id::Block, Body::Binary(Binary::Block(block))) => group.block = Some(block),
+                (Id::BlockDuration, Body::Unsigned(duration)) => {
+                    group.duration = Some(duration.value())
+                }
+                (Id::ReferenceBlock, Body::Signed(reference)) => group.references.push(reference),
+                (Id::DiscardPadding, Body::Signed(duration_ns)) => {
+                    group.discard_padding = header.discard_padding.or(Some(DiscardPadding {
+                        duration_ns,
+                        samples: None,
+                    }))
+                }
+                (_, body) => group
+                    .children
+                    .push(ElementTree::Normal(Element { header, body })),
+            }
+        }
+        group
+    }
+}
+
+/// Replace BlockGroups, at any depth, by a single element holding a
+/// [`ComposedBlockGroup`], as most users want the fields of the group
+/// together per frame.
+///
+/// The composed elements keep the header of the BlockGroup, but can no
+/// longer be written back.
+pub fn compose_block_groups(trees: &mut [ElementTree]) {
+    for tree in trees {
+        let ElementTree::Master(master) = tree else {
+            continue;
+        };
+        if master.header.id != Id::BlockGroup {
+            compose_block_groups(&mut master.children);
+            continue;
+        }
+        let children = std::mem::take(&mut master.children);
+        *tree = ElementTree::Normal(Element {
+            header: master.header.clone(),
+            body: Body::Binary(Binary::BlockGroup(Box::new(ComposedBlockGroup::new(
+                children,
+            )))),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, tree::build_element_trees, FrameType};
+
+    // Cluster with Timestamp 10, a keyframe SimpleBlock, a SimpleBlock
+    // that isn't a keyframe, a BlockGroup with a ReferenceBlock and a
+    // BlockGroup without any
+    const INPUT: &[u8] = &[
+        0x1F, 0x43, 0xB6, 0x75, 0xA2, 0xE7, 0x81, 0x0A, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80, 0xA3,
+        0x84, 0x81, 0x00, 0x01, 0x00, 0xA0, 0x89, 0xA1, 0x84, 0x81, 0x00, 0x02, 0x00, 0xFB, 0x81,
+        0xFF, 0xA0, 0x86, 0xA1, 0x84, 0x82, 0x00, 0x03, 0x00,
+    ];
+
+    #[test]
+    fn test_keyframes() {
+        let keyframes = keyframes(ElementIterator::new(INPUT))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            keyframes,
+            [
+                Keyframe {
+                    track_number: 1,
+                    time_ns: Some(10_000_000),
+                    position: Some(8),
+                    size: Some(6),
+                },
+                Keyframe {
+                    track_number: 2,
+                    time_ns: Some(13_000_000),
+                    position: Some(31),
+                    size: Some(8),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocks = blocks(ElementIterator::new(INPUT))
+            .map(|block| {
+                let block = block.unwrap();
+                (block.track_number, block.keyframe, block.position)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            blocks,
+            [
+                (1, true, Some(8)),
+                (1, false, Some(14)),
+                (1, false, Some(22)),
+                (2, true, Some(33)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compose_block_groups() {
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let mut trees = build_element_trees(&elements);
+        compose_block_groups(&mut trees);
+
+        let groups = trees[0]
+            .children()
+            .iter()
+            .filter_map(|tree| match tree.body() {
+                Some(Body::Binary(Binary::BlockGroup(group))) => Some((tree.header(), group)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(groups.len(), 2);
+
+        let (header, group) = groups[0];
+        assert_eq!(header.id, Id::BlockGroup);
+        assert_eq!(header.position, Some(20));
+        assert_eq!(group.references, [-1]);
+        assert_eq!(
+            group.block.as_ref().and_then(Block::frame_type),
+            Some(FrameType::P)
+        );
+        assert!(group.children.is_empty());
+
+        let (_, group) = groups[1];
+        assert!(group.references.is_empty());
+        assert_eq!(
+            group.block.as_ref().and_then(Block::frame_type),
+            Some(FrameType::I)
+        );
+    }
+
+    #[test]
+    fn test_clusters() {
+        // A second Cluster with Timestamp 20 and a SimpleBlock of track 3
+        const CLUSTER: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x89, 0xE7, 0x81, 0x14, 0xA3, 0x84, 0x83, 0x00, 0x00, 0x80,
+        ];
+        let input = [INPUT, CLUSTER].concat();
+        let clusters = clusters(ElementIterator::new(&input[..]))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            clusters,
+            [
+                ClusterSummary {
+                    time_ns: Some(10_000_000),
+                    position: Some(0),
+                    size: Some(39),
+                    block_count: 4,
+                    track_numbers: vec![1, 2],
+                },
+                ClusterSummary {
+                    time_ns: Some(20_000_000),
+                    position: Some(39),
+                    size: Some(14),
+                    block_count: 1,
+                    track_numbers: vec![3],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clusters_with_huge_timestamp() {
+        // Cluster with Timestamp 0x00FFFFFFFFFFFFFF and a SimpleBlock
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x8F, 0xE7, 0x87, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xA3, 0x84, 0x81, 0x00, 0x00, 0x80,
+        ];
+        let clusters = clusters(ElementIterator::new(INPUT))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(clusters[0].time_ns, None);
+        assert_eq!(clusters[0].block_count, 1);
+
+        let mut elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        retain_clusters_in_time_range(&mut elements, 10_000_000_000..i64::MAX);
+        assert_eq!(elements.len(), 3);
+    }
+
+    #[test]
+    fn test_retain_clusters_in_time_range() {
+        // Clusters with Timestamps 10, 20 and 30, each with a SimpleBlock
+        // at timestamp 0 and 5
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x8F, 0xE7, 0x81, 0x0A, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80,
+            0xA3, 0x84, 0x81, 0x00, 0x05, 0x80, 0x1F, 0x43, 0xB6, 0x75, 0x8F, 0xE7, 0x81, 0x14,
+            0xA3, 0x84, 0x81, 0x00, 0x00, 0x80, 0xA3, 0x84, 0x81, 0x00, 0x05, 0x80, 0x1F, 0x43,
+            0xB6, 0x75, 0x8F, 0xE7, 0x81, 0x1E, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80, 0xA3, 0x84,
+            0x81, 0x00, 0x05, 0x80,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let cluster_positions = |time_range: Range<i64>| {
+            let mut elements = elements.clone();
+            retain_clusters_in_time_range(&mut elements, time_range);
+            elements
+                .iter()
+                .filter(|element| element.header.id == Id::Cluster)
+                .map(|element| element.header.position.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(cluster_positions(0..i64::MAX), [0, 20, 40]);
+        assert_eq!(cluster_positions(21_000_000..22_000_000), [20]);
+        assert_eq!(cluster_positions(20_000_000..30_000_000), [20]);
+        assert_eq!(cluster_positions(19_000_000..31_000_000), [0, 20, 40]);
+        assert_eq!(cluster_positions(35_000_000..40_000_000), [40]);
+        assert_eq!(
+            cluster_positions(36_000_000..40_000_000),
+            Vec::<usize>::new()
+        );
+
+        let mut retained = elements.clone();
+        retain_clusters_in_time_range(&mut retained, 20_000_000..30_000_000);
+        assert_eq!(retained[..], elements[4..8]);
+    }
+
+    #[test]
+    fn test_retain_blocks_of_tracks() {
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let positions = |track_numbers: &[usize]| {
+            let mut elements = elements.clone();
+            retain_blocks_of_tracks(&mut elements, track_numbers);
+            elements
+                .iter()
+                .map(|element| element.header.position.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(&[1]), [0, 5, 8, 14, 20, 22, 28]);
+        assert_eq!(positions(&[2]), [0, 5, 31, 33]);
+        assert_eq!(positions(&[1, 2]).len(), elements.len());
+    }
+}
@@ -0,0 +1,212 @@
+//! Zero-copy parsing of elements
+//!
+//! The types in this module borrow string and binary payloads from the
+//! input slice instead of allocating them, which is useful when going
+//! through large memory-mapped inputs.
+use chrono::{DateTime, Utc};
+
+use crate::{
+    elements::Type, parse_date, parse_float, parse_header, parse_int, peek_binary, take, Body,
+    Element, Error, Header, IResult, Result, Unsigned,
+};
+
+/// An [EBML Body](https://github.com/ietf-wg-cellar/ebml-specification/blob/master/specification.markdown#ebml-body)
+/// borrowing its payload from the input
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyRef<'a> {
+    /// A Master Body contains no data, but will contain zero or more elements
+    /// that come after it.
+    Master,
+    /// An Unsigned Integer that may contain a known Enumeration
+    Unsigned(Unsigned),
+    /// A Signed Integer
+    Signed(i64),
+    /// A Float
+    Float(f64),
+    /// A String
+    String(&'a str),
+    /// An UTF-8 String
+    Utf8(&'a str),
+    /// A Date
+    Date(DateTime<Utc>),
+    /// The full payload of a Binary element
+    Binary(&'a [u8]),
+}
+
+/// Represents an EBML Element borrowing its payload from the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementRef<'a> {
+    /// The Header
+    pub header: Header,
+    /// The Body
+    pub body: BodyRef<'a>,
+}
+
+impl ElementRef<'_> {
+    /// Convert into an owned [`Element`], summarizing binary payloads
+    /// the same way [`parse_element`](crate::parse_element) does.
+    pub fn to_element(&self) -> Result<Element> {
+        let body = match self.body {
+            BodyRef::Master => Body::Master,
+            BodyRef::Unsigned(ref value) => Body::Unsigned(value.clone()),
+            BodyRef::Signed(value) => Body::Signed(value),
+            BodyRef::Float(value) => Body::Float(value),
+            BodyRef::String(value) => Body::String(value.to_string()),
+            BodyRef::Utf8(value) => Body::Utf8(value.to_string()),
+            BodyRef::Date(value) => Body::Date(value),
+            BodyRef::Binary(value) => Body::Binary(peek_binary(&self.header, value)?.1),
+        };
+        Ok(Element {
+            header: self.header.clone(),
+            body,
+        })
+    }
+}
+
+fn parse_str<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], &'a str> {
+    let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
+    let (input, string_bytes) = take(body_size)(input)?;
+    let value = std::str::from_utf8(string_bytes)?;
+
+    // Remove trimming null characters
+    Ok((input, value.trim_end_matches('\0')))
+}
+
+/// Parse element body without copying string and binary payloads
+pub fn parse_body_ref<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], BodyRef<'a>> {
+    let (input, body) = match header.id.get_type() {
+        Type::Master => (input, BodyRef::Master),
+        Type::Unsigned => {
+            let (input, value) = parse_int(header, input)?;
+            (input, BodyRef::Unsigned(Unsigned::new(&header.id, value)))
+        }
+        Type::Signed => {
+            let (input, value) = parse_int(header, input)?;
+            (input, BodyRef::Signed(value))
+        }
+        Type::Float => {
+            let (input, value) = parse_float(header, input)?;
+            (input, BodyRef::Float(value))
+        }
+        Type::String => {
+            let (input, value) = parse_str(header, input)?;
+            (input, BodyRef::String(value))
+        }
+        Type::Utf8 => {
+            let (input, value) = parse_str(header, input)?;
+            (input, BodyRef::Utf8(value))
+        }
+        Type::Date => {
+            let (input, value) = parse_date(header, input)?;
+            (input, BodyRef::Date(value))
+        }
+        Type::Binary => {
+            let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
+            let (input, value) = take(body_size)(input)?;
+            (input, BodyRef::Binary(value))
+        }
+    };
+    Ok((input, body))
+}
+
+/// Parse an element without copying string and binary payloads
+pub fn parse_element_ref(input: &[u8]) -> IResult<&[u8], ElementRef<'_>> {
+    let (input, header) = parse_header(input)?;
+    let (input, body) = parse_body_ref(&header, input)?;
+    Ok((input, ElementRef { header, body }))
+}
+
+/// An iterator over all elements of an in-memory input, borrowing
+/// their payloads.
+///
+/// Elements have their `position` set. Iteration stops after the first
+/// error, which is yielded as the last item. Trailing incomplete data
+/// yields [`Error::NeedData`].
+pub struct ElementRefIterator<'a> {
+    input: &'a [u8],
+    position: usize,
+    failed: bool,
+}
+
+impl<'a> ElementRefIterator<'a> {
+    /// Create an iterator over the elements in `input`
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            position: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<'a> Iterator for ElementRefIterator<'a> {
+    type Item = Result<ElementRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() || self.failed {
+            return None;
+        }
+        match parse_element_ref(self.input) {
+            Ok((input, mut element)) => {
+                element.header.position = Some(self.position);
+                self.position += self.input.len() - input.len();
+                self.input = input;
+                Some(Ok(element))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{elements::Id, Binary};
+    use std::num::NonZeroUsize;
+
+    const EMPTY: &[u8] = &[];
+
+    #[test]
+    fn test_parse_element_ref() {
+        const INPUT: &[u8] = &[0x42, 0x82, 0x86, 0x77, 0x65, 0x62, 0x6D, 0x00, 0x00];
+        assert_eq!(
+            parse_element_ref(INPUT),
+            Ok((
+                EMPTY,
+                ElementRef {
+                    header: Header::new(Id::DocType, 3, 6),
+                    body: BodyRef::String("webm"),
+                }
+            ))
+        );
+
+        const CRC32: &[u8] = &[0xBF, 0x84, 0xAF, 0x93, 0x97, 0x18];
+        let (_, element) = parse_element_ref(CRC32).unwrap();
+        assert_eq!(element.body, BodyRef::Binary(&CRC32[2..]));
+        assert_eq!(
+            element.to_element(),
+            Ok(Element {
+                header: Header::new(Id::Crc32, 2, 4),
+                body: Body::Binary(Binary::Standard("[af 93 97 18]".into()))
+            })
+        );
+    }
+
+    #[test]
+    fn test_element_ref_iterator() {
+        const INPUT: &[u8] = &[
+            0x1A, 0x45, 0xDF, 0xA3, 0x88, 0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D, 0xEC, 0x82,
+            0x00,
+        ];
+        let elements = ElementRefIterator::new(INPUT).collect::<Vec<_>>();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0].as_ref().unwrap().body, BodyRef::Master);
+        assert_eq!(elements[1].as_ref().unwrap().body, BodyRef::String("webm"));
+        assert_eq!(elements[1].as_ref().unwrap().header.position, Some(5));
+        // The Void element is missing one byte
+        assert_eq!(elements[2], Err(Error::NeedData(NonZeroUsize::new(1))));
+    }
+}
@@ -0,0 +1,142 @@
+//! Chapters of a file as an indented timeline, edition by edition
+use std::fmt;
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    format_time,
+    model::{Document, TimelineChapter},
+    Element,
+};
+
+/// An edition and its chapters, see [`chapter_list`]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditionChapters {
+    /// Unique ID of the edition
+    pub uid: Option<u64>,
+    /// Whether the edition is hidden
+    pub hidden: bool,
+    /// Whether the edition should be used as the default one
+    pub default: bool,
+    /// Whether the edition is ordered
+    pub ordered: bool,
+    /// Chapters of the edition, with nested chapters following their
+    /// parents
+    pub chapters: Vec<TimelineChapter>,
+}
+
+/// Editions of a file and their chapters
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChapterList {
+    /// Editions in the order of the Chapters element
+    pub editions: Vec<EditionChapters>,
+}
+
+impl fmt::Display for EditionChapters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Edition")?;
+        let flags = [
+            (self.default, "default"),
+            (self.ordered, "ordered"),
+            (self.hidden, "hidden"),
+        ];
+        let details = self
+            .uid
+            .map(|uid| format!("uid {uid}"))
+            .into_iter()
+            .chain(
+                flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, flag)| flag.to_string()),
+            )
+            .collect::<Vec<_>>();
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        for chapter in &self.chapters {
+            write!(f, "\n{}", "  ".repeat(chapter.level + 1))?;
+            write!(f, "{}", format_time(chapter.start as i64))?;
+            if let Some(end) = chapter.end {
+                write!(f, " - {}", format_time(end as i64))?;
+            }
+            let titles = chapter
+                .titles
+                .iter()
+                .map(|(language, title)| format!("{language}: {title}"))
+                .collect::<Vec<_>>();
+            if !titles.is_empty() {
+                write!(f, "  {}", titles.join(", "))?;
+            }
+            if chapter.hidden {
+                write!(f, " (hidden)")?;
+            }
+            if !chapter.enabled {
+                write!(f, " (disabled)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ChapterList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let editions = self.editions.iter().map(ToString::to_string);
+        write!(f, "{}", editions.collect::<Vec<_>>().join("\n"))
+    }
+}
+
+/// List the editions and chapters of the first Segment of parsed elements.
+///
+/// Chapters without an end time end at the start of the next chapter at
+/// the same level, or at the end of their parent or of the Segment.
+pub fn chapter_list(elements: &[Element]) -> ChapterList {
+    let document = Document::from_elements(elements);
+    let editions = document
+        .chapters
+        .iter()
+        .map(|edition| EditionChapters {
+            uid: edition.uid,
+            hidden: edition.hidden,
+            default: edition.default,
+            ordered: edition.ordered,
+            chapters: document.edition_timeline(edition),
+        })
+        .collect();
+    ChapterList { editions }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_chapter_list() {
+        // Default edition with a chapter from 0 to 10 s titled in English
+        // and French, a nested hidden chapter at 2 s, and a chapter at 10 s
+        // without end
+        const INPUT: &[u8] = &[
+            0x10, 0x43, 0xA7, 0x70, 0xE4, 0x45, 0xB9, 0xE1, 0x45, 0xBC, 0x81, 0x01, 0x45, 0xDB,
+            0x81, 0x01, 0xB6, 0xCA, 0x73, 0xC4, 0x81, 0x01, 0x91, 0x81, 0x00, 0x92, 0x85, 0x02,
+            0x54, 0x0B, 0xE4, 0x00, 0x80, 0x8D, 0x85, 0x85, 0x49, 0x6E, 0x74, 0x72, 0x6F, 0x43,
+            0x7C, 0x83, 0x65, 0x6E, 0x67, 0x80, 0x8E, 0x85, 0x86, 0x44, 0xC3, 0xA9, 0x62, 0x75,
+            0x74, 0x43, 0x7C, 0x83, 0x66, 0x72, 0x65, 0xB6, 0x9B, 0x73, 0xC4, 0x81, 0x02, 0x91,
+            0x84, 0x77, 0x35, 0x94, 0x00, 0x98, 0x81, 0x01, 0x80, 0x8C, 0x85, 0x84, 0x4C, 0x6F,
+            0x67, 0x6F, 0x43, 0x7C, 0x83, 0x65, 0x6E, 0x67, 0xB6, 0x8B, 0x73, 0xC4, 0x81, 0x03,
+            0x91, 0x85, 0x02, 0x54, 0x0B, 0xE4, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            chapter_list(&elements).to_string(),
+            "Edition (uid 1, default)\n  \
+             00:00:00.000 - 00:00:10.000  eng: Intro, fre: Début\n    \
+             00:00:02.000 - 00:00:10.000  eng: Logo (hidden)\n  \
+             00:00:10.000"
+        );
+    }
+}
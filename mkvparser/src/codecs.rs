@@ -0,0 +1,702 @@
+//! Decoding of codec-specific CodecPrivate payloads
+//!
+//! The layout of CodecPrivate depends on the CodecID of the track, so these
+//! payloads are only decoded by the element iterators, which keep track of
+//! the CodecID of the current TrackEntry.
+use std::num::NonZeroUsize;
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{take, Error, IResult};
+
+/// A decoded CodecPrivate payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum CodecPrivate {
+    /// HEVC decoder configuration record (hvcC)
+    Hevc(HevcConfiguration),
+    /// VP9 codec features
+    Vp9(Vp9Configuration),
+    /// Opus identification header
+    Opus(OpusHead),
+    /// FLAC stream information
+    Flac(FlacStreamInfo),
+    /// AAC AudioSpecificConfig
+    Aac(AacConfiguration),
+}
+
+/// Whether CodecPrivate payloads of the given CodecID can be decoded
+pub fn is_supported(codec_id: &str) -> bool {
+    matches!(
+        codec_id,
+        "V_MPEGH/ISO/HEVC" | "V_VP9" | "A_OPUS" | "A_FLAC" | "A_AAC"
+    )
+}
+
+/// Decode a CodecPrivate payload given the CodecID of its track.
+///
+/// Returns `None` for codecs whose CodecPrivate is not supported.
+pub fn parse_codec_private(codec_id: &str, input: &[u8]) -> Result<Option<CodecPrivate>, Error> {
+    let codec_private = match codec_id {
+        "V_MPEGH/ISO/HEVC" => CodecPrivate::Hevc(parse_hevc_configuration(input)?.1),
+        "V_VP9" => CodecPrivate::Vp9(parse_vp9_configuration(input)?.1),
+        "A_OPUS" => CodecPrivate::Opus(parse_opus_head(input)?.1),
+        "A_FLAC" => CodecPrivate::Flac(parse_flac_stream_info(input)?.1),
+        "A_AAC" => CodecPrivate::Aac(parse_aac_configuration(input)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(codec_private))
+}
+
+pub(crate) fn parse_u8(input: &[u8]) -> IResult<&[u8], u8> {
+    let (input, bytes) = take(1usize)(input)?;
+    Ok((input, bytes[0]))
+}
+
+pub(crate) fn parse_u16(input: &[u8]) -> IResult<&[u8], u16> {
+    let (input, bytes) = take(2usize)(input)?;
+    Ok((input, u16::from_be_bytes([bytes[0], bytes[1]])))
+}
+
+pub(crate) fn parse_u32(input: &[u8]) -> IResult<&[u8], u32> {
+    let (input, bytes) = take(4usize)(input)?;
+    Ok((
+        input,
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    ))
+}
+
+/// Summary of an array of NAL units in a [`HevcConfiguration`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HevcNalArray {
+    /// NAL unit type
+    pub nal_unit_type: u8,
+    /// Name of the NAL unit type, such as VPS, SPS or PPS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nal_unit_name: Option<&'static str>,
+    /// Whether all NAL units of this type are in the array
+    pub array_completeness: bool,
+    /// Sizes of the NAL units in the array
+    pub nal_unit_sizes: Vec<usize>,
+}
+
+/// HEVC decoder configuration record, as defined in ISO/IEC 14496-15
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HevcConfiguration {
+    /// Configuration version
+    pub configuration_version: u8,
+    /// general_profile_space
+    pub profile_space: u8,
+    /// general_profile_idc
+    pub profile_idc: u8,
+    /// Name of the profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<&'static str>,
+    /// general_tier_flag, where `true` is the High tier
+    pub high_tier: bool,
+    /// general_level_idc, which is 30 times the level number
+    pub level_idc: u8,
+    /// general_profile_compatibility_flags
+    pub profile_compatibility_flags: u32,
+    /// general_constraint_indicator_flags
+    pub constraint_indicator_flags: u64,
+    /// Chroma format, such as "4:2:0"
+    pub chroma_format: &'static str,
+    /// Bit depth of luma samples
+    pub bit_depth_luma: u8,
+    /// Bit depth of chroma samples
+    pub bit_depth_chroma: u8,
+    /// Size in bytes of the NAL unit length fields in the frames
+    pub length_size: u8,
+    /// Arrays of parameter sets and SEI NAL units
+    pub arrays: Vec<HevcNalArray>,
+}
+
+fn hevc_profile_name(profile_idc: u8) -> Option<&'static str> {
+    Some(match profile_idc {
+        1 => "Main",
+        2 => "Main 10",
+        3 => "Main Still Picture",
+        4 => "Format Range Extensions",
+        5 => "High Throughput",
+        6 => "Multiview Main",
+        7 => "Scalable Main",
+        8 => "3D Main",
+        9 => "Screen Content Coding",
+        10 => "Scalable Format Range Extensions",
+        11 => "High Throughput Screen Content Coding",
+        _ => return None,
+    })
+}
+
+fn hevc_nal_unit_name(nal_unit_type: u8) -> Option<&'static str> {
+    Some(match nal_unit_type {
+        32 => "VPS",
+        33 => "SPS",
+        34 => "PPS",
+        39 => "Prefix SEI",
+        40 => "Suffix SEI",
+        _ => return None,
+    })
+}
+
+fn parse_hevc_nal_array(input: &[u8]) -> IResult<&[u8], HevcNalArray> {
+    let (input, flags) = parse_u8(input)?;
+    let (mut input, num_nalus) = parse_u16(input)?;
+    let mut nal_unit_sizes = Vec::with_capacity(num_nalus as usize);
+    for _ in 0..num_nalus {
+        let (next_input, nal_unit_size) = parse_u16(input)?;
+        let (next_input, _) = take(nal_unit_size as usize)(next_input)?;
+        input = next_input;
+        nal_unit_sizes.push(nal_unit_size as usize);
+    }
+
+    let nal_unit_type = flags & 0x3F;
+    Ok((
+        input,
+        HevcNalArray {
+            nal_unit_type,
+            nal_unit_name: hevc_nal_unit_name(nal_unit_type),
+            array_completeness: flags & 0x80 != 0,
+            nal_unit_sizes,
+        },
+    ))
+}
+
+/// Parse an HEVC decoder configuration record (hvcC)
+pub fn parse_hevc_configuration(input: &[u8]) -> IResult<&[u8], HevcConfiguration> {
+    let (input, fixed) = take(23usize)(input)?;
+    let configuration_version = fixed[0];
+    if configuration_version != 1 {
+        return Err(Error::Parser);
+    }
+    let profile_idc = fixed[1] & 0x1F;
+    let mut constraint_indicator_flags = [0; 8];
+    constraint_indicator_flags[2..].copy_from_slice(&fixed[6..12]);
+
+    let num_arrays = fixed[22];
+    let mut input = input;
+    let mut arrays = Vec::with_capacity(num_arrays as usize);
+    for _ in 0..num_arrays {
+        let (next_input, array) = parse_hevc_nal_array(input)?;
+        input = next_input;
+        arrays.push(array);
+    }
+
+    Ok((
+        input,
+        HevcConfiguration {
+            configuration_version,
+            profile_space: fixed[1] >> 6,
+            profile_idc,
+            profile: hevc_profile_name(profile_idc),
+            high_tier: fixed[1] & 0x20 != 0,
+            level_idc: fixed[12],
+            profile_compatibility_flags: u32::from_be_bytes([
+                fixed[2], fixed[3], fixed[4], fixed[5],
+            ]),
+            constraint_indicator_flags: u64::from_be_bytes(constraint_indicator_flags),
+            chroma_format: match fixed[16] & 0x03 {
+                0 => "4:0:0",
+                1 => "4:2:0",
+                2 => "4:2:2",
+                _ => "4:4:4",
+            },
+            bit_depth_luma: (fixed[17] & 0x07) + 8,
+            bit_depth_chroma: (fixed[18] & 0x07) + 8,
+            length_size: (fixed[21] & 0x03) + 1,
+            arrays,
+        },
+    ))
+}
+
+/// VP9 codec features, as defined in the
+/// [WebM codec mapping](https://www.webmproject.org/docs/container/#vp9-codec-feature-metadata-codecprivate)
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Vp9Configuration {
+    /// Profile
+    pub profile: Option<u8>,
+    /// Level, which is 10 times the level number
+    pub level: Option<u8>,
+    /// Bit depth
+    pub bit_depth: Option<u8>,
+    /// Chroma subsampling
+    pub chroma_subsampling: Option<&'static str>,
+}
+
+/// Parse the VP9 codec feature list
+pub fn parse_vp9_configuration(mut input: &[u8]) -> IResult<&[u8], Vp9Configuration> {
+    let mut configuration = Vp9Configuration::default();
+    while !input.is_empty() {
+        let (next_input, id) = parse_u8(input)?;
+        let (next_input, length) = parse_u8(next_input)?;
+        let (next_input, value) = take(length as usize)(next_input)?;
+        input = next_input;
+
+        // All known features are 1-byte long
+        let Some(&value) = value.first() else {
+            continue;
+        };
+        match id {
+            1 => configuration.profile = Some(value),
+            2 => configuration.level = Some(value),
+            3 => configuration.bit_depth = Some(value),
+            4 => {
+                configuration.chroma_subsampling = Some(match value {
+                    0 => "4:2:0 vertical",
+                    1 => "4:2:0 colocated",
+                    2 => "4:2:2",
+                    3 => "4:4:4",
+                    _ => "reserved",
+                })
+            }
+            _ => (),
+        }
+    }
+    Ok((input, configuration))
+}
+
+/// Opus identification header, as defined in
+/// [RFC 7845](https://www.rfc-editor.org/rfc/rfc7845#section-5.1)
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpusHead {
+    /// Version
+    pub version: u8,
+    /// Number of output channels
+    pub channel_count: u8,
+    /// Number of samples at 48 kHz to discard at the beginning of the stream
+    pub pre_skip: u16,
+    /// Sample rate of the original input, in Hz
+    pub input_sample_rate: u32,
+    /// Output gain, in Q7.8 dB
+    pub output_gain: i16,
+    /// Channel mapping family
+    pub mapping_family: u8,
+    /// Number of Opus streams, for mapping families other than 0
+    pub stream_count: Option<u8>,
+    /// Number of coupled streams, for mapping families other than 0
+    pub coupled_count: Option<u8>,
+    /// Mapping of output channels to decoded channels, for mapping families
+    /// other than 0
+    pub channel_mapping: Option<Vec<u8>>,
+}
+
+impl OpusHead {
+    /// Pre-skip in nanoseconds, which is the expected value of CodecDelay
+    pub fn pre_skip_ns(&self) -> u64 {
+        self.pre_skip as u64 * 1_000_000_000 / 48_000
+    }
+
+    /// Whether the CodecDelay of the track, in nanoseconds, matches the
+    /// pre-skip, allowing for rounding to a sample.
+    pub fn matches_codec_delay(&self, codec_delay: u64) -> bool {
+        codec_delay.abs_diff(self.pre_skip_ns()) < 1_000_000_000 / 48_000
+    }
+}
+
+/// Parse an Opus identification header (OpusHead)
+pub fn parse_opus_head(input: &[u8]) -> IResult<&[u8], OpusHead> {
+    let (input, magic) = take(8usize)(input)?;
+    if magic != b"OpusHead" {
+        return Err(Error::Parser);
+    }
+    let (input, fixed) = take(11usize)(input)?;
+    let channel_count = fixed[1];
+    let mapping_family = fixed[10];
+    let mut opus_head = OpusHead {
+        version: fixed[0],
+        channel_count,
+        pre_skip: u16::from_le_bytes([fixed[2], fixed[3]]),
+        input_sample_rate: u32::from_le_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]),
+        output_gain: i16::from_le_bytes([fixed[8], fixed[9]]),
+        mapping_family,
+        stream_count: None,
+        coupled_count: None,
+        channel_mapping: None,
+    };
+    if mapping_family == 0 {
+        return Ok((input, opus_head));
+    }
+
+    let (input, stream_count) = parse_u8(input)?;
+    let (input, coupled_count) = parse_u8(input)?;
+    let (input, channel_mapping) = take(channel_count as usize)(input)?;
+    opus_head.stream_count = Some(stream_count);
+    opus_head.coupled_count = Some(coupled_count);
+    opus_head.channel_mapping = Some(channel_mapping.to_vec());
+    Ok((input, opus_head))
+}
+
+/// FLAC STREAMINFO metadata block, as defined in
+/// [RFC 9639](https://www.rfc-editor.org/rfc/rfc9639#section-8.2)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlacStreamInfo {
+    /// Minimum block size, in samples
+    pub min_block_size: u16,
+    /// Maximum block size, in samples
+    pub max_block_size: u16,
+    /// Minimum frame size in bytes, or 0 if unknown
+    pub min_frame_size: u32,
+    /// Maximum frame size in bytes, or 0 if unknown
+    pub max_frame_size: u32,
+    /// Sample rate, in Hz
+    pub sample_rate: u32,
+    /// Number of channels
+    pub channels: u8,
+    /// Bits per sample
+    pub bits_per_sample: u8,
+    /// Total number of samples per channel, or 0 if unknown
+    pub total_samples: u64,
+    /// MD5 checksum of the unencoded audio data, as a hex string
+    pub md5: String,
+}
+
+/// Parse the fLaC marker followed by the STREAMINFO metadata block
+pub fn parse_flac_stream_info(input: &[u8]) -> IResult<&[u8], FlacStreamInfo> {
+    let (input, marker) = take(4usize)(input)?;
+    if marker != b"fLaC" {
+        return Err(Error::Parser);
+    }
+    // STREAMINFO must be the first metadata block and is 34 bytes long
+    let (input, block_header) = take(4usize)(input)?;
+    if block_header[0] & 0x7F != 0 || block_header[1..] != [0, 0, 34] {
+        return Err(Error::Parser);
+    }
+    let (input, block) = take(34usize)(input)?;
+
+    let u24 = |bytes: &[u8]| u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+    let packed = u64::from_be_bytes(block[10..18].try_into().unwrap());
+    Ok((
+        input,
+        FlacStreamInfo {
+            min_block_size: u16::from_be_bytes([block[0], block[1]]),
+            max_block_size: u16::from_be_bytes([block[2], block[3]]),
+            min_frame_size: u24(&block[4..7]),
+            max_frame_size: u24(&block[7..10]),
+            sample_rate: (packed >> 44) as u32,
+            channels: ((packed >> 41) & 0x07) as u8 + 1,
+            bits_per_sample: ((packed >> 36) & 0x1F) as u8 + 1,
+            total_samples: packed & 0xF_FFFF_FFFF,
+            md5: block[18..].iter().map(|b| format!("{:02x}", b)).collect(),
+        },
+    ))
+}
+
+// Reads big-endian bit fields
+pub(crate) struct BitReader<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Self { input, position: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.input.len() * 8 - self.position
+    }
+
+    pub(crate) fn read(&mut self, num_bits: usize) -> Result<u32, Error> {
+        if num_bits > self.remaining() {
+            let needed = (num_bits - self.remaining()).div_ceil(8);
+            return Err(Error::NeedData(NonZeroUsize::new(needed)));
+        }
+        let mut value = 0;
+        for _ in 0..num_bits {
+            let bit = (self.input[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn read_flag(&mut self) -> Result<bool, Error> {
+        Ok(self.read(1)? == 1)
+    }
+}
+
+/// AAC AudioSpecificConfig, as defined in ISO/IEC 14496-3
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AacConfiguration {
+    /// Audio object type of the core decoder
+    pub audio_object_type: u8,
+    /// Name of the audio object type
+    pub audio_object_type_name: Option<&'static str>,
+    /// Sampling frequency of the core decoder, in Hz
+    pub sampling_frequency: Option<u32>,
+    /// Channel configuration, where 0 means it's defined in the bitstream
+    pub channel_configuration: u8,
+    /// Whether Spectral Band Replication is signalled
+    pub sbr: bool,
+    /// Whether Parametric Stereo is signalled
+    pub ps: bool,
+    /// Output sampling frequency when SBR is used, in Hz
+    pub extension_sampling_frequency: Option<u32>,
+}
+
+fn aac_audio_object_type(reader: &mut BitReader) -> Result<u8, Error> {
+    let audio_object_type = reader.read(5)? as u8;
+    if audio_object_type == 31 {
+        Ok(32 + reader.read(6)? as u8)
+    } else {
+        Ok(audio_object_type)
+    }
+}
+
+fn aac_sampling_frequency(reader: &mut BitReader) -> Result<Option<u32>, Error> {
+    const SAMPLING_FREQUENCIES: [u32; 13] = [
+        96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+    ];
+    let index = reader.read(4)?;
+    if index == 0xF {
+        return Ok(Some(reader.read(24)?));
+    }
+    Ok(SAMPLING_FREQUENCIES.get(index as usize).copied())
+}
+
+fn aac_audio_object_type_name(audio_object_type: u8) -> Option<&'static str> {
+    Some(match audio_object_type {
+        1 => "AAC Main",
+        2 => "AAC LC",
+        3 => "AAC SSR",
+        4 => "AAC LTP",
+        5 => "SBR",
+        6 => "AAC Scalable",
+        17 => "ER AAC LC",
+        19 => "ER AAC LTP",
+        20 => "ER AAC Scalable",
+        23 => "ER AAC LD",
+        29 => "PS",
+        39 => "ER AAC ELD",
+        42 => "USAC",
+        _ => return None,
+    })
+}
+
+/// Parse an AAC AudioSpecificConfig
+///
+/// Both explicit hierarchical signalling of SBR/PS, and backward-compatible
+/// signalling with sync extensions are supported.
+pub fn parse_aac_configuration(input: &[u8]) -> Result<AacConfiguration, Error> {
+    let mut reader = BitReader::new(input);
+    let mut audio_object_type = aac_audio_object_type(&mut reader)?;
+    let sampling_frequency = aac_sampling_frequency(&mut reader)?;
+    let channel_configuration = reader.read(4)? as u8;
+
+    let mut sbr = false;
+    let mut ps = false;
+    let mut extension_sampling_frequency = None;
+    if audio_object_type == 5 || audio_object_type == 29 {
+        sbr = true;
+        ps = audio_object_type == 29;
+        extension_sampling_frequency = aac_sampling_frequency(&mut reader)?;
+        audio_object_type = aac_audio_object_type(&mut reader)?;
+    }
+
+    // GASpecificConfig, which is followed by sync extensions
+    if matches!(audio_object_type, 1..=4 | 6 | 7 | 17 | 19..=23) {
+        let _frame_length_flag = reader.read_flag()?;
+        if reader.read_flag()? {
+            let _core_coder_delay = reader.read(14)?;
+        }
+        let _extension_flag = reader.read_flag()?;
+
+        if !sbr && reader.remaining() >= 16 && reader.read(11)? == 0x2B7 {
+            let extension_audio_object_type = aac_audio_object_type(&mut reader)?;
+            if extension_audio_object_type == 5 {
+                sbr = reader.read_flag()?;
+                if sbr {
+                    extension_sampling_frequency = aac_sampling_frequency(&mut reader)?;
+                    if reader.remaining() >= 12 && reader.read(11)? == 0x548 {
+                        ps = reader.read_flag()?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(AacConfiguration {
+        audio_object_type,
+        audio_object_type_name: aac_audio_object_type_name(audio_object_type),
+        sampling_frequency,
+        channel_configuration,
+        sbr,
+        ps,
+        extension_sampling_frequency,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hevc_configuration() {
+        const HVCC: &[u8] = &[
+            0x01, 0x01, 0x60, 0x00, 0x00, 0x00, 0x90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5D, 0xF0,
+            0x00, 0xFC, 0xFD, 0xF8, 0xF8, 0x00, 0x00, 0x0F, 0x02, 0xA0, 0x00, 0x01, 0x00, 0x03,
+            0x40, 0x01, 0x0C, 0xA1, 0x00, 0x02, 0x00, 0x02, 0x42, 0x01, 0x00, 0x01, 0x44,
+        ];
+        let codec_private = parse_codec_private("V_MPEGH/ISO/HEVC", HVCC)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            codec_private,
+            CodecPrivate::Hevc(HevcConfiguration {
+                configuration_version: 1,
+                profile_space: 0,
+                profile_idc: 1,
+                profile: Some("Main"),
+                high_tier: false,
+                level_idc: 93,
+                profile_compatibility_flags: 0x60000000,
+                constraint_indicator_flags: 0x900000000000,
+                chroma_format: "4:2:0",
+                bit_depth_luma: 8,
+                bit_depth_chroma: 8,
+                length_size: 4,
+                arrays: vec![
+                    HevcNalArray {
+                        nal_unit_type: 32,
+                        nal_unit_name: Some("VPS"),
+                        array_completeness: true,
+                        nal_unit_sizes: vec![3],
+                    },
+                    HevcNalArray {
+                        nal_unit_type: 33,
+                        nal_unit_name: Some("SPS"),
+                        array_completeness: true,
+                        nal_unit_sizes: vec![2, 1],
+                    },
+                ],
+            })
+        );
+
+        assert_eq!(
+            parse_codec_private("V_MPEGH/ISO/HEVC", &HVCC[..30]),
+            Err(Error::NeedData(NonZeroUsize::new(1)))
+        );
+        assert_eq!(parse_codec_private("V_THEORA", HVCC), Ok(None));
+    }
+
+    #[test]
+    fn test_parse_vp9_configuration() {
+        const VP9: &[u8] = &[
+            0x01, 0x01, 0x00, 0x02, 0x01, 0x0A, 0x03, 0x01, 0x08, 0x04, 0x01, 0x01,
+        ];
+        assert_eq!(
+            parse_codec_private("V_VP9", VP9),
+            Ok(Some(CodecPrivate::Vp9(Vp9Configuration {
+                profile: Some(0),
+                level: Some(10),
+                bit_depth: Some(8),
+                chroma_subsampling: Some("4:2:0 colocated"),
+            })))
+        );
+        assert_eq!(
+            parse_codec_private("V_VP9", &VP9[..4]),
+            Err(Error::NeedData(NonZeroUsize::new(1)))
+        );
+    }
+
+    #[test]
+    fn test_parse_opus_head() {
+        const OPUS_HEAD: &[u8] = &[
+            0x4F, 0x70, 0x75, 0x73, 0x48, 0x65, 0x61, 0x64, 0x01, 0x02, 0x38, 0x01, 0x80, 0xBB,
+            0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let Ok(Some(CodecPrivate::Opus(opus_head))) = parse_codec_private("A_OPUS", OPUS_HEAD)
+        else {
+            panic!("expected an OpusHead");
+        };
+        assert_eq!(
+            opus_head,
+            OpusHead {
+                version: 1,
+                channel_count: 2,
+                pre_skip: 312,
+                input_sample_rate: 48000,
+                output_gain: 0,
+                mapping_family: 0,
+                stream_count: None,
+                coupled_count: None,
+                channel_mapping: None,
+            }
+        );
+        assert!(opus_head.matches_codec_delay(6500000));
+        assert!(!opus_head.matches_codec_delay(0));
+
+        assert_eq!(
+            parse_codec_private("A_OPUS", &OPUS_HEAD[1..]),
+            Err(Error::Parser)
+        );
+    }
+
+    #[test]
+    fn test_parse_flac_stream_info() {
+        let mut flac = b"fLaC".to_vec();
+        flac.extend_from_slice(&[0x80, 0x00, 0x00, 0x22]);
+        flac.extend_from_slice(&[
+            0x10, 0x00, 0x10, 0x00, 0x00, 0x00, 0x0E, 0x00, 0x34, 0x5C, 0x0A, 0xC4, 0x42, 0xF0,
+            0x00, 0x00, 0xAC, 0x44,
+        ]);
+        flac.extend_from_slice(&[0xAB; 16]);
+        assert_eq!(
+            parse_codec_private("A_FLAC", &flac),
+            Ok(Some(CodecPrivate::Flac(FlacStreamInfo {
+                min_block_size: 4096,
+                max_block_size: 4096,
+                min_frame_size: 14,
+                max_frame_size: 13404,
+                sample_rate: 44100,
+                channels: 2,
+                bits_per_sample: 16,
+                total_samples: 44100,
+                md5: "ab".repeat(16),
+            })))
+        );
+    }
+
+    #[test]
+    fn test_parse_aac_configuration() {
+        // AAC LC, 48 kHz, stereo
+        assert_eq!(
+            parse_codec_private("A_AAC", &[0x11, 0x90]),
+            Ok(Some(CodecPrivate::Aac(AacConfiguration {
+                audio_object_type: 2,
+                audio_object_type_name: Some("AAC LC"),
+                sampling_frequency: Some(48000),
+                channel_configuration: 2,
+                sbr: false,
+                ps: false,
+                extension_sampling_frequency: None,
+            })))
+        );
+
+        // HE-AAC v2 with explicit hierarchical signalling, 24 kHz core
+        let Ok(Some(CodecPrivate::Aac(configuration))) =
+            parse_codec_private("A_AAC", &[0xEB, 0x09, 0x88, 0x00])
+        else {
+            panic!("expected an AudioSpecificConfig");
+        };
+        assert_eq!(configuration.audio_object_type, 2);
+        assert_eq!(configuration.sampling_frequency, Some(24000));
+        assert!(configuration.sbr && configuration.ps);
+        assert_eq!(configuration.extension_sampling_frequency, Some(48000));
+
+        // HE-AAC with backward-compatible signalling
+        let Ok(Some(CodecPrivate::Aac(configuration))) =
+            parse_codec_private("A_AAC", &[0x13, 0x10, 0x56, 0xE5, 0x98])
+        else {
+            panic!("expected an AudioSpecificConfig");
+        };
+        assert_eq!(configuration.sampling_frequency, Some(24000));
+        assert!(configuration.sbr && !configuration.ps);
+        assert_eq!(configuration.extension_sampling_frequency, Some(48000));
+    }
+}
@@ -0,0 +1,176 @@
+//! CRC-32 checks of Master elements
+//!
+//! A CRC-32 element, when present, is the first child of a Master element
+//! and holds the checksum of the rest of the body of its parent, stored in
+//! little-endian order.
+use std::{
+    fmt,
+    io::{Read, Seek, SeekFrom},
+};
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{elements::Id, Body, Element};
+
+// Lookup table of the IEEE 802.3 polynomial in reversed bit order
+const TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+// Update a running checksum, without the initial and final inversions
+fn update(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc = TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+/// Compute the CRC-32 of data as used by EBML, i.e. the IEEE 802.3 one
+pub fn crc32(data: &[u8]) -> u32 {
+    !update(!0, data)
+}
+
+/// A Master element whose CRC-32 doesn't match its body, see [`check_crcs`]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrcMismatch {
+    /// The Master element
+    pub id: Id,
+    /// Path of the Master element, if the elements were parsed with paths
+    pub path: Option<String>,
+    /// Position of the Master element in the input
+    pub position: usize,
+    /// Checksum stored in the CRC-32 element
+    pub stored: u32,
+    /// Checksum of the body of the Master element
+    pub computed: u32,
+}
+
+impl fmt::Display for CrcMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{path}")?,
+            None => write!(f, "{}", self.id)?,
+        }
+        write!(
+            f,
+            " at offset {:#x} has a CRC-32 of {:08x}, but its body has {:08x}",
+            self.position, self.stored, self.computed
+        )
+    }
+}
+
+/// Check the CRC-32 elements of parsed elements against the input they were
+/// parsed from, returning the Master elements whose checksums don't match.
+///
+/// Elements must be in the order they were parsed, with their positions set.
+/// CRC-32 elements that aren't the first child of a Master element with a
+/// known size are ignored.
+pub fn check_crcs<R: Read + Seek>(
+    reader: &mut R,
+    elements: &[Element],
+) -> std::io::Result<Vec<CrcMismatch>> {
+    let mut mismatches = Vec::new();
+    for pair in elements.windows(2) {
+        let [parent, crc] = pair else { unreachable!() };
+        if crc.header.id != Id::Crc32 || !matches!(parent.body, Body::Master) {
+            continue;
+        }
+        let (parent, crc) = (&parent.header, &crc.header);
+        let (Some(position), Some(size), Some(crc_position)) =
+            (parent.position, parent.size, crc.position)
+        else {
+            continue;
+        };
+        let crc_body = crc_position + crc.header_size;
+        let is_first_child = position + parent.header_size == crc_position;
+        if !is_first_child || crc.body_size != Some(4) || crc_body + 4 > position + size {
+            continue;
+        }
+
+        let mut stored = [0; 4];
+        reader.seek(SeekFrom::Start(crc_body as u64))?;
+        reader.read_exact(&mut stored)?;
+        let mut remaining = (position + size - crc_body - 4) as u64;
+        let mut checksum = !0;
+        let mut buffer = vec![0; 64 * 1024];
+        while remaining > 0 {
+            let length = remaining.min(buffer.len() as u64) as usize;
+            reader.read_exact(&mut buffer[..length])?;
+            checksum = update(checksum, &buffer[..length]);
+            remaining -= length as u64;
+        }
+
+        let stored = u32::from_le_bytes(stored);
+        let computed = !checksum;
+        if stored != computed {
+            mismatches.push(CrcMismatch {
+                id: parent.id.clone(),
+                path: parent.path.clone(),
+                position,
+                stored,
+                computed,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, ParserOptions, Result};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_check_crcs() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+
+        // Info with a valid CRC-32, and Tracks with a CRC-32 off by one
+        const INPUT: &[u8] = &[
+            0x15, 0x49, 0xA9, 0x66, 0x8D, 0xBF, 0x84, 0x60, 0x86, 0x1E, 0x94, 0x2A, 0xD7, 0xB1,
+            0x83, 0x0F, 0x42, 0x40, 0x16, 0x54, 0xAE, 0x6B, 0x8B, 0xBF, 0x84, 0x17, 0x75, 0x80,
+            0x2B, 0xAE, 0x83, 0xD7, 0x81, 0x01,
+        ];
+        let options = ParserOptions {
+            element_paths: true,
+            ..ParserOptions::default()
+        };
+        let elements = ElementIterator::new(INPUT)
+            .with_options(options)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let mismatches = check_crcs(&mut Cursor::new(INPUT), &elements).unwrap();
+        assert_eq!(
+            mismatches,
+            [CrcMismatch {
+                id: Id::Tracks,
+                path: Some("\\Tracks".to_string()),
+                position: 18,
+                stored: 0x2B807517,
+                computed: 0x2B807516,
+            }]
+        );
+        assert_eq!(
+            mismatches[0].to_string(),
+            "\\Tracks at offset 0x12 has a CRC-32 of 2b807517, but its body has 2b807516"
+        );
+    }
+}
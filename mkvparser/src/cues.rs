@@ -0,0 +1,173 @@
+//! Table of the cue points of a file, with their positions resolved to
+//! absolute offsets and checked against the Clusters actually found
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{elements::Id, format_time, model::Document, table::write_table, Element};
+
+/// A track position of a cue point, see [`cue_table`]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CueEntry {
+    /// Time of the cue point in nanoseconds
+    pub time_ns: i64,
+    /// Time of the cue point as HH:MM:SS.mmm
+    pub time: String,
+    /// Track of the position
+    pub track: Option<u64>,
+    /// Absolute position of the Cluster, if the position of the Segment is
+    /// known
+    pub cluster_position: Option<u64>,
+    /// Position of the block, relative to the Cluster data
+    pub relative_position: Option<u64>,
+    /// Absolute position of the block, if the header of its Cluster was
+    /// found
+    pub block_position: Option<u64>,
+    /// Duration of the block in nanoseconds
+    pub duration_ns: Option<i64>,
+    /// Whether a Cluster starts at the cluster position
+    pub cluster_found: bool,
+}
+
+/// Cue points of a file, one entry per track position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CueTable {
+    /// Entries in the order of the Cues
+    pub entries: Vec<CueEntry>,
+}
+
+// An optional cell, empty if unknown
+fn cell<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+impl fmt::Display for CueTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .entries
+            .iter()
+            .map(|entry| {
+                vec![
+                    entry.time.clone(),
+                    cell(entry.track),
+                    cell(entry.cluster_position),
+                    cell(entry.relative_position),
+                    cell(entry.block_position),
+                    cell(entry.duration_ns.map(format_time)),
+                    if entry.cluster_found { "ok" } else { "missing" }.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        write_table(
+            f,
+            &[
+                "time", "track", "cluster", "relative", "block", "duration", "status",
+            ],
+            &rows,
+        )
+    }
+}
+
+/// List the cue points of the first Segment of parsed elements.
+///
+/// Elements must have positions. Cluster positions are checked against the
+/// Clusters found among the elements, so that Cluster headers are enough,
+/// as parsed with [`ParserOptions::skip_clusters`](crate::ParserOptions).
+pub fn cue_table(elements: &[Element]) -> CueTable {
+    let document = Document::from_elements(elements);
+    let timestamp_scale = document.timestamp_scale();
+    let scaled = |ticks: u64| ticks.saturating_mul(timestamp_scale).min(i64::MAX as u64) as i64;
+    // Header sizes of Clusters by position
+    let clusters = elements
+        .iter()
+        .filter(|element| element.header.id == Id::Cluster)
+        .filter_map(|element| Some((element.header.position? as u64, element.header.header_size)))
+        .collect::<BTreeMap<_, _>>();
+    let (document, clusters) = (&document, &clusters);
+    let entries = document
+        .cues
+        .iter()
+        .flat_map(|cue_point| {
+            let time_ns = scaled(cue_point.time);
+            cue_point.track_positions.iter().map(move |positions| {
+                let cluster_position = document
+                    .segment_data_position
+                    .zip(positions.cluster_position)
+                    .map(|(segment_data_position, position)| segment_data_position + position);
+                let header_size = cluster_position.and_then(|position| clusters.get(&position));
+                CueEntry {
+                    time_ns,
+                    time: format_time(time_ns),
+                    track: positions.track,
+                    cluster_position,
+                    relative_position: positions.relative_position,
+                    block_position: cluster_position
+                        .zip(header_size)
+                        .zip(positions.relative_position)
+                        .map(|((position, header_size), relative_position)| {
+                            position + *header_size as u64 + relative_position
+                        }),
+                    duration_ns: positions.duration.map(scaled),
+                    cluster_found: header_size.is_some(),
+                }
+            })
+        })
+        .collect();
+    CueTable { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_cue_table() {
+        // Segment with Cues pointing at the Cluster following them at time
+        // 0, with a relative position of 3, and at a missing Cluster at
+        // time 1000
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xAB, 0x1C, 0x53, 0xBB, 0x6B, 0x9E, 0xBB, 0x8E, 0xB3, 0x81,
+            0x00, 0xB7, 0x89, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x23, 0xF0, 0x81, 0x03, 0xBB, 0x8C,
+            0xB3, 0x82, 0x03, 0xE8, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x40, 0x1F, 0x43,
+            0xB6, 0x75, 0x83, 0xE7, 0x81, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let table = cue_table(&elements);
+        assert_eq!(
+            table.entries,
+            [
+                CueEntry {
+                    time_ns: 0,
+                    time: "00:00:00.000".into(),
+                    track: Some(1),
+                    cluster_position: Some(40),
+                    relative_position: Some(3),
+                    block_position: Some(48),
+                    duration_ns: None,
+                    cluster_found: true,
+                },
+                CueEntry {
+                    time_ns: 1_000_000_000,
+                    time: "00:00:01.000".into(),
+                    track: Some(1),
+                    cluster_position: Some(69),
+                    relative_position: None,
+                    block_position: None,
+                    duration_ns: None,
+                    cluster_found: false,
+                },
+            ]
+        );
+        assert_eq!(
+            table.to_string(),
+            "time          track  cluster  relative  block  duration  status\n\
+             00:00:00.000  1      40       3         48               ok\n\
+             00:00:01.000  1      69                                  missing"
+        );
+    }
+}
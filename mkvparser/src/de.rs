@@ -0,0 +1,335 @@
+//! Deserialization of parsed elements
+//!
+//! Elements can be read back from a previous dump, e.g. in JSON or YAML, to
+//! be transformed and written again with the [`writer`](crate::writer)
+//! module. Values are interpreted according to the type of their element,
+//! and the fields that aren't serialized are recomputed.
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, de::Error as _, Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::{
+    elements::{Id, Type},
+    enumerations::Enumeration,
+    languages::element_language,
+    tree::{ElementTree, MasterElement},
+    Binary, Block, Body, Element, Header, SimpleBlock, StreamBoundary, Truncated, Unsigned,
+};
+
+// An element as serialized either in a series of elements or in a tree
+#[derive(Deserialize)]
+struct SerializedElement {
+    #[serde(flatten)]
+    header: Header,
+    #[serde(default)]
+    value: Value,
+    children: Option<Vec<ElementTree>>,
+}
+
+impl SerializedElement {
+    fn into_element(self) -> Result<Element, String> {
+        let mut header = self.header;
+        header.body_size = header
+            .size
+            .map(|size| size.saturating_sub(header.header_size));
+        let body = body_from_value(&header.id, self.value)?;
+        let mut element = Element { header, body };
+        element.header.language = element_language(&element);
+        Ok(element)
+    }
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, String> {
+    T::deserialize(value).map_err(|e| e.to_string())
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(index, c)| match index {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn binary_from_value(id: &Id, value: Value) -> Result<Binary, String> {
+    let binary = match (id, value) {
+        (Id::Corrupted, Value::Null) => Binary::Corrupted,
+        (Id::Truncated, value) => Binary::Truncated(from_value::<Truncated>(value)?),
+        (Id::StreamBoundary, value) => Binary::StreamBoundary(from_value::<StreamBoundary>(value)?),
+        (Id::SimpleBlock, value @ Value::Object(_)) => {
+            Binary::SimpleBlock(from_value::<SimpleBlock>(value)?)
+        }
+        (Id::Block, value @ Value::Object(_)) => Binary::Block(from_value::<Block>(value)?),
+        (Id::ChapProcessData, value @ Value::Array(_)) => Binary::DvdCommands(from_value(value)?),
+        (Id::SeekId, Value::String(name)) => {
+            Binary::SeekId(name.parse().map_err(|e: crate::Error| e.to_string())?)
+        }
+        (Id::Void, Value::String(padding)) => Binary::Void(
+            padding
+                .strip_suffix(" bytes of padding")
+                .and_then(|size| size.parse().ok())
+                .ok_or_else(|| format!("invalid padding of {id}: {padding}"))?,
+        ),
+        (_, Value::String(uuid)) if is_uuid(&uuid) => Binary::Uuid(uuid),
+        // Previews are either "[xx xx ...]" or "N bytes", which aren't
+        // valid base64
+        (_, Value::String(preview))
+            if preview.starts_with('[') || BASE64_STANDARD.decode(&preview).is_err() =>
+        {
+            Binary::Standard(preview)
+        }
+        (_, Value::String(encoded)) => Binary::Base64(encoded),
+        // Decoded payloads, such as CodecPrivate, don't keep enough of the
+        // original data to be read back
+        (_, value) => return Err(format!("cannot read back the payload of {id}: {value}")),
+    };
+    Ok(binary)
+}
+
+fn body_from_value(id: &Id, value: Value) -> Result<Body, String> {
+    let invalid = |value: &Value| format!("invalid value for {id}: {value}");
+    let body = match (id.get_type(), value) {
+        (Type::Master, Value::Null) => Body::Master,
+        (Type::Unsigned, Value::Number(number)) => Body::Unsigned(Unsigned::new(
+            id,
+            number.as_u64().ok_or_else(|| invalid(&number.into()))?,
+        )),
+        (Type::Unsigned, Value::String(label)) => Body::Unsigned(Unsigned::Enumeration(
+            Enumeration::from_label(id, &label).ok_or_else(|| invalid(&label.into()))?,
+        )),
+        (Type::Signed, Value::Number(number)) => {
+            Body::Signed(number.as_i64().ok_or_else(|| invalid(&number.into()))?)
+        }
+        (Type::Float, Value::Number(number)) => {
+            Body::Float(number.as_f64().ok_or_else(|| invalid(&number.into()))?)
+        }
+        (Type::String, Value::String(value)) => Body::String(value),
+        (Type::Utf8, Value::String(value)) => Body::Utf8(value),
+        (Type::Date, value) => Body::Date(from_value::<DateTime<Utc>>(value)?),
+        (Type::Binary, value) => Body::Binary(binary_from_value(id, value)?),
+        (_, value) => return Err(invalid(&value)),
+    };
+    Ok(body)
+}
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        SerializedElement::deserialize(d)?
+            .into_element()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for ElementTree {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let mut serialized = SerializedElement::deserialize(d)?;
+        let children = serialized.children.take();
+        let element = serialized.into_element().map_err(D::Error::custom)?;
+        Ok(match children {
+            Some(children) => ElementTree::Master(MasterElement {
+                header: element.header,
+                children,
+            }),
+            None => ElementTree::Normal(element),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for MasterElement {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        match ElementTree::deserialize(d)? {
+            ElementTree::Master(master) => Ok(master),
+            ElementTree::Normal(element) => Err(D::Error::custom(format!(
+                "{} has no children",
+                element.header.id
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    /// Without the ID of its element, the type of a body is guessed from
+    /// its value, so only Master, integer, float and string bodies can be
+    /// read back. Deserialize an [`Element`] instead to get the exact type.
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let body = match Value::deserialize(d)? {
+            Value::Null => Body::Master,
+            Value::Number(number) => match (number.as_u64(), number.as_i64(), number.as_f64()) {
+                (Some(value), _, _) => Body::Unsigned(Unsigned::Standard(value)),
+                (None, Some(value), _) => Body::Signed(value),
+                (None, None, Some(value)) => Body::Float(value),
+                (None, None, None) => {
+                    return Err(D::Error::custom(format!("invalid body: {number}")))
+                }
+            },
+            Value::String(value) => Body::String(value),
+            value => return Err(D::Error::custom(format!("invalid body: {value}"))),
+        };
+        Ok(body)
+    }
+}
+
+// Sizes are serialized as a number, or as "Unknown"
+pub(crate) fn deserialize_size<'de, D: Deserializer<'de>>(d: D) -> Result<Option<usize>, D::Error> {
+    match Value::deserialize(d)? {
+        Value::String(size) if size == "Unknown" => Ok(None),
+        Value::Number(size) => size
+            .as_u64()
+            .map(|size| Some(size as usize))
+            .ok_or_else(|| D::Error::custom(format!("invalid size: {size}"))),
+        size => Err(D::Error::custom(format!("invalid size: {size}"))),
+    }
+}
+
+pub(crate) fn deserialize_milliseconds<'de, D: Deserializer<'de>>(d: D) -> Result<i64, D::Error> {
+    let milliseconds = f64::deserialize(d)?;
+    Ok((milliseconds * 1e6).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enumerations::TrackType,
+        iter::ElementIterator,
+        tree::build_element_trees,
+        writer::{write_element, write_element_tree},
+        DiscardPadding,
+    };
+
+    // EBML header, then a Segment with SegmentUUID, SeekHead, Void and
+    // Tracks with a TrackEntry with TrackType and Language
+    const INPUT: &[u8] = &[
+        0x1A, 0x45, 0xDF, 0xA3, 0x87, 0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D, 0x18, 0x53, 0x80,
+        0x67, 0xB7, 0x73, 0xA4, 0x90, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09,
+        0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x11, 0x4D, 0x9B, 0x74, 0x8A, 0x4D, 0xBB, 0x87, 0x53,
+        0xAB, 0x84, 0x16, 0x54, 0xAE, 0x6B, 0xEC, 0x82, 0x00, 0x00, 0x16, 0x54, 0xAE, 0x6B, 0x8C,
+        0xAE, 0x8A, 0x83, 0x81, 0x02, 0x22, 0xB5, 0x9C, 0x83, 0x65, 0x6E, 0x67,
+    ];
+
+    // Cluster with a SimpleBlock and a BlockGroup with a Block and
+    // DiscardPadding
+    const CLUSTER: &[u8] = &[
+        0x1F, 0x43, 0xB6, 0x75, 0x95, 0xE7, 0x81, 0x0A, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80, 0xA0,
+        0x8A, 0xA1, 0x84, 0x81, 0x00, 0x01, 0x00, 0x75, 0xA2, 0x81, 0xFF,
+    ];
+
+    fn parse(input: &[u8]) -> Vec<Element> {
+        ElementIterator::new(input)
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_elements() {
+        let elements = parse(INPUT);
+        let yaml = serde_yaml::to_string(&elements).unwrap();
+        let deserialized: Vec<Element> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(deserialized, elements);
+
+        let mut output = Vec::new();
+        for element in &deserialized {
+            write_element(&mut output, element).unwrap();
+        }
+        assert_eq!(output, INPUT);
+
+        // Blocks are read back, but can't be written again. The frame sizes
+        // of unlaced blocks are only known from parsing their body.
+        let mut blocks = parse(CLUSTER);
+        for element in &mut blocks {
+            match &mut element.body {
+                Body::Binary(Binary::SimpleBlock(block)) => block.frame_size = None,
+                Body::Binary(Binary::Block(block)) => block.frame_size = None,
+                _ => (),
+            }
+        }
+        let json = serde_json::to_string(&blocks).unwrap();
+        let deserialized: Vec<Element> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, blocks);
+    }
+
+    #[test]
+    fn test_deserialize_element_trees() {
+        let trees = build_element_trees(&parse(INPUT));
+        let yaml = serde_yaml::to_string(&trees).unwrap();
+        let deserialized: Vec<ElementTree> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(deserialized, trees);
+
+        let mut output = Vec::new();
+        for tree in &deserialized {
+            write_element_tree(&mut output, tree).unwrap();
+        }
+        assert_eq!(output, INPUT);
+    }
+
+    #[test]
+    fn test_deserialize_values() {
+        let element: Element = serde_yaml::from_str(
+            "id: TrackType\nheader_size: 1\nsize: 3\nvalue: audio\nimplied: true",
+        )
+        .unwrap();
+        assert_eq!(
+            element,
+            Element {
+                header: Header {
+                    implied: true,
+                    ..Header::new(Id::TrackType, 1, 2)
+                },
+                body: Body::Unsigned(Unsigned::Enumeration(Enumeration::TrackType(
+                    TrackType::Audio
+                ))),
+            }
+        );
+
+        let element: Element = serde_yaml::from_str(
+            "id: DiscardPadding\nheader_size: 3\nsize: 7\nvalue: -6500000\n\
+             discard_padding:\n  milliseconds: -6.5\n  samples: -312",
+        )
+        .unwrap();
+        assert_eq!(
+            element.header.discard_padding,
+            Some(DiscardPadding {
+                duration_ns: -6_500_000,
+                samples: Some(-312)
+            })
+        );
+
+        let element: Element =
+            serde_yaml::from_str("id: Segment\nheader_size: 12\nsize: Unknown\nvalue: null")
+                .unwrap();
+        assert_eq!(element.header.size, None);
+        assert_eq!(element.header.body_size, None);
+
+        let element: Element =
+            serde_yaml::from_str("id: CodecPrivate\nheader_size: 3\nsize: 5\nvalue: 3q0=").unwrap();
+        assert_eq!(element.body, Body::Binary(Binary::Base64("3q0=".into())));
+
+        assert!(serde_yaml::from_str::<Element>(
+            "id: CodecPrivate\nheader_size: 3\nsize: 5\nvalue:\n  profile: 1"
+        )
+        .is_err());
+        assert!(serde_yaml::from_str::<Element>(
+            "id: TrackType\nheader_size: 1\nsize: 3\nvalue: video game"
+        )
+        .is_err());
+
+        assert_eq!(
+            serde_yaml::from_str::<Body>("5").unwrap(),
+            Body::Unsigned(Unsigned::Standard(5))
+        );
+        assert_eq!(
+            serde_yaml::from_str::<Body>("-5").unwrap(),
+            Body::Signed(-5)
+        );
+        assert_eq!(
+            serde_yaml::from_str::<Body>("0.5").unwrap(),
+            Body::Float(0.5)
+        );
+        assert_eq!(serde_yaml::from_str::<Body>("~").unwrap(), Body::Master);
+        assert_eq!(
+            serde_yaml::from_str::<Body>("webm").unwrap(),
+            Body::String("webm".into())
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Non-fatal diagnostics about parsed elements
+//!
+//! Unlike errors, diagnostics don't stop parsing: they point out elements
+//! that could be parsed but are likely to cause issues in players.
+use std::{fmt, io::Read};
+
+use serde::Serialize;
+
+use crate::{
+    elements::Id,
+    iter::{ElementIterator, OpenMasters},
+    validate::out_of_range,
+    Body, Element, Result,
+};
+
+/// A warning about a parsed element
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Diagnostic {
+    /// An element that is no longer part of the latest version of the DocType
+    DeprecatedElement {
+        /// The deprecated element
+        id: Id,
+        /// Position of the element in the input
+        position: Option<usize>,
+    },
+    /// An element that extends past the end of the Master element it is in
+    OverflowingElement {
+        /// The overflowing element
+        id: Id,
+        /// Position of the element in the input
+        position: Option<usize>,
+        /// The Master element it is in
+        parent: Id,
+        /// Number of bytes past the end of the parent
+        overflow: usize,
+    },
+    /// A numeric value out of the range allowed by the EBML Schema
+    OutOfRange {
+        /// The element with the invalid value
+        id: Id,
+        /// Position of the element in the input
+        position: Option<usize>,
+        /// The allowed range, as written in the schema
+        range: &'static str,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = |position: &Option<usize>| {
+            position.map_or(String::new(), |position| {
+                format!(" at offset {position:#x}")
+            })
+        };
+        match self {
+            Diagnostic::DeprecatedElement { id, position: p } => {
+                write!(f, "deprecated element {id}{}", position(p))
+            }
+            Diagnostic::OverflowingElement {
+                id,
+                position: p,
+                parent,
+                overflow,
+            } => write!(
+                f,
+                "{id}{} overflows its parent {parent} by {overflow} bytes",
+                position(p)
+            ),
+            Diagnostic::OutOfRange {
+                id,
+                position: p,
+                range,
+            } => write!(
+                f,
+                "{id}{} is out of the allowed range \"{range}\"",
+                position(p)
+            ),
+        }
+    }
+}
+
+/// Collects diagnostics from elements as they are parsed
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+    // Open Master elements, tracked as the parser does
+    open_masters: OpenMasters,
+}
+
+impl Diagnostics {
+    /// Create an empty collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inspect the next parsed element. Elements must be inspected in the
+    /// order they are parsed and have their positions set for sizes to be
+    /// checked.
+    pub fn inspect(&mut self, element: &Element) {
+        let header = &element.header;
+        if header.id.is_deprecated() {
+            self.diagnostics.push(Diagnostic::DeprecatedElement {
+                id: header.id.clone(),
+                position: header.position,
+            });
+        }
+        if let Some(range) = out_of_range(element) {
+            self.diagnostics.push(Diagnostic::OutOfRange {
+                id: header.id.clone(),
+                position: header.position,
+                range,
+            });
+        }
+
+        let Some(position) = header.position else {
+            return;
+        };
+        self.open_masters.close_before(&header.id, position);
+        if let (Some(parent), Some(size)) = (self.open_masters.last(), header.size) {
+            if let Some(end) = parent.end.filter(|end| position + size > *end) {
+                self.diagnostics.push(Diagnostic::OverflowingElement {
+                    id: header.id.clone(),
+                    position: Some(position),
+                    parent: parent.id.clone(),
+                    overflow: position + size - end,
+                });
+            }
+        }
+        if let Body::Master = element.body {
+            self.open_masters.open(header, position);
+        }
+    }
+
+    /// Diagnostics collected so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Consume the collector, returning the diagnostics
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+/// Parse all elements from a reader, along with diagnostics about them
+pub fn parse_elements_with_diagnostics<R: Read>(
+    reader: R,
+) -> Result<(Vec<Element>, Vec<Diagnostic>)> {
+    let mut diagnostics = Diagnostics::new();
+    let elements = ElementIterator::new(reader)
+        .map(|element| {
+            let element = element?;
+            diagnostics.inspect(&element);
+            Ok(element)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((elements, diagnostics.into_diagnostics()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Header, Unsigned};
+
+    #[test]
+    fn test_diagnostics() {
+        let element = |header: Header, position, body| Element {
+            header: Header {
+                position: Some(position),
+                ..header
+            },
+            body,
+        };
+        let elements = [
+            element(Header::new(Id::Info, 4, 8), 0, Body::Master),
+            element(
+                Header::new(Id::TimestampScale, 4, 1),
+                4,
+                Body::Unsigned(Unsigned::Standard(0)),
+            ),
+            element(
+                Header::new(Id::SilentTrackNumber, 2, 2),
+                9,
+                Body::Unsigned(Unsigned::Standard(1)),
+            ),
+        ];
+        let mut diagnostics = Diagnostics::new();
+        for element in &elements {
+            diagnostics.inspect(element);
+        }
+        assert_eq!(
+            diagnostics.diagnostics(),
+            [
+                Diagnostic::OutOfRange {
+                    id: Id::TimestampScale,
+                    position: Some(4),
+                    range: "not 0",
+                },
+                Diagnostic::DeprecatedElement {
+                    id: Id::SilentTrackNumber,
+                    position: Some(9),
+                },
+                Diagnostic::OverflowingElement {
+                    id: Id::SilentTrackNumber,
+                    position: Some(9),
+                    parent: Id::Info,
+                    overflow: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            diagnostics.diagnostics()[0].to_string(),
+            "TimestampScale at offset 0x4 is out of the allowed range \"not 0\""
+        );
+    }
+}
@@ -1,6 +1,28 @@
 macro_rules! ebml_elements {
-    ($($(#[doc = $doc:literal])* name = $element_name:ident, original_name = $original_name:expr, id = $id:expr, variant = $variant:ident;)+) => {
-        use serde::{Serialize, Serializer};
+    ($($(#[doc = $doc:literal])* name = $element_name:ident, original_name = $original_name:expr, id = $id:expr, variant = $variant:ident, path = $path:expr, min_occurs = $min_occurs:expr, max_occurs = $max_occurs:expr, range = $range:expr, default = $default:expr, minver = $minver:expr, maxver = $maxver:expr;)+) => {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        /// Constraints of an element from the EBML Schema
+        #[derive(Debug, PartialEq)]
+        pub(crate) struct ElementSchema {
+            /// Path of the element, e.g. `\Segment\Info\TimestampScale`
+            pub(crate) path: &'static str,
+            /// Minimum number of occurrences within its parent
+            pub(crate) min_occurs: usize,
+            /// Maximum number of occurrences within its parent
+            pub(crate) max_occurs: Option<usize>,
+            /// Allowed range of numeric values, e.g. `not 0` or `1-8`
+            pub(crate) range: Option<&'static str>,
+            /// Default value
+            pub(crate) default: Option<&'static str>,
+            /// First version of the DocType the element is part of
+            pub(crate) minver: u64,
+            /// Last version of the DocType the element is part of
+            pub(crate) maxver: Option<u64>,
+        }
+
+        /// All elements known by the schema
+        pub(crate) const SCHEMA_IDS: &[Id] = &[$(Id::$element_name,)+];
 
         /// Matroska Element Type.
         #[derive(Debug, PartialEq)]
@@ -27,9 +49,13 @@ macro_rules! ebml_elements {
         #[derive(Debug, PartialEq, Eq, Clone)]
         pub enum Id {
             /// Unknown ID containing the value parsed.
-            Unknown(u32),
+            Unknown(u64),
             /// Corrupted element. Used when there is a parsing error and a portion of the input is skipped.
             Corrupted,
+            /// Truncated element. Used when the input ends in the middle of an element.
+            Truncated,
+            /// Boundary between concatenated streams. Used before each EBML header after the first one.
+            StreamBoundary,
             $(
                 $(#[doc = $doc])*
                 $element_name,
@@ -37,9 +63,9 @@ macro_rules! ebml_elements {
         }
 
         impl Id {
-            /// Build a new ID from an u32. If the value does not represent a known element,
+            /// Build a new ID from an u64. If the value does not represent a known element,
             /// an Unknown ID will be created.
-            pub fn new(id: u32) -> Self {
+            pub fn new(id: u64) -> Self {
                 match id {
                     $($id => Self::$element_name,)+
                     _ => Self::Unknown(id)
@@ -55,27 +81,121 @@ macro_rules! ebml_elements {
             pub fn get_type(&self) -> Type {
                 match self {
                     $(Id::$element_name => Type::$variant,)+
-                    Id::Unknown(_) | Id::Corrupted => Type::Binary
+                    Id::Unknown(_) | Id::Corrupted | Id::Truncated | Id::StreamBoundary => Type::Binary
+                }
+            }
+
+            /// Get the schema of this element, if known
+            pub(crate) fn schema(&self) -> Option<&'static ElementSchema> {
+                match self {
+                    $(Id::$element_name => Some(&ElementSchema {
+                        path: $path,
+                        min_occurs: $min_occurs,
+                        max_occurs: $max_occurs,
+                        range: $range,
+                        default: $default,
+                        minver: $minver,
+                        maxver: $maxver,
+                    }),)+
+                    Id::Unknown(_) | Id::Corrupted | Id::Truncated | Id::StreamBoundary => None
                 }
             }
 
+            /// Get the default value of this element from the EBML Schema, if any
+            pub fn default_value(&self) -> Option<&'static str> {
+                self.schema().and_then(|schema| schema.default)
+            }
+
+            /// Get the allowed range of numeric values of this element from the
+            /// EBML Schema, such as `not 0` or `1-8`
+            pub fn value_range(&self) -> Option<&'static str> {
+                self.schema().and_then(|schema| schema.range)
+            }
+
+            /// Whether this element is no longer part of the latest version of
+            /// the DocType
+            pub fn is_deprecated(&self) -> bool {
+                self.schema().is_some_and(|schema| schema.maxver.is_some())
+            }
+
+            /// Get the first version of the DocType this element is part of
+            pub fn min_doctype_version(&self) -> Option<u64> {
+                self.schema().map(|schema| schema.minver)
+            }
+
+            /// Get the last version of the DocType this element is part of, if
+            /// it is no longer part of the latest one
+            pub fn max_doctype_version(&self) -> Option<u64> {
+                self.schema().and_then(|schema| schema.maxver)
+            }
+
             /// Get underlying integer value
-            pub fn get_value(&self) -> Option<u32> {
+            pub fn get_value(&self) -> Option<u64> {
                 match self {
                     $(Id::$element_name => Some($id),)+
                     Id::Unknown(value) => Some(*value),
-                    Id::Corrupted => None
+                    Id::Corrupted | Id::Truncated | Id::StreamBoundary => None
+                }
+            }
+        }
+
+        impl Id {
+            /// Build an ID from the name of the element in the EBML Schema,
+            /// such as `TimestampScale`. Returns `None` for unknown names.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($original_name => Some(Self::$element_name),)+
+                    _ => None
+                }
+            }
+        }
+
+        impl std::fmt::Display for Id {
+            /// Write the name of this element, or its hexadecimal value if unknown
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Id::$element_name => f.write_str($original_name),)+
+                    Id::Unknown(value) => write!(f, "0x{:X}", value),
+                    Id::Corrupted => f.write_str("Corrupted"),
+                    Id::Truncated => f.write_str("Truncated"),
+                    Id::StreamBoundary => f.write_str("StreamBoundary")
+                }
+            }
+        }
+
+        impl std::str::FromStr for Id {
+            type Err = crate::Error;
+
+            /// Parse an ID from the name of the element, or from its
+            /// hexadecimal value such as `0x1A45DFA3`
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    "Corrupted" => return Ok(Self::Corrupted),
+                    "Truncated" => return Ok(Self::Truncated),
+                    "StreamBoundary" => return Ok(Self::StreamBoundary),
+                    _ => (),
+                }
+                if let Some(id) = Self::from_name(s) {
+                    return Ok(id);
                 }
+                s.strip_prefix("0x")
+                    .or_else(|| s.strip_prefix("0X"))
+                    .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                    .map(Self::new)
+                    .ok_or_else(|| crate::Error::UnknownElementName(s.to_string()))
             }
         }
 
         impl Serialize for Id {
             fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
-                match *self {
-                    $(Id::$element_name => s.serialize_str($original_name),)+
-                    Id::Unknown(value) => s.serialize_str(&format!("0x{:X}", value)),
-                    Id::Corrupted => s.serialize_str("Corrupted")
-                }
+                s.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Id {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(d)?;
+                name.parse().map_err(serde::de::Error::custom)
             }
         }
     };
@@ -105,6 +225,31 @@ macro_rules! ebml_enumerations {
                         _ => None,
                     }
                 }
+
+                /// Get the underlying integer value
+                pub fn value(&self) -> u64 {
+                    match self {
+                        $(Self::$variant => $value,)+
+                    }
+                }
+
+                /// Get the label of the value in the specification
+                pub fn label(&self) -> &'static str {
+                    match self {
+                        $(Self::$variant => $original_label,)+
+                    }
+                }
+
+                /// Create a new instance from the label of the value in the
+                /// specification. Labels such as `reserved` may be shared by
+                /// several values, in which case the first one is used.
+                #[allow(unreachable_patterns)]
+                pub fn from_label(label: &str) -> Option<Self> {
+                    match label {
+                        $($original_label => Some(Self::$variant),)+
+                        _ => None,
+                    }
+                }
             }
         )+
 
@@ -128,6 +273,26 @@ macro_rules! ebml_enumerations {
                     _ => None
                 }
             }
+
+            /// Create new enumeration from the label of the value in the
+            /// specification, such as `audio` for a TrackType
+            pub fn from_label(id: &Id, label: &str) -> Option<Self> {
+                match id {
+                    $(
+                        Id::$id => $id::from_label(label).map(Self::$id),
+                    )+
+                    _ => None
+                }
+            }
+
+            /// Get the underlying integer value
+            pub fn value(&self) -> u64 {
+                match self {
+                    $(
+                        Self::$id(value) => value.value(),
+                    )+
+                }
+            }
         }
     };
 }
@@ -1,11 +1,17 @@
-use std::{num::TryFromIntError, string::FromUtf8Error};
+use std::{
+    num::{NonZeroUsize, TryFromIntError},
+    str::Utf8Error,
+    string::FromUtf8Error,
+};
+
+use crate::elements::Id;
 
 /// An Error while parsing Matroska/WebM files
 #[derive(Debug, PartialEq, thiserror::Error)]
 pub enum Error {
-    /// Need data
-    #[error("need data")]
-    NeedData,
+    /// Need data, with the number of additional bytes needed if known
+    #[error("need {}", needed(.0))]
+    NeedData(Option<NonZeroUsize>),
     /// Parsing error
     #[error("parser error")]
     Parser,
@@ -21,6 +27,9 @@ pub enum Error {
     /// Error building UTF-8 string
     #[error("{0}")]
     Utf8(#[from] FromUtf8Error),
+    /// Error borrowing an UTF-8 string
+    #[error("{0}")]
+    Utf8Str(#[from] Utf8Error),
     /// Forbidden Integer size
     #[error("forbidden integer size")]
     ForbiddenIntegerSize,
@@ -39,12 +48,64 @@ pub enum Error {
     /// Invalid Date
     #[error("invalid date")]
     InvalidDate,
+    /// Lacing sizes inconsistent with the block size
+    #[error("invalid lacing")]
+    InvalidLacing,
+    /// Element is larger than allowed by the parser options
+    #[error("element too large")]
+    ElementTooLarge,
+    /// Element is nested deeper than allowed by the parser options
+    #[error("maximum depth exceeded")]
+    MaxDepthExceeded,
+    /// Binary payload is not fully available
+    #[error("incomplete binary payload")]
+    IncompleteBinary,
+    /// Malformed data found while corruption recovery is disabled
+    #[error("malformed {} at offset {position:#x}: {error}", describe(id, parent))]
+    Malformed {
+        /// Position of the malformed element in the input
+        position: usize,
+        /// The malformed element, if its ID could be parsed
+        id: Option<Id>,
+        /// The Master element containing the malformed element, if any
+        parent: Option<Id>,
+        /// What was wrong with the element
+        error: Box<Error>,
+    },
+    /// Name that does not match any element of the EBML Schema
+    #[error("unknown element name: {0}")]
+    UnknownElementName(String),
+    /// I/O error while reading or writing
+    #[error("io error: {0}")]
+    Io(std::io::ErrorKind),
+}
+
+fn needed(needed: &Option<NonZeroUsize>) -> String {
+    match needed {
+        Some(needed) => format!("{needed} more bytes"),
+        None => "data".to_string(),
+    }
+}
+
+fn describe(id: &Option<Id>, parent: &Option<Id>) -> String {
+    let element = id.as_ref().map_or("data".to_string(), Id::to_string);
+    match parent {
+        Some(parent) => format!("{element} in {parent}"),
+        None => element,
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value.kind())
+    }
 }
 
 impl From<nom::Err<()>> for Error {
     fn from(value: nom::Err<()>) -> Self {
         match value {
-            nom::Err::Incomplete(_) => Self::NeedData,
+            nom::Err::Incomplete(nom::Needed::Size(needed)) => Self::NeedData(Some(needed)),
+            nom::Err::Incomplete(nom::Needed::Unknown) => Self::NeedData(None),
             _ => Self::Parser,
         }
     }
@@ -59,5 +120,13 @@ mod tests {
     #[test]
     fn parser() {
         assert_eq!(Error::Parser, nom::Err::Error(()).into());
+        assert_eq!(
+            Error::NeedData(NonZeroUsize::new(3)),
+            nom::Err::Incomplete(nom::Needed::new(3)).into()
+        );
+        assert_eq!(
+            Error::NeedData(NonZeroUsize::new(3)).to_string(),
+            "need 3 more bytes"
+        );
     }
 }
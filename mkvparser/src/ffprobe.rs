@@ -0,0 +1,409 @@
+//! Description of a file in the JSON layout of ffprobe
+//!
+//! A [`Probe`] mirrors the `streams` and `format` objects printed by
+//! `ffprobe -show_streams -show_format -of json`, so that tools reading
+//! them can be used with Matroska files described by a [`Document`].
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Timelike};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    enumerations::TrackType,
+    model::{AttachedFile, Document, SegmentInfo, TagTarget, TrackEntry},
+};
+
+/// Dispositions of a stream, as 0 or 1 like ffprobe does
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Disposition {
+    /// Eligible for automatic selection
+    pub default: u8,
+    /// Dubbed audio
+    pub dub: u8,
+    /// Original language of the content
+    pub original: u8,
+    /// Commentary
+    pub comment: u8,
+    /// Song lyrics
+    pub lyrics: u8,
+    /// Karaoke
+    pub karaoke: u8,
+    /// Forced to be presented
+    pub forced: u8,
+    /// For users with hearing impairments
+    pub hearing_impaired: u8,
+    /// For users with visual impairments
+    pub visual_impaired: u8,
+    /// Clean effects, without dialogue
+    pub clean_effects: u8,
+    /// Picture attached to the file, such as cover art
+    pub attached_pic: u8,
+    /// Timed thumbnails
+    pub timed_thumbnails: u8,
+}
+
+/// A track or attached file, as an entry of the `streams` of ffprobe
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProbeStream {
+    /// Index of the stream, in the order of the tracks and then of the
+    /// attached files
+    pub index: usize,
+    /// Name of the codec in ffmpeg, such as `h264` or `opus`
+    pub codec_name: Option<String>,
+    /// Kind of stream, such as `video`, `audio`, `subtitle` or `attachment`
+    pub codec_type: String,
+    /// Width of video frames
+    pub width: Option<u64>,
+    /// Height of video frames
+    pub height: Option<u64>,
+    /// Sampling frequency of audio in Hz
+    pub sample_rate: Option<String>,
+    /// Number of audio channels
+    pub channels: Option<u64>,
+    /// Bits per audio sample
+    pub bits_per_raw_sample: Option<String>,
+    /// Frame rate of video, from the DefaultDuration of the track
+    pub r_frame_rate: Option<String>,
+    /// Average frame rate of video, taken as the frame rate
+    pub avg_frame_rate: Option<String>,
+    /// Unit of timestamps in seconds, from the TimestampScale
+    pub time_base: Option<String>,
+    /// Dispositions of the stream
+    pub disposition: Disposition,
+    /// Metadata of the stream, such as its language and title
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Overview of the file, as the `format` of ffprobe
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProbeFormat {
+    /// Name of the file
+    pub filename: String,
+    /// Number of streams
+    pub nb_streams: usize,
+    /// Number of programs, which Matroska doesn't have
+    pub nb_programs: usize,
+    /// Names of the format in ffmpeg
+    pub format_name: String,
+    /// Description of the format
+    pub format_long_name: String,
+    /// Duration in seconds
+    pub duration: Option<String>,
+    /// Size of the file in bytes
+    pub size: Option<String>,
+    /// Overall bit rate in bits per second
+    pub bit_rate: Option<String>,
+    /// Confidence of the format detection, always certain
+    pub probe_score: u8,
+    /// Metadata of the file, such as its title and muxing application
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Description of a file in the JSON layout of ffprobe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Probe {
+    /// Tracks and attached files
+    pub streams: Vec<ProbeStream>,
+    /// Overview of the file
+    pub format: ProbeFormat,
+}
+
+// Name of the codec in ffmpeg for a Matroska CodecID
+fn codec_name(codec_id: &str) -> Option<&'static str> {
+    Some(match codec_id {
+        "V_MPEG4/ISO/AVC" => "h264",
+        "V_MPEGH/ISO/HEVC" => "hevc",
+        "V_MPEGI/ISO/VVC" => "vvc",
+        "V_AV1" => "av1",
+        "V_VP8" => "vp8",
+        "V_VP9" => "vp9",
+        "V_MPEG1" => "mpeg1video",
+        "V_MPEG2" => "mpeg2video",
+        "V_THEORA" => "theora",
+        "V_PRORES" => "prores",
+        "V_FFV1" => "ffv1",
+        "A_OPUS" => "opus",
+        "A_VORBIS" => "vorbis",
+        "A_FLAC" => "flac",
+        "A_AC3" => "ac3",
+        "A_EAC3" => "eac3",
+        "A_TRUEHD" => "truehd",
+        "A_DTS" => "dts",
+        "A_ALAC" => "alac",
+        "A_MPEG/L2" => "mp2",
+        "A_MPEG/L3" => "mp3",
+        "A_PCM/FLOAT/IEEE" => "pcm_f32le",
+        "S_TEXT/UTF8" => "subrip",
+        "S_TEXT/ASS" => "ass",
+        "S_TEXT/SSA" => "ssa",
+        "S_TEXT/WEBVTT" => "webvtt",
+        "S_VOBSUB" => "dvd_subtitle",
+        "S_HDMV/PGS" => "hdmv_pgs_subtitle",
+        "S_DVBSUB" => "dvb_subtitle",
+        codec_id if codec_id.starts_with("A_AAC") => "aac",
+        _ => return None,
+    })
+}
+
+// Name of the codec of PCM audio, which depends on the sample size
+fn pcm_codec_name(codec_id: &str, bit_depth: Option<u64>) -> Option<String> {
+    let endianness = match codec_id {
+        "A_PCM/INT/LIT" => "le",
+        "A_PCM/INT/BIG" => "be",
+        _ => return None,
+    };
+    match bit_depth? {
+        8 => Some("pcm_u8".to_string()),
+        bit_depth => Some(format!("pcm_s{bit_depth}{endianness}")),
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Fraction approximating `num / den` with terms of at most `max`, from the
+// convergents of its continued fraction like ffmpeg does
+fn approximate_fraction(num: u64, den: u64, max: u64) -> (u64, u64) {
+    let (mut num, mut den) = (num, den);
+    let (mut p0, mut q0, mut p1, mut q1) = (0, 1, 1, 0);
+    while den != 0 {
+        let a = num / den;
+        let (p2, q2) = (a * p1 + p0, a * q1 + q0);
+        if p2 > max || q2 > max {
+            break;
+        }
+        (p0, q0, p1, q1) = (p1, q1, p2, q2);
+        (num, den) = (den, num - a * den);
+    }
+    (p1, q1)
+}
+
+// Frame rate of a track as a fraction, such as 24000/1001
+fn frame_rate(default_duration: u64) -> Option<String> {
+    if default_duration == 0 {
+        return None;
+    }
+    let (num, den) = approximate_fraction(1_000_000_000, default_duration, 30_000);
+    Some(format!("{num}/{den}"))
+}
+
+// Unit of timestamps in seconds as a fraction, such as 1/1000
+fn time_base(timestamp_scale: u64) -> String {
+    let divisor = gcd(timestamp_scale, 1_000_000_000);
+    format!("{}/{}", timestamp_scale / divisor, 1_000_000_000 / divisor)
+}
+
+fn track_stream(
+    index: usize,
+    track: &TrackEntry,
+    timestamp_scale: u64,
+    tags: &BTreeMap<TagTarget, Vec<(String, String)>>,
+) -> ProbeStream {
+    let codec_id = track.codec_id.as_deref().unwrap_or_default();
+    let video = track.video.as_ref();
+    let audio = track.audio.as_ref();
+    let codec_type = match track.track_type {
+        Some(TrackType::Video) => "video",
+        Some(TrackType::Audio) => "audio",
+        Some(TrackType::Subtitle) => "subtitle",
+        _ => "data",
+    };
+    let frame_rate = video.and(track.default_duration).and_then(frame_rate);
+
+    let mut stream_tags = BTreeMap::from([("language".to_string(), track.language.clone())]);
+    if let Some(name) = &track.name {
+        stream_tags.insert("title".to_string(), name.clone());
+    }
+    if let Some(values) = track
+        .track_uid
+        .and_then(|uid| tags.get(&TagTarget::Track(uid)))
+    {
+        stream_tags.extend(values.iter().cloned());
+    }
+
+    ProbeStream {
+        index,
+        codec_name: codec_name(codec_id)
+            .map(str::to_string)
+            .or_else(|| pcm_codec_name(codec_id, audio.and_then(|audio| audio.bit_depth))),
+        codec_type: codec_type.to_string(),
+        width: video.and_then(|video| video.pixel_width),
+        height: video.and_then(|video| video.pixel_height),
+        sample_rate: audio.map(|audio| audio.sampling_frequency.round().to_string()),
+        channels: audio.map(|audio| audio.channels),
+        bits_per_raw_sample: audio
+            .and_then(|audio| audio.bit_depth)
+            .map(|bit_depth| bit_depth.to_string()),
+        r_frame_rate: frame_rate.clone(),
+        avg_frame_rate: frame_rate,
+        time_base: Some(time_base(timestamp_scale)),
+        disposition: Disposition {
+            default: track.default.into(),
+            original: track.original.into(),
+            comment: track.commentary.into(),
+            forced: track.forced.into(),
+            hearing_impaired: track.hearing_impaired.into(),
+            visual_impaired: track.visual_impaired.into(),
+            ..Disposition::default()
+        },
+        tags: stream_tags,
+    }
+}
+
+fn attachment_stream(index: usize, attachment: &AttachedFile) -> ProbeStream {
+    // Pictures are shown as video streams with a single frame, like cover
+    // art, while other files are attachments
+    let (codec_type, codec_name, attached_pic) = match attachment.mime_type.as_str() {
+        "image/jpeg" => ("video", Some("mjpeg"), 1),
+        "image/png" => ("video", Some("png"), 1),
+        "font/ttf" | "application/x-truetype-font" => ("attachment", Some("ttf"), 0),
+        "font/otf" | "application/vnd.ms-opentype" => ("attachment", Some("otf"), 0),
+        _ => ("attachment", None, 0),
+    };
+    ProbeStream {
+        index,
+        codec_name: codec_name.map(str::to_string),
+        codec_type: codec_type.to_string(),
+        width: None,
+        height: None,
+        sample_rate: None,
+        channels: None,
+        bits_per_raw_sample: None,
+        r_frame_rate: None,
+        avg_frame_rate: None,
+        time_base: None,
+        disposition: Disposition {
+            attached_pic,
+            ..Disposition::default()
+        },
+        tags: BTreeMap::from([
+            ("filename".to_string(), attachment.file_name.clone()),
+            ("mimetype".to_string(), attachment.mime_type.clone()),
+        ]),
+    }
+}
+
+/// Describe a document as ffprobe would, given the name and size of its
+/// file
+pub fn probe(document: &Document, filename: &str, size: Option<u64>) -> Probe {
+    let timestamp_scale = document.timestamp_scale();
+    let tags = document.tags_by_target();
+    let streams = document
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(index, track)| track_stream(index, track, timestamp_scale, &tags))
+        .chain(
+            document
+                .attachments
+                .iter()
+                .enumerate()
+                .map(|(index, attachment)| {
+                    attachment_stream(document.tracks.len() + index, attachment)
+                }),
+        )
+        .collect::<Vec<_>>();
+
+    let info = document.info.as_ref();
+    let duration_s = info
+        .and_then(SegmentInfo::duration_ns)
+        .map(|duration_ns| duration_ns / 1e9);
+    let mut format_tags = BTreeMap::new();
+    if let Some(info) = info {
+        format_tags.extend(info.title.clone().map(|title| ("title".to_string(), title)));
+        format_tags.extend(
+            info.muxing_app
+                .clone()
+                .map(|muxing_app| ("encoder".to_string(), muxing_app)),
+        );
+        format_tags.extend(info.date_utc.map(|date_utc| {
+            let creation_time = format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+                date_utc.year(),
+                date_utc.month(),
+                date_utc.day(),
+                date_utc.hour(),
+                date_utc.minute(),
+                date_utc.second(),
+                date_utc.timestamp_subsec_micros(),
+            );
+            ("creation_time".to_string(), creation_time)
+        }));
+    }
+    if let Some(values) = tags.get(&TagTarget::Segment) {
+        format_tags.extend(values.iter().cloned());
+    }
+
+    Probe {
+        format: ProbeFormat {
+            filename: filename.to_string(),
+            nb_streams: streams.len(),
+            nb_programs: 0,
+            format_name: "matroska,webm".to_string(),
+            format_long_name: "Matroska / WebM".to_string(),
+            duration: duration_s.map(|duration_s| format!("{duration_s:.6}")),
+            size: size.map(|size| size.to_string()),
+            bit_rate: size
+                .zip(duration_s.filter(|duration_s| *duration_s > 0.0))
+                .map(|(size, duration_s)| ((size * 8) as f64 / duration_s).round().to_string()),
+            probe_score: 100,
+            tags: format_tags,
+        },
+        streams,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter::ElementIterator;
+
+    #[test]
+    fn test_frame_rate() {
+        assert_eq!(frame_rate(33_333_333).as_deref(), Some("30/1"));
+        assert_eq!(frame_rate(41_708_333).as_deref(), Some("24000/1001"));
+        assert_eq!(frame_rate(40_000_000).as_deref(), Some("25/1"));
+        assert_eq!(time_base(1_000_000), "1/1000");
+        assert_eq!(time_base(100_000), "1/10000");
+    }
+
+    #[test]
+    fn test_probe() {
+        // Segment with an Info of TimestampScale 1000000 and Duration 2000,
+        // and a Tracks with a 1280x720 VP9 track of 40 ms frames
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xB9, 0x15, 0x49, 0xA9, 0x66, 0x8E, 0x2A, 0xD7, 0xB1, 0x83,
+            0x0F, 0x42, 0x40, 0x44, 0x89, 0x84, 0x44, 0xFA, 0x00, 0x00, 0x16, 0x54, 0xAE, 0x6B,
+            0xA1, 0xAE, 0x9F, 0xD7, 0x81, 0x01, 0x83, 0x81, 0x01, 0x86, 0x85, 0x56, 0x5F, 0x56,
+            0x50, 0x39, 0x23, 0xE3, 0x83, 0x84, 0x02, 0x62, 0x5A, 0x00, 0xE0, 0x88, 0xB0, 0x82,
+            0x05, 0x00, 0xBA, 0x82, 0x02, 0xD0,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        let probe = probe(&Document::from_elements(&elements), "a.webm", Some(500_000));
+
+        let [stream] = &probe.streams[..] else {
+            panic!("expected a single stream: {:?}", probe.streams);
+        };
+        assert_eq!(stream.codec_name.as_deref(), Some("vp9"));
+        assert_eq!(stream.codec_type, "video");
+        assert_eq!((stream.width, stream.height), (Some(1280), Some(720)));
+        assert_eq!(stream.r_frame_rate.as_deref(), Some("25/1"));
+        assert_eq!(stream.time_base.as_deref(), Some("1/1000"));
+        assert_eq!(stream.disposition.default, 1);
+        assert_eq!(stream.tags["language"], "eng");
+
+        assert_eq!(probe.format.nb_streams, 1);
+        assert_eq!(probe.format.duration.as_deref(), Some("2.000000"));
+        assert_eq!(probe.format.bit_rate.as_deref(), Some("2000000"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Decoding of HDR metadata carried in BlockAdditional and BlockAddIDExtraData
+//! elements
+//!
+//! How these payloads are interpreted depends on the BlockAddID of the
+//! BlockMore and the BlockAdditionMapping of the track, so they are only
+//! decoded by the element iterators.
+//!
+//! The static HDR properties of a track, from its Colour element, can also be
+//! summarized in one line with [`set_hdr_summaries`].
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    codecs::{parse_u16, parse_u8, BitReader},
+    elements::Id,
+    enumerations::{Primaries, TransferCharacteristics},
+    Body, Element, Error, IResult,
+};
+
+/// BlockAddIDType of ITU-T T.35 metadata
+pub const ITU_T_35_BLOCK_ADD_ID_TYPE: u64 = 4;
+
+/// BlockAddID that WebM reserves for ITU-T T.35 metadata
+pub const WEBM_ITU_T_35_BLOCK_ADD_ID: u64 = 4;
+
+/// BlockAddIDTypes of Dolby Vision configuration records, i.e. the 'dvcC',
+/// 'dvvC' and 'dvwC' four-character codes
+pub const DOLBY_VISION_BLOCK_ADD_ID_TYPES: [u64; 3] = [0x64766343, 0x64767643, 0x64767743];
+
+/// Header of an HDR10+ dynamic metadata payload, as defined in SMPTE ST 2094-40
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hdr10Plus {
+    /// Application version
+    pub application_version: u8,
+    /// Number of processing windows
+    pub num_windows: u8,
+    /// Maximum luminance of the targeted display, in nits. Only decoded
+    /// when there's a single window.
+    pub targeted_system_display_maximum_luminance: Option<u32>,
+}
+
+/// ITU-T T.35 metadata
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItuT35 {
+    /// itu_t_t35_country_code
+    pub country_code: u8,
+    /// itu_t_t35_terminal_provider_code
+    pub terminal_provider_code: u16,
+    /// itu_t_t35_terminal_provider_oriented_code
+    pub terminal_provider_oriented_code: u16,
+    /// HDR10+ metadata, if the payload contains it
+    pub hdr10_plus: Option<Hdr10Plus>,
+}
+
+fn parse_hdr10_plus(input: &[u8]) -> Result<Hdr10Plus, Error> {
+    let mut reader = BitReader::new(input);
+    let application_version = reader.read(8)? as u8;
+    let num_windows = reader.read(2)? as u8;
+    let targeted_system_display_maximum_luminance = if num_windows == 1 {
+        Some(reader.read(27)?)
+    } else {
+        None
+    };
+    Ok(Hdr10Plus {
+        application_version,
+        num_windows,
+        targeted_system_display_maximum_luminance,
+    })
+}
+
+/// Parse ITU-T T.35 metadata, decoding HDR10+ payloads
+pub fn parse_itu_t35(input: &[u8]) -> IResult<&[u8], ItuT35> {
+    let (input, country_code) = parse_u8(input)?;
+    let (input, terminal_provider_code) = parse_u16(input)?;
+    let (input, terminal_provider_oriented_code) = parse_u16(input)?;
+
+    // HDR10+ is registered in the USA by Samsung with application identifier 4
+    let hdr10_plus = match (
+        country_code,
+        terminal_provider_code,
+        terminal_provider_oriented_code,
+        input.first(),
+    ) {
+        (0xB5, 0x003C, 0x0001, Some(4)) => Some(parse_hdr10_plus(&input[1..])?),
+        _ => None,
+    };
+
+    Ok((
+        &[],
+        ItuT35 {
+            country_code,
+            terminal_provider_code,
+            terminal_provider_oriented_code,
+            hdr10_plus,
+        },
+    ))
+}
+
+/// Dolby Vision configuration record (dvcC, dvvC or dvwC)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DolbyVisionConfiguration {
+    /// Major version
+    pub version_major: u8,
+    /// Minor version
+    pub version_minor: u8,
+    /// Dolby Vision profile
+    pub profile: u8,
+    /// Dolby Vision level
+    pub level: u8,
+    /// Whether the stream contains RPUs
+    pub rpu_present: bool,
+    /// Whether the stream contains an enhancement layer
+    pub el_present: bool,
+    /// Whether the stream contains a base layer
+    pub bl_present: bool,
+    /// Compatibility of the base layer with other HDR or SDR formats
+    pub bl_signal_compatibility_id: u8,
+}
+
+/// Parse a Dolby Vision configuration record
+pub fn parse_dolby_vision_configuration(input: &[u8]) -> IResult<&[u8], DolbyVisionConfiguration> {
+    let (input, version_major) = parse_u8(input)?;
+    let (input, version_minor) = parse_u8(input)?;
+    let (input, flags) = parse_u16(input)?;
+    let (input, compatibility) = parse_u8(input)?;
+    Ok((
+        input,
+        DolbyVisionConfiguration {
+            version_major,
+            version_minor,
+            profile: (flags >> 9) as u8,
+            level: ((flags >> 3) & 0x3F) as u8,
+            rpu_present: flags & 0b100 != 0,
+            el_present: flags & 0b010 != 0,
+            bl_present: flags & 0b001 != 0,
+            bl_signal_compatibility_id: compatibility >> 4,
+        },
+    ))
+}
+
+fn transfer_name(value: u64) -> Option<&'static str> {
+    Some(match TransferCharacteristics::new(value)? {
+        TransferCharacteristics::ItuRBt2100PerceptualQuantization => "PQ",
+        TransferCharacteristics::AribStdB67Hlg => "HLG",
+        transfer => transfer.label(),
+    })
+}
+
+fn primaries_name(value: u64) -> Option<&'static str> {
+    Primaries::new(value).map(|primaries| primaries.label().trim_start_matches("ITU-R "))
+}
+
+// Luminance values are commonly stored as 32-bit floats, and mastering
+// displays are described with a precision of 0.0001 cd/m²
+fn round_luminance(value: f64) -> f64 {
+    (value * 1e4).round() / 1e4
+}
+
+/// Summarize the HDR properties found in the descendants of a Video
+/// element, given in parsed order, e.g. `transfer=PQ, primaries=BT.2020,
+/// MaxCLL=1000, MaxFALL=400, mastering=0.005-1000 cd/m²`.
+///
+/// Returns `None` if none of them is set.
+pub fn hdr_summary(video_descendants: &[Element]) -> Option<String> {
+    let mut transfer = None;
+    let mut primaries = None;
+    let mut max_cll = None;
+    let mut max_fall = None;
+    let mut luminance_min = None;
+    let mut luminance_max = None;
+    for element in video_descendants {
+        match (&element.header.id, &element.body) {
+            (Id::TransferCharacteristics, Body::Unsigned(value)) => {
+                transfer = transfer_name(value.value());
+            }
+            (Id::Primaries, Body::Unsigned(value)) => primaries = primaries_name(value.value()),
+            (Id::MaxCll, Body::Unsigned(value)) => max_cll = Some(value.value()),
+            (Id::MaxFall, Body::Unsigned(value)) => max_fall = Some(value.value()),
+            (Id::LuminanceMin, Body::Float(value)) => luminance_min = Some(round_luminance(*value)),
+            (Id::LuminanceMax, Body::Float(value)) => luminance_max = Some(round_luminance(*value)),
+            _ => (),
+        }
+    }
+
+    let mut parts = Vec::new();
+    if let Some(transfer) = transfer {
+        parts.push(format!("transfer={transfer}"));
+    }
+    if let Some(primaries) = primaries {
+        parts.push(format!("primaries={primaries}"));
+    }
+    if let Some(max_cll) = max_cll {
+        parts.push(format!("MaxCLL={max_cll}"));
+    }
+    if let Some(max_fall) = max_fall {
+        parts.push(format!("MaxFALL={max_fall}"));
+    }
+    match (luminance_min, luminance_max) {
+        (Some(min), Some(max)) => parts.push(format!("mastering={min}-{max} cd/m²")),
+        (Some(min), None) => parts.push(format!("mastering min={min} cd/m²")),
+        (None, Some(max)) => parts.push(format!("mastering max={max} cd/m²")),
+        (None, None) => (),
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Set the HDR summary of each Video element among parsed elements, see
+/// [`hdr_summary`]. Elements must be in the order they are parsed.
+pub fn set_hdr_summaries(elements: &mut [Element]) {
+    let is_in_video = |element: &Element| {
+        element.header.id.schema().is_some_and(|schema| {
+            schema
+                .path
+                .starts_with("\\Segment\\Tracks\\TrackEntry\\Video\\")
+        })
+    };
+    for index in 0..elements.len() {
+        if elements[index].header.id != Id::Video {
+            continue;
+        }
+        let descendants = &elements[index + 1..];
+        let end = descendants
+            .iter()
+            .position(|element| !is_in_video(element))
+            .unwrap_or(descendants.len());
+        elements[index].header.hdr = hdr_summary(&descendants[..end]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_parse_itu_t35() {
+        // HDR10+ with one window and a 1000 nits targeted display
+        const HDR10_PLUS: &[u8] = &[
+            0xB5, 0x00, 0x3C, 0x00, 0x01, 0x04, 0x01, 0x40, 0x00, 0x1F, 0x40,
+        ];
+        assert_eq!(
+            parse_itu_t35(HDR10_PLUS),
+            Ok((
+                &[][..],
+                ItuT35 {
+                    country_code: 0xB5,
+                    terminal_provider_code: 0x3C,
+                    terminal_provider_oriented_code: 0x01,
+                    hdr10_plus: Some(Hdr10Plus {
+                        application_version: 1,
+                        num_windows: 1,
+                        targeted_system_display_maximum_luminance: Some(1000),
+                    }),
+                }
+            ))
+        );
+
+        assert_eq!(
+            parse_itu_t35(&[0xB5, 0x00, 0x31, 0x00, 0x01])
+                .unwrap()
+                .1
+                .hdr10_plus,
+            None
+        );
+        assert_eq!(
+            parse_itu_t35(&[0xB5, 0x00]),
+            Err(Error::NeedData(NonZeroUsize::new(1)))
+        );
+    }
+
+    #[test]
+    fn test_parse_dolby_vision_configuration() {
+        // Profile 8.1, level 6, with RPU and base layer
+        const DVCC: &[u8] = &[
+            0x01, 0x00, 0x10, 0x35, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            parse_dolby_vision_configuration(DVCC).unwrap().1,
+            DolbyVisionConfiguration {
+                version_major: 1,
+                version_minor: 0,
+                profile: 8,
+                level: 6,
+                rpu_present: true,
+                el_present: false,
+                bl_present: true,
+                bl_signal_compatibility_id: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_hdr_summaries() {
+        // Video with a Colour element for PQ and BT.2020, MaxCLL 1000,
+        // MaxFALL 400 and a mastering display from 0.005 to 1000 cd/m²,
+        // followed by a TrackEntry with a Video element without Colour
+        const INPUT: &[u8] = &[
+            0xE0, 0xA6, 0x55, 0xB0, 0xA3, 0x55, 0xBA, 0x81, 0x10, 0x55, 0xBB, 0x81, 0x09, 0x55,
+            0xBC, 0x82, 0x03, 0xE8, 0x55, 0xBD, 0x82, 0x01, 0x90, 0x55, 0xD0, 0x8E, 0x55, 0xDA,
+            0x84, 0x3B, 0xA3, 0xD7, 0x0A, 0x55, 0xD9, 0x84, 0x44, 0x7A, 0x00, 0x00, 0xAE, 0x82,
+            0xE0, 0x80,
+        ];
+        let mut elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        set_hdr_summaries(&mut elements);
+        let summaries = elements
+            .iter()
+            .filter(|element| element.header.id == Id::Video)
+            .map(|element| element.header.hdr.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summaries,
+            [
+                Some(
+                    "transfer=PQ, primaries=BT.2020, MaxCLL=1000, MaxFALL=400, \
+                     mastering=0.005-1000 cd/m²"
+                ),
+                None
+            ]
+        );
+    }
+}
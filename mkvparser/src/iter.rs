@@ -0,0 +1,1698 @@
+//! Streaming iteration over the elements of any [`Read`] source
+//!
+//! Elements are parsed lazily as the underlying reader is consumed, so
+//! arbitrarily large files and non-seekable inputs (e.g. pipes) can be
+//! inspected without loading them in memory.
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet, VecDeque},
+    io::{Read, Seek, SeekFrom},
+    num::NonZeroUsize,
+};
+
+use crate::{
+    codecs,
+    elements::{Id, Type},
+    enumerations::ProjectionType,
+    find_sync_point, hdr,
+    languages::element_language,
+    menus,
+    model::DEFAULT_TIMESTAMP_SCALE,
+    parse_body_with_options, parse_header_with_limits, parse_id, parse_id_with_limits,
+    parse_varint_with_max_length, peek_binary_with_options, projection, take, Binary, Body,
+    DiscardPadding, EbmlLimits, Element, Error, Header, IResult, ParserOptions, Result,
+    StreamBoundary, SyncPoint, Truncated, SYNC_ELEMENT_IDS,
+};
+
+const DEFAULT_BUFFER_SIZE: usize = 8192;
+// Reads are sized for the next element, but never beyond this
+const MAX_BUFFER_SIZE: usize = 1 << 24;
+
+struct ShortParsed {
+    element: Element,
+    bytes_to_be_skipped: usize,
+}
+
+// For all element types except Binary, we can just parse the body, consuming all
+// bytes in it. Binary bodies can be rather large, but:
+// - we are not going to display their full payload in the dump anyways
+// - we don't want to load those large buffers in memory
+// so we just peek the first bytes in the beginning for some binary sub-types,
+// summarize the payload or serialize short ones.
+// For the binary bodies, since we're only peeking the buffer and not consuming it,
+// we return to the caller how many bytes should be skipped.
+//
+// Payloads that depend on previous elements, such as CodecPrivate of supported
+// codecs, are the exception, as they are fully consumed to be decoded.
+fn parse_short<'a>(
+    input: &'a [u8],
+    options: &ParserOptions,
+    context: &Context,
+) -> IResult<&'a [u8], ShortParsed> {
+    if context.generic_doctype {
+        if let Some(parsed) = parse_short_generic(input, options, &context.limits)? {
+            return Ok(parsed);
+        }
+    }
+    let (input, header) = parse_header_with_limits(
+        input,
+        options,
+        &context.limits,
+        &context.unknown_leaf_scanned,
+    )?;
+    if context.decodes_payload(&header.id) {
+        let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
+        let (remaining, payload) = take(body_size)(input)?;
+        // Fall back to the standard binary summary if decoding fails
+        let binary = match context.decode_payload(&header.id, payload) {
+            Some(binary) => binary,
+            None => peek_binary_with_options(&header, input, options)?.1,
+        };
+        return Ok((
+            remaining,
+            ShortParsed {
+                element: Element {
+                    header,
+                    body: Body::Binary(binary),
+                },
+                bytes_to_be_skipped: 0,
+            },
+        ));
+    }
+    if header.id.get_type() != Type::Binary {
+        let (input, body) = parse_body_with_options(&header, input, options)?;
+        Ok((
+            input,
+            ShortParsed {
+                element: Element { header, body },
+                bytes_to_be_skipped: 0,
+            },
+        ))
+    } else {
+        let (input, mut binary) = peek_binary_with_options(&header, input, options)?;
+        let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
+        if binary
+            .track_number()
+            .is_some_and(|track_number| context.encrypted_tracks.contains(&track_number))
+        {
+            binary.decode_encryption(input, body_size)?;
+        }
+        Ok((
+            input,
+            ShortParsed {
+                element: Element {
+                    header,
+                    body: Body::Binary(binary),
+                },
+                bytes_to_be_skipped: body_size,
+            },
+        ))
+    }
+}
+
+// Size of the body prefix checked for child elements in documents of
+// unknown DocTypes
+const MASTER_PROBE_SIZE: usize = 4096;
+
+// Documents with a DocType other than Matroska or WebM only share the
+// elements of the EBML schema with them. Other elements are parsed here as
+// Unknown: they are considered Master elements if their body looks like a
+// sequence of elements, so that the structure of the document is kept, and
+// opaque binaries otherwise. Returns None for elements of the EBML schema.
+fn parse_short_generic<'a>(
+    input: &'a [u8],
+    options: &ParserOptions,
+    limits: &EbmlLimits,
+) -> Result<Option<(&'a [u8], ShortParsed)>> {
+    let initial_len = input.len();
+    let (input, id) = parse_id_with_limits(input, limits)?;
+    let id_size = initial_len - input.len();
+    let (input, body_size) = parse_varint_with_max_length(input, limits.max_size_length)?;
+    if id
+        .schema()
+        .is_some_and(|schema| schema.path.starts_with("\\EBML") || schema.path.starts_with("\\("))
+    {
+        return Ok(None);
+    }
+
+    let id = Id::Unknown(id.get_value().unwrap());
+    let header_size = initial_len - input.len();
+    let Some(body_size) = body_size else {
+        // Only Master elements can have unknown size
+        let mut header = Header::with_unknown_size(id, header_size);
+        header.set_field_sizes(id_size, options);
+        let element = Element {
+            header,
+            body: Body::Master,
+        };
+        return Ok(Some((
+            input,
+            ShortParsed {
+                element,
+                bytes_to_be_skipped: 0,
+            },
+        )));
+    };
+
+    let mut header = Header::new(id, header_size, body_size);
+    header.set_field_sizes(id_size, options);
+    let (input, element, bytes_to_be_skipped) = if looks_like_master(input, body_size, limits)? {
+        let element = Element {
+            header,
+            body: Body::Master,
+        };
+        (input, element, 0)
+    } else {
+        let (input, binary) = peek_binary_with_options(&header, input, options)?;
+        let element = Element {
+            header,
+            body: Body::Binary(binary),
+        };
+        (input, element, body_size)
+    };
+    Ok(Some((
+        input,
+        ShortParsed {
+            element,
+            bytes_to_be_skipped,
+        },
+    )))
+}
+
+// Whether a body starts with a sequence of elements that fits in it. Only
+// the first bytes of large bodies are checked.
+fn looks_like_master(input: &[u8], body_size: usize, limits: &EbmlLimits) -> Result<bool> {
+    let probe_size = body_size.min(MASTER_PROBE_SIZE);
+    let (_, probe) = take(probe_size)(input)?;
+
+    let mut offset = 0;
+    while offset < body_size {
+        if offset >= probe_size {
+            return Ok(true);
+        }
+        let child = parse_id_with_limits(&probe[offset..], limits)
+            .and_then(|(rest, _)| parse_varint_with_max_length(rest, limits.max_size_length));
+        let (rest, child_body_size) = match child {
+            Ok(parsed) => parsed,
+            // The child header goes beyond the probe, but may fit in the body
+            Err(Error::NeedData(_)) => return Ok(offset > 0 && probe_size < body_size),
+            Err(_) => return Ok(false),
+        };
+        let Some(child_body_size) = child_body_size else {
+            // An element with unknown size extends up to the end of its parent
+            return Ok(true);
+        };
+        offset = probe_size - rest.len() + child_body_size;
+    }
+    Ok(offset == body_size)
+}
+
+fn parse_short_corrupt<'a>(
+    input: &'a [u8],
+    is_corrupt: &mut bool,
+) -> IResult<&'a [u8], ShortParsed> {
+    if input.is_empty() {
+        return Err(Error::NeedData(Some(NonZeroUsize::MIN)));
+    }
+    // If we fully consume the buffer as a corrupt region, we are still in
+    // a "corrupt state", so the caller should directly parse a
+    // corrupt region again until some valid element is found instead of
+    // attempting to parse an element (it could happen that parsing from
+    // the wrong start byte yields valid elements and the parser never
+    // returns to a valid state again).
+    let offset = match find_sync_point(input) {
+        SyncPoint::Found(offset) => {
+            *is_corrupt = false;
+            offset
+        }
+        // Wait for more data to tell whether the corrupt region ends there
+        SyncPoint::Undecided(_) => return Err(Error::NeedData(None)),
+        SyncPoint::NotFound => input.len(),
+    };
+    Ok((
+        &input[offset..],
+        ShortParsed {
+            element: Element {
+                header: Header::new(Id::corrupted(), 0, offset),
+                body: Body::Binary(Binary::Corrupted),
+            },
+            bytes_to_be_skipped: 0,
+        },
+    ))
+}
+
+// Describe the incomplete element at the end of the input, if at least its
+// ID can be read and it extends past the end
+fn parse_truncated(input: &[u8], limits: &EbmlLimits) -> Option<Truncated> {
+    let (rest, id) = parse_id_with_limits(input, limits).ok()?;
+    let declared_size = parse_varint_with_max_length(rest, limits.max_size_length)
+        .ok()
+        .and_then(|(body, size)| size.map(|size| input.len() - body.len() + size));
+    if declared_size.is_some_and(|size| size <= input.len()) {
+        return None;
+    }
+    Some(Truncated {
+        id,
+        declared_size,
+        available: input.len(),
+    })
+}
+
+fn parse_short_or_corrupt<'a>(
+    input: &'a [u8],
+    is_corrupt: &mut bool,
+    options: &ParserOptions,
+    context: &Context,
+) -> IResult<&'a [u8], ShortParsed> {
+    let parsed_short = if *is_corrupt {
+        parse_short_corrupt(input, is_corrupt)
+    } else {
+        parse_short(input, options, context)
+    };
+
+    match parsed_short {
+        Ok((input, short_parsed)) => Ok((input, short_parsed)),
+        Err(e @ Error::NeedData(_)) => Err(e),
+        Err(e) if !options.recover_corruption => Err(e),
+        Err(_) => {
+            *is_corrupt = true;
+            parse_short_corrupt(input, is_corrupt)
+        }
+    }
+}
+
+// While pushing corrupt elements, we check whether the pending element was also corrupt
+// to merge the corrupt area rather than emitting a new element.
+fn merge_corrupt_element(pending: &mut Option<Element>, corrupt_element: Element) {
+    match pending {
+        Some(pending_element) => {
+            pending_element.header = Header {
+                position: pending_element.header.position,
+                ..Header::new(
+                    Id::corrupted(),
+                    pending_element.header.header_size + corrupt_element.header.header_size,
+                    pending_element.header.body_size.unwrap()
+                        + corrupt_element.header.body_size.unwrap(),
+                )
+            };
+        }
+        None => *pending = Some(corrupt_element),
+    }
+}
+
+// State from previous elements that is needed to decode some payloads
+#[derive(Clone)]
+struct Context {
+    // CodecID of the current TrackEntry
+    codec_id: Option<String>,
+    // TrackNumber of the current TrackEntry
+    track_number: Option<usize>,
+    // Whether the current TrackEntry uses AES encryption
+    track_encrypted: bool,
+    encrypted_tracks: HashSet<usize>,
+    // SamplingFrequency of the current TrackEntry
+    sampling_frequency: Option<f64>,
+    sampling_frequencies: HashMap<usize, f64>,
+    // TrackNumber of the Block of the current BlockGroup
+    block_track_number: Option<usize>,
+    // BlockAddIDValue and BlockAddIDType of the current BlockAdditionMapping
+    block_addition_mapping: (Option<u64>, Option<u64>),
+    // BlockAddIDs of ITU-T T.35 metadata
+    itu_t35_block_add_ids: HashSet<u64>,
+    // BlockAddID of the current BlockMore
+    block_add_id: u64,
+    // Whether the DocType is neither Matroska nor WebM
+    generic_doctype: bool,
+    // EBMLMaxIDLength and EBMLMaxSizeLength of the current EBML header
+    limits: EbmlLimits,
+    // TimestampScale of the current Segment
+    timestamp_scale: u64,
+    // Timestamp of the current Cluster
+    cluster_timestamp: Option<u64>,
+    // ChapProcessCodecID of the current ChapProcess
+    chap_process_codec_id: u64,
+    // ProjectionType of the current Projection
+    projection_type: Option<ProjectionType>,
+    // How far the body of the next element was searched for its end, if it
+    // is a non-Master element with unknown size that needs more data
+    unknown_leaf_scanned: Cell<usize>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            codec_id: None,
+            track_number: None,
+            track_encrypted: false,
+            encrypted_tracks: HashSet::new(),
+            sampling_frequency: None,
+            sampling_frequencies: HashMap::new(),
+            block_track_number: None,
+            block_addition_mapping: (None, None),
+            itu_t35_block_add_ids: HashSet::from([hdr::WEBM_ITU_T_35_BLOCK_ADD_ID]),
+            block_add_id: 1,
+            generic_doctype: false,
+            limits: EbmlLimits::default(),
+            timestamp_scale: DEFAULT_TIMESTAMP_SCALE,
+            cluster_timestamp: None,
+            chap_process_codec_id: 0,
+            projection_type: None,
+            unknown_leaf_scanned: Cell::new(0),
+        }
+    }
+}
+
+impl Context {
+    fn decodes_payload(&self, id: &Id) -> bool {
+        match id {
+            Id::CodecPrivate => self.codec_id.as_deref().is_some_and(codecs::is_supported),
+            Id::BlockAdditional => self.itu_t35_block_add_ids.contains(&self.block_add_id),
+            Id::BlockAddIdExtraData => self
+                .block_addition_mapping
+                .1
+                .is_some_and(|value| hdr::DOLBY_VISION_BLOCK_ADD_ID_TYPES.contains(&value)),
+            Id::ChapProcessData => self.chap_process_codec_id == menus::DVD_CHAP_PROCESS_CODEC_ID,
+            Id::ProjectionPrivate => self.projection_type.is_some(),
+            _ => false,
+        }
+    }
+
+    fn decode_payload(&self, id: &Id, payload: &[u8]) -> Option<Binary> {
+        match id {
+            Id::CodecPrivate => {
+                let codec_id = self.codec_id.as_deref()?;
+                let codec_private = codecs::parse_codec_private(codec_id, payload).ok()??;
+                Some(Binary::CodecPrivate(codec_private))
+            }
+            Id::BlockAdditional => Some(Binary::ItuT35(hdr::parse_itu_t35(payload).ok()?.1)),
+            Id::BlockAddIdExtraData => Some(Binary::DolbyVision(
+                hdr::parse_dolby_vision_configuration(payload).ok()?.1,
+            )),
+            Id::ChapProcessData => Some(Binary::DvdCommands(
+                menus::parse_dvd_commands(payload).ok()?.1,
+            )),
+            Id::ProjectionPrivate => {
+                let projection_type = self.projection_type.as_ref()?;
+                let projection_private =
+                    projection::parse_projection_private(projection_type, payload).ok()??;
+                Some(Binary::Projection(projection_private))
+            }
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, element: &Element) {
+        match (&element.header.id, &element.body) {
+            (Id::TrackEntry, _) => {
+                self.codec_id = None;
+                self.track_number = None;
+                self.track_encrypted = false;
+                self.sampling_frequency = None;
+            }
+            (Id::BlockAdditionMapping, _) => self.block_addition_mapping = (None, None),
+            (Id::BlockAddIdValue, Body::Unsigned(value)) => {
+                self.block_addition_mapping.0 = Some(value.value());
+            }
+            (Id::BlockAddIdType, Body::Unsigned(value)) => {
+                self.block_addition_mapping.1 = Some(value.value());
+            }
+            (Id::BlockMore, _) => self.block_add_id = 1,
+            // A new stream, possibly with another DocType, starts afresh
+            (Id::Ebml, _) => *self = Self::default(),
+            // Values out of the range allowed by the EBML specification are ignored
+            (Id::EbmlMaxIdLength, Body::Unsigned(value)) if value.value() >= 4 => {
+                self.limits.max_id_length = usize::try_from(value.value()).unwrap_or(usize::MAX);
+            }
+            (Id::EbmlMaxSizeLength, Body::Unsigned(value)) if value.value() >= 1 => {
+                self.limits.max_size_length = usize::try_from(value.value()).unwrap_or(usize::MAX);
+            }
+            (Id::DocType, Body::String(doc_type)) => {
+                self.generic_doctype = !matches!(doc_type.as_str(), "matroska" | "webm");
+            }
+            (Id::Segment, _) => {
+                self.timestamp_scale = DEFAULT_TIMESTAMP_SCALE;
+                self.cluster_timestamp = None;
+            }
+            (Id::TimestampScale, Body::Unsigned(value)) => self.timestamp_scale = value.value(),
+            (Id::Cluster, _) => self.cluster_timestamp = None,
+            (Id::Timestamp, Body::Unsigned(value)) => self.cluster_timestamp = Some(value.value()),
+            (Id::BlockAddId, Body::Unsigned(value)) => self.block_add_id = value.value(),
+            (Id::ChapProcess, _) => self.chap_process_codec_id = 0,
+            (Id::Projection, _) => self.projection_type = None,
+            (Id::ProjectionType, Body::Unsigned(value)) => {
+                self.projection_type = ProjectionType::new(value.value());
+            }
+            (Id::ChapProcessCodecId, Body::Unsigned(value)) => {
+                self.chap_process_codec_id = value.value();
+            }
+            (Id::CodecId, Body::String(codec_id)) => self.codec_id = Some(codec_id.clone()),
+            (Id::TrackNumber, Body::Unsigned(track_number)) => {
+                self.track_number = usize::try_from(track_number.value()).ok();
+            }
+            (Id::ContentEncAlgo, Body::Unsigned(algorithm)) => {
+                self.track_encrypted = algorithm.value() == 5;
+            }
+            (Id::SamplingFrequency, Body::Float(frequency)) => {
+                self.sampling_frequency = Some(*frequency);
+            }
+            (Id::BlockGroup, _) => self.block_track_number = None,
+            (Id::Block, Body::Binary(binary)) => self.block_track_number = binary.track_number(),
+            _ => return,
+        }
+        if let (Some(track_number), true) = (self.track_number, self.track_encrypted) {
+            self.encrypted_tracks.insert(track_number);
+        }
+        if let (Some(track_number), Some(frequency)) = (self.track_number, self.sampling_frequency)
+        {
+            self.sampling_frequencies.insert(track_number, frequency);
+        }
+        if let (Some(value), Some(block_add_id_type)) = self.block_addition_mapping {
+            if block_add_id_type == hdr::ITU_T_35_BLOCK_ADD_ID_TYPE {
+                self.itu_t35_block_add_ids.insert(value);
+            } else {
+                self.itu_t35_block_add_ids.remove(&value);
+            }
+        }
+    }
+}
+
+// A Master element whose children are still being parsed
+#[derive(Debug, Clone)]
+pub(crate) struct OpenMaster {
+    pub(crate) id: Id,
+    pub(crate) start: usize,
+    // Where the Master element ends, if its size is known
+    pub(crate) end: Option<usize>,
+}
+
+// The Master elements open at the position of the last parsed element,
+// from the outermost one
+#[derive(Debug, Default, Clone)]
+pub(crate) struct OpenMasters(Vec<OpenMaster>);
+
+impl OpenMasters {
+    // Close the Master elements that can't contain an element starting at
+    // a position: the ones ending before it, and the ones with unknown size
+    // that it can't be a child of
+    pub(crate) fn close_before(&mut self, id: &Id, position: usize) {
+        // A new stream closes all elements of the previous one
+        if *id == Id::Ebml {
+            self.0.clear();
+        }
+        while let Some(master) = self.0.last() {
+            let is_closed = match master.end {
+                Some(end) => position >= end,
+                None => !id.can_be_children_of(&master.id),
+            };
+            if !is_closed {
+                break;
+            }
+            self.0.pop();
+        }
+    }
+
+    // Open a Master element, after the ones it is in were kept open by
+    // `close_before()`
+    pub(crate) fn open(&mut self, header: &Header, position: usize) {
+        self.0.push(OpenMaster {
+            id: header.id.clone(),
+            start: position,
+            end: header.size.map(|size| position + size),
+        });
+    }
+}
+
+impl std::ops::Deref for OpenMasters {
+    type Target = [OpenMaster];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// A Block held back, along with the elements that follow it, until the end
+// of its BlockGroup so that its frame type is known from the ReferenceBlock
+// elements
+struct HeldBlockGroup {
+    // Index of the BlockGroup in the open Master elements
+    depth: usize,
+    start: usize,
+    references: Vec<i64>,
+    elements: Vec<Element>,
+}
+
+/// Input-agnostic parsing state shared by the different element readers.
+///
+/// Bytes are pushed in with `feed()` and complete elements are pulled out
+/// with `next_element()`. Large binary bodies are never buffered: the
+/// bytes are discarded as they are fed, or a reader that supports seeking
+/// can skip over them with `take_skip()`.
+#[derive(Default)]
+pub(crate) struct Decoder {
+    options: ParserOptions,
+    buffer: Vec<u8>,
+    start: usize,
+    position: usize,
+    skip: usize,
+    is_corrupt: bool,
+    open_masters: OpenMasters,
+    context: Context,
+    pending_corrupt: Option<Element>,
+    queued: VecDeque<Element>,
+    block_group: Option<HeldBlockGroup>,
+    // Number of EBML headers found so far
+    streams: usize,
+    truncated: Option<Element>,
+    needed: Option<NonZeroUsize>,
+    finished: bool,
+    failed: bool,
+}
+
+impl Decoder {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn set_options(&mut self, options: ParserOptions) {
+        self.options = options;
+    }
+
+    pub(crate) fn feed(&mut self, mut data: &[u8]) {
+        let skipped = self.skip.min(data.len());
+        self.skip -= skipped;
+        data = &data[skipped..];
+        self.needed = self
+            .needed
+            .and_then(|needed| NonZeroUsize::new(needed.get().saturating_sub(data.len())));
+
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Restart parsing from a new position of the input. Track information
+    /// gathered so far is kept to decode blocks.
+    pub(crate) fn reset(&mut self, position: usize) {
+        *self = Self {
+            position,
+            context: std::mem::take(&mut self.context),
+            ..Self::new(std::mem::take(&mut self.options))
+        };
+    }
+
+    /// Number of bytes to feed before the next element can be complete, if
+    /// known. Bytes to be skipped are included.
+    pub(crate) fn needed(&self) -> Option<NonZeroUsize> {
+        self.needed.and_then(|needed| needed.checked_add(self.skip))
+    }
+
+    /// Size of the next read from the input, so that the next element can
+    /// be completed at once where possible
+    pub(crate) fn chunk_size(&self) -> usize {
+        self.needed.map_or(DEFAULT_BUFFER_SIZE, |needed| {
+            needed.get().clamp(DEFAULT_BUFFER_SIZE, MAX_BUFFER_SIZE)
+        })
+    }
+
+    /// Number of bytes that belong to an already emitted element and should
+    /// be skipped by the caller instead of being fed.
+    pub(crate) fn take_skip(&mut self) -> usize {
+        std::mem::take(&mut self.skip)
+    }
+
+    /// Create a decoder for the input from a given position, with the same
+    /// options and with the state gathered so far. It's meant to parse the
+    /// body of the last emitted Master element separately.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn fork(&self, position: usize) -> Self {
+        Self {
+            position,
+            context: self.context.clone(),
+            open_masters: self.open_masters.clone(),
+            streams: self.streams,
+            ..Self::new(self.options.clone())
+        }
+    }
+
+    /// Skip the body of the last emitted Master element, e.g. after it has
+    /// been forked to be parsed separately.
+    pub(crate) fn skip_body(&mut self, body_size: usize) {
+        let skipped = body_size.min(self.buffer.len() - self.start);
+        self.start += skipped;
+        self.position += body_size;
+        self.skip += body_size - skipped;
+    }
+
+    // Nesting level of an element, i.e. the number of Master elements still
+    // open at its position. The element path and level are also set here if
+    // requested.
+    fn level(&mut self, element: &mut Element) -> usize {
+        let position = element.header.position.unwrap_or_default();
+        self.open_masters.close_before(&element.header.id, position);
+        let level = self.open_masters.len();
+        if self.options.element_paths {
+            let path = self
+                .open_masters
+                .iter()
+                .map(|master| &master.id)
+                .chain([&element.header.id])
+                .map(|id| format!("\\{id}"))
+                .collect();
+            element.header.path = Some(path);
+        }
+        if self.options.element_levels {
+            element.header.level = Some(level);
+        }
+        if self.options.deprecated_elements {
+            element.header.deprecated = element.header.id.is_deprecated();
+        }
+        if let Body::Master = element.body {
+            self.open_masters.open(&element.header, position);
+        }
+        level
+    }
+
+    // Attach the position of malformed data to an error, along with the
+    // element found there and the Master element it is in, if known
+    fn malformed(&self, position: usize, id: Option<Id>, error: Error) -> Error {
+        let parent = self
+            .open_masters
+            .iter()
+            .rev()
+            .find(|master| master.start < position && master.end.is_none_or(|end| position < end))
+            .map(|master| master.id.clone());
+        Error::Malformed {
+            position,
+            id,
+            parent,
+            error: Box::new(error),
+        }
+    }
+
+    fn decode(&mut self) -> Result<Element> {
+        let input = &self.buffer[self.start..];
+        let position = self.position;
+        let (
+            remaining,
+            ShortParsed {
+                mut element,
+                bytes_to_be_skipped,
+            },
+        ) = parse_short_or_corrupt(input, &mut self.is_corrupt, &self.options, &self.context)
+            .map_err(|e| match e {
+                // Missing data is only an error once the input has ended
+                Error::NeedData(_) => e,
+                e => self.malformed(position, parse_id(input).ok().map(|(_, id)| id), e),
+            })?;
+
+        let consumed = input.len() - remaining.len();
+        if remaining.len() >= bytes_to_be_skipped {
+            // If the binary body is already in our buffer, just skip in
+            // the buffer
+            self.start += consumed + bytes_to_be_skipped;
+        } else {
+            // Else, drop the remaining bytes in the buffer and skip the rest
+            // as it is fed.
+            self.skip = bytes_to_be_skipped - remaining.len();
+            self.start = self.buffer.len();
+        }
+
+        self.context.update(&element);
+
+        element.header.position = Some(self.position);
+        element.header.language = element_language(&element);
+        element.header.discard_padding = DiscardPadding::new(
+            &element,
+            self.context
+                .block_track_number
+                .and_then(|track_number| self.context.sampling_frequencies.get(&track_number))
+                .copied(),
+        );
+        if let Body::Binary(ref mut binary) = element.body {
+            binary.offset_frames(self.position + element.header.header_size);
+            if let Some(cluster_timestamp) = self.context.cluster_timestamp {
+                binary.set_absolute_time(cluster_timestamp, self.context.timestamp_scale);
+            }
+        }
+        let consumed_size = if let Body::Master = element.body {
+            element.header.header_size
+        } else {
+            // It's safe to unwrap because all non-Master elements have a set size
+            element.header.size.unwrap()
+        };
+        self.position += consumed_size;
+
+        if element.header.id != Id::corrupted() {
+            let level = self.level(&mut element);
+            if self
+                .options
+                .max_depth
+                .is_some_and(|max_depth| level > max_depth)
+            {
+                if !self.options.recover_corruption {
+                    return Err(self.malformed(
+                        position,
+                        Some(element.header.id),
+                        Error::MaxDepthExceeded,
+                    ));
+                }
+                element = Element {
+                    header: Header {
+                        position: element.header.position,
+                        ..Header::new(Id::corrupted(), 0, consumed_size)
+                    },
+                    body: Body::Binary(Binary::Corrupted),
+                };
+            }
+        }
+
+        if self.options.skip_clusters && element.header.id == Id::Cluster {
+            if let Some(body_size) = element.header.body_size {
+                self.skip_body(body_size);
+            }
+        }
+
+        Ok(element)
+    }
+
+    // A marker to emit before an element if it starts a new stream, i.e. if
+    // it is an EBML header other than the first one
+    fn stream_boundary(&mut self, element: &Element) -> Option<Element> {
+        if element.header.id != Id::Ebml {
+            return None;
+        }
+        self.streams += 1;
+        let offset = element.header.position?;
+        (self.streams > 1).then(|| Element {
+            header: Header {
+                position: Some(offset),
+                ..Header::new(Id::StreamBoundary, 0, 0)
+            },
+            body: Body::Binary(Binary::StreamBoundary(StreamBoundary {
+                stream: self.streams - 1,
+                offset,
+            })),
+        })
+    }
+
+    pub(crate) fn next_element(&mut self) -> Option<Result<Element>> {
+        if let Some(element) = self.queued.pop_front() {
+            return Some(Ok(element));
+        }
+        if self.failed {
+            return None;
+        }
+        loop {
+            match self.decode() {
+                Ok(element) if element.header.id == Id::corrupted() => {
+                    merge_corrupt_element(&mut self.pending_corrupt, element);
+                }
+                Ok(element) => {
+                    let boundary = self.stream_boundary(&element);
+                    let is_open = |group: &HeldBlockGroup| {
+                        self.open_masters
+                            .get(group.depth)
+                            .is_some_and(|master| master.start == group.start)
+                    };
+                    if !self.block_group.as_ref().is_none_or(is_open) {
+                        self.release_block_group();
+                    }
+                    if self.block_group.is_none() && element.header.id == Id::Block {
+                        self.hold_block_group();
+                    }
+                    let elements = self
+                        .pending_corrupt
+                        .take()
+                        .into_iter()
+                        .chain(boundary)
+                        .chain([element]);
+                    match &mut self.block_group {
+                        Some(group) => {
+                            for element in elements {
+                                if let (Id::ReferenceBlock, Body::Signed(reference)) =
+                                    (&element.header.id, &element.body)
+                                {
+                                    group.references.push(*reference);
+                                }
+                                group.elements.push(element);
+                            }
+                        }
+                        None => self.queued.extend(elements),
+                    }
+                    if let Some(element) = self.queued.pop_front() {
+                        return Some(Ok(element));
+                    }
+                }
+                Err(Error::NeedData(needed)) => {
+                    self.needed = needed;
+                    break;
+                }
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        if self.finished {
+            if !self.options.recover_corruption && self.start < self.buffer.len() {
+                self.failed = true;
+                let id = parse_id(&self.buffer[self.start..]).ok().map(|(_, id)| id);
+                let error = Error::NeedData(self.needed);
+                return Some(Err(self.malformed(self.position, id, error)));
+            }
+            return self
+                .pending_corrupt
+                .take()
+                .or_else(|| self.truncated.take())
+                .map(Ok);
+        }
+        None
+    }
+
+    // Start holding back elements if a Block was just found in a BlockGroup
+    fn hold_block_group(&mut self) {
+        let depth = self.open_masters.len().saturating_sub(1);
+        self.block_group = self
+            .open_masters
+            .last()
+            .filter(|master| master.id == Id::BlockGroup)
+            .map(|master| HeldBlockGroup {
+                depth,
+                start: master.start,
+                references: Vec::new(),
+                elements: Vec::new(),
+            });
+    }
+
+    // Emit the elements held back since the Block of a BlockGroup, now that
+    // all its ReferenceBlock elements are known
+    fn release_block_group(&mut self) {
+        let Some(group) = self.block_group.take() else {
+            return;
+        };
+        self.queued
+            .extend(group.elements.into_iter().map(|mut element| {
+                if let Body::Binary(ref mut binary) = element.body {
+                    binary.set_frame_type(&group.references);
+                }
+                element
+            }));
+    }
+
+    /// Signal that the input has ended. Any bytes left unparsed are
+    /// emitted as a final `Truncated` element by `next_element()` if they
+    /// start an element that extends past the end, or as a corrupt element
+    /// otherwise. They are an error if corruption recovery is disabled.
+    pub(crate) fn finish(&mut self) {
+        self.release_block_group();
+        let remaining = self.buffer.len() - self.start;
+        if remaining > 0 && self.options.recover_corruption {
+            let truncated = if self.is_corrupt {
+                None
+            } else {
+                parse_truncated(&self.buffer[self.start..], &self.context.limits)
+            };
+            match truncated {
+                Some(truncated) => {
+                    self.truncated = Some(Element {
+                        header: Header {
+                            position: Some(self.position),
+                            ..Header::new(Id::Truncated, 0, remaining)
+                        },
+                        body: Body::Binary(Binary::Truncated(truncated)),
+                    })
+                }
+                None => merge_corrupt_element(
+                    &mut self.pending_corrupt,
+                    Element {
+                        header: Header {
+                            position: Some(self.position),
+                            ..Header::new(Id::corrupted(), 0, remaining)
+                        },
+                        body: Body::Binary(Binary::Corrupted),
+                    },
+                ),
+            }
+            self.position += remaining;
+            self.start = self.buffer.len();
+        }
+        self.finished = true;
+    }
+}
+
+type SkipFn<R> = fn(&mut R, usize) -> std::io::Result<()>;
+
+fn skip_by_reading<R: Read>(reader: &mut R, count: usize) -> std::io::Result<()> {
+    std::io::copy(&mut reader.take(count as u64), &mut std::io::sink())?;
+    Ok(())
+}
+
+fn skip_by_seeking<R: Seek>(reader: &mut R, count: usize) -> std::io::Result<()> {
+    reader.seek(SeekFrom::Current(count as i64))?;
+    Ok(())
+}
+
+/// An iterator that lazily parses elements from a reader.
+///
+/// Buffering and incomplete inputs are handled internally. Corrupt regions
+/// are reported as a single `Corrupted` element each, and every element
+/// has its `position` set to its offset from the start of the reader.
+pub struct ElementIterator<R> {
+    reader: R,
+    decoder: Decoder,
+    chunk: Vec<u8>,
+    skip: SkipFn<R>,
+    finished: bool,
+}
+
+impl<R: Read> ElementIterator<R> {
+    /// Create an iterator from a reader. Bytes from binary bodies that are
+    /// not needed are read and discarded.
+    pub fn new(reader: R) -> Self {
+        Self::with_skip(reader, skip_by_reading)
+    }
+
+    fn with_skip(reader: R, skip: SkipFn<R>) -> Self {
+        Self {
+            reader,
+            decoder: Decoder::default(),
+            chunk: vec![0; DEFAULT_BUFFER_SIZE],
+            skip,
+            finished: false,
+        }
+    }
+
+    /// Use the given options for parsing
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.decoder.set_options(options);
+        self
+    }
+
+    /// Consume the iterator, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Skip the body of the last emitted element, which must be a Master
+    /// element with this body size, e.g. a Cluster that isn't needed.
+    pub fn skip_body(&mut self, body_size: usize) {
+        self.decoder.skip_body(body_size);
+    }
+}
+
+impl<R: Read + Seek> ElementIterator<R> {
+    /// Create an iterator from a seekable reader. Bytes from binary bodies
+    /// that are not needed are seeked over instead of being read.
+    pub fn seekable(reader: R) -> Self {
+        Self::with_skip(reader, skip_by_seeking)
+    }
+
+    /// Resume parsing from an absolute position of the reader, e.g. the
+    /// start of a Cluster found from the Cues.
+    pub fn seek(&mut self, position: u64) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(position))?;
+        self.decoder.reset(position.try_into()?);
+        self.finished = false;
+        Ok(())
+    }
+
+    /// Parse the element at an absolute position of the reader, along with
+    /// all its descendants if it is a Master element.
+    ///
+    /// Master elements with unknown size end at the next top-level element.
+    pub fn read_element_at(&mut self, position: u64) -> Result<Vec<Element>> {
+        self.seek(position)?;
+        let Some(first) = self.next().transpose()? else {
+            return Ok(Vec::new());
+        };
+        let end = match first.body {
+            Body::Master => first.header.size.map(|size| position as usize + size),
+            _ => Some(position as usize + first.header.size.unwrap_or_default()),
+        };
+
+        let mut elements = vec![first];
+        if end.is_some_and(|end| self.decoder.position >= end) {
+            return Ok(elements);
+        }
+        for element in self.by_ref() {
+            let element = element?;
+            let is_past_end = match end {
+                Some(end) => element.header.position.unwrap_or_default() >= end,
+                None => SYNC_ELEMENT_IDS.contains(&element.header.id),
+            };
+            if is_past_end {
+                break;
+            }
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+}
+
+impl<R: Read> Iterator for ElementIterator<R> {
+    type Item = Result<Element>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.decoder.next_element() {
+                return Some(element);
+            }
+            if self.finished {
+                return None;
+            }
+
+            let skip = self.decoder.take_skip();
+            if skip > 0 {
+                if let Err(e) = (self.skip)(&mut self.reader, skip) {
+                    self.finished = true;
+                    return Some(Err(e.into()));
+                }
+            }
+
+            self.chunk.resize(self.decoder.chunk_size(), 0);
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => {
+                    self.finished = true;
+                    self.decoder.finish();
+                }
+                Ok(num_read) => self.decoder.feed(&self.chunk[..num_read]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{FrameType, Unsigned};
+
+    const EBML_HEADER: &[u8] = &[
+        0x1A, 0x45, 0xDF, 0xA3, 0x9F, 0x42, 0x86, 0x81, 0x01, 0x42, 0xF7, 0x81, 0x01, 0x42, 0xF2,
+        0x81, 0x04, 0x42, 0xF3, 0x81, 0x08, 0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D, 0x42, 0x87,
+        0x81, 0x04, 0x42, 0x85, 0x81, 0x02,
+    ];
+
+    // A reader that only returns one byte per read() call
+    struct ByteByByte<'a>(&'a [u8]);
+
+    impl Read for ByteByByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn sequential_corrupt_elements() {
+        let mut pending = None;
+        let example_element = Element {
+            header: Header::new(Id::corrupted(), 0, 4),
+            body: Body::Binary(Binary::Corrupted),
+        };
+        merge_corrupt_element(&mut pending, example_element.clone());
+        merge_corrupt_element(&mut pending, example_element);
+
+        assert_eq!(
+            pending,
+            Some(Element {
+                header: Header::new(Id::corrupted(), 0, 8),
+                body: Body::Binary(Binary::Corrupted),
+            })
+        )
+    }
+
+    #[test]
+    fn iterate_elements() {
+        let elements = ElementIterator::new(ByteByByte(EBML_HEADER))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(elements.len(), 8);
+        assert_eq!(
+            elements[1],
+            Element {
+                header: Header {
+                    position: Some(5),
+                    ..Header::new(Id::EbmlVersion, 3, 1)
+                },
+                body: Body::Unsigned(Unsigned::Standard(1)),
+            }
+        );
+        assert_eq!(elements[7].header.position, Some(32));
+    }
+
+    #[test]
+    fn skip_binary_bodies() {
+        // Void with a 16-bytes body followed by a DocType
+        let mut input = vec![0xEC, 0x90];
+        input.extend_from_slice(&[0; 16]);
+        input.extend_from_slice(&[0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D]);
+
+        for elements in [
+            ElementIterator::new(ByteByByte(&input)).collect::<Result<Vec<_>>>(),
+            ElementIterator::seekable(Cursor::new(&input)).collect::<Result<Vec<_>>>(),
+        ] {
+            let elements = elements.unwrap();
+            assert_eq!(elements.len(), 2);
+            assert_eq!(elements[0].body, Body::Binary(Binary::Void(16)));
+            assert_eq!(elements[1].body, Body::String("webm".to_string()));
+            assert_eq!(elements[1].header.position, Some(18));
+        }
+    }
+
+    #[test]
+    fn trailing_corrupt_element() {
+        let mut input = vec![0x42, 0x87, 0x90, 0x01];
+        input.extend_from_slice(EBML_HEADER);
+        input.extend_from_slice(&[0x00, 0x87]);
+
+        let elements = ElementIterator::new(Cursor::new(&input))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(elements.len(), 10);
+        assert_eq!(
+            elements[0],
+            Element {
+                header: Header {
+                    position: Some(0),
+                    ..Header::new(Id::corrupted(), 0, 4)
+                },
+                body: Body::Binary(Binary::Corrupted),
+            }
+        );
+        assert_eq!(
+            elements[9],
+            Element {
+                header: Header {
+                    position: Some(40),
+                    ..Header::new(Id::corrupted(), 0, 2)
+                },
+                body: Body::Binary(Binary::Corrupted),
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_truncated_element() {
+        // DocType with a 4-bytes body, of which only 2 bytes are present
+        let mut input = EBML_HEADER.to_vec();
+        input.extend_from_slice(&[0x42, 0x82, 0x84, 0x77, 0x65]);
+
+        let elements = ElementIterator::new(ByteByByte(&input))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(elements.len(), 9);
+        assert_eq!(
+            elements[8],
+            Element {
+                header: Header {
+                    position: Some(36),
+                    ..Header::new(Id::Truncated, 0, 5)
+                },
+                body: Body::Binary(Binary::Truncated(Truncated {
+                    id: Id::DocType,
+                    declared_size: Some(7),
+                    available: 5,
+                })),
+            }
+        );
+
+        // The size of the element is missing as well
+        let elements = ElementIterator::new(Cursor::new(&input[..38]))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            elements[8].body,
+            Body::Binary(Binary::Truncated(Truncated {
+                id: Id::DocType,
+                declared_size: None,
+                available: 2,
+            }))
+        );
+    }
+
+    #[test]
+    fn iterate_with_options() {
+        let options = ParserOptions {
+            max_depth: Some(0),
+            ..ParserOptions::default()
+        };
+        let elements = ElementIterator::new(Cursor::new(EBML_HEADER))
+            .with_options(options)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        // Children of the EBML header are too deep and get reported as corrupt
+        assert_eq!(elements.len(), 2);
+        assert_eq!(
+            elements[1],
+            Element {
+                header: Header {
+                    position: Some(5),
+                    ..Header::new(Id::corrupted(), 0, 31)
+                },
+                body: Body::Binary(Binary::Corrupted),
+            }
+        );
+
+        let mut input = EBML_HEADER.to_vec();
+        input.extend_from_slice(&[0x42, 0x87]);
+        let options = ParserOptions {
+            recover_corruption: false,
+            ..ParserOptions::default()
+        };
+        let elements = ElementIterator::new(Cursor::new(&input))
+            .with_options(options.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(elements.len(), 9);
+        assert_eq!(
+            elements[8],
+            Err(Error::Malformed {
+                position: 36,
+                id: Some(Id::DocTypeVersion),
+                parent: None,
+                error: Box::new(Error::NeedData(NonZeroUsize::new(1))),
+            })
+        );
+
+        // DocTypeVersion with a 9-bytes body
+        let mut input = EBML_HEADER.to_vec();
+        input.extend_from_slice(&[0x42, 0x87, 0x89]);
+        input.extend_from_slice(&[0; 9]);
+        let elements = ElementIterator::new(Cursor::new(&input))
+            .with_options(options)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            elements[8],
+            Err(Error::Malformed {
+                position: 36,
+                id: Some(Id::DocTypeVersion),
+                parent: None,
+                error: Box::new(Error::ForbiddenIntegerSize),
+            })
+        );
+        assert_eq!(
+            elements[8].as_ref().unwrap_err().to_string(),
+            "malformed DocTypeVersion at offset 0x24: forbidden integer size"
+        );
+    }
+
+    #[test]
+    fn decode_encrypted_blocks() {
+        // Tracks with an AES-encrypted track 1, followed by one of its blocks
+        const INPUT: &[u8] = &[
+            0x16, 0x54, 0xAE, 0x6B, 0x92, 0xAE, 0x90, 0xD7, 0x81, 0x01, 0x6D, 0x80, 0x8A, 0x62,
+            0x40, 0x87, 0x50, 0x35, 0x84, 0x47, 0xE1, 0x81, 0x05, 0xA3, 0x8F, 0x81, 0x00, 0x00,
+            0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2A, 0xFF, 0xFF,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let Body::Binary(Binary::SimpleBlock(ref block)) = elements.last().unwrap().body else {
+            panic!("expected a SimpleBlock");
+        };
+        assert_eq!(
+            block.encryption,
+            Some(crate::BlockEncryption {
+                status: crate::EncryptionStatus::Encrypted,
+                iv: Some("000000000000002a".into()),
+                partitions: None,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_dvd_commands() {
+        // ChapProcess with the DVD command set and a single command
+        const INPUT: &[u8] = &[
+            0x69, 0x44, 0x97, 0x69, 0x55, 0x81, 0x01, 0x69, 0x11, 0x90, 0x69, 0x22, 0x81, 0x01,
+            0x69, 0x33, 0x89, 0x01, 0x30, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            elements.last().unwrap().body,
+            Body::Binary(Binary::DvdCommands(
+                vec!["[30 02 00 00 00 01 00 00]".into()]
+            ))
+        );
+    }
+
+    #[test]
+    fn decode_projection_private() {
+        // Projection with a cubemap ProjectionType and 4 pixels of padding
+        const INPUT: &[u8] = &[
+            0x76, 0x70, 0x93, 0x76, 0x71, 0x81, 0x02, 0x76, 0x72, 0x8C, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            elements.last().unwrap().body,
+            Body::Binary(Binary::Projection(projection::ProjectionPrivate::Cubemap(
+                projection::CubemapProjection {
+                    version: 0,
+                    flags: 0,
+                    layout: 0,
+                    padding: 4,
+                }
+            )))
+        );
+    }
+
+    #[test]
+    fn silent_tracks_in_cluster() {
+        // Cluster with a Timestamp and SilentTracks for tracks 1 and 2
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x8E, 0xE7, 0x81, 0x00, 0x58, 0x54, 0x88, 0x58, 0xD7, 0x81,
+            0x01, 0x58, 0xD7, 0x81, 0x02,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let silent_tracks = elements
+            .iter()
+            .map(|element| (&element.header.id, &element.body))
+            .skip(2)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            silent_tracks,
+            [
+                (&Id::SilentTracks, &Body::Master),
+                (
+                    &Id::SilentTrackNumber,
+                    &Body::Unsigned(Unsigned::Standard(1))
+                ),
+                (
+                    &Id::SilentTrackNumber,
+                    &Body::Unsigned(Unsigned::Standard(2))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn absolute_block_times() {
+        // Segment with a TimestampScale of 500000, followed by a Cluster with
+        // Timestamp 10 and a SimpleBlock with relative timestamp -2
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x15, 0x49,
+            0xA9, 0x66, 0x87, 0x2A, 0xD7, 0xB1, 0x83, 0x07, 0xA1, 0x20, 0x1F, 0x43, 0xB6, 0x75,
+            0x8A, 0xE7, 0x81, 0x0A, 0xA3, 0x85, 0x81, 0xFF, 0xFE, 0x80, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let Body::Binary(Binary::SimpleBlock(ref block)) = elements.last().unwrap().body else {
+            panic!("expected a SimpleBlock");
+        };
+        assert_eq!(block.absolute_time_ns, Some(4_000_000));
+        assert_eq!(block.absolute_time.as_deref(), Some("00:00:00.004"));
+    }
+
+    #[test]
+    fn parse_generic_doctype() {
+        // EBML header with DocType "foo", followed by an element containing
+        // another one, and by an element with the ID of Info and an opaque body
+        const INPUT: &[u8] = &[
+            0x1A, 0x45, 0xDF, 0xA3, 0x86, 0x42, 0x82, 0x83, 0x66, 0x6F, 0x6F, 0x1A, 0x2B, 0x3C,
+            0x4D, 0x84, 0x81, 0x82, 0x00, 0x01, 0x15, 0x49, 0xA9, 0x66, 0x83, 0x61, 0x62, 0x63,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let element = |header: Header, position, body| Element {
+            header: Header {
+                position: Some(position),
+                ..header
+            },
+            body,
+        };
+        assert_eq!(
+            elements[2..],
+            [
+                element(Header::new(Id::Unknown(0x1A2B3C4D), 5, 4), 11, Body::Master),
+                element(
+                    Header::new(Id::Unknown(0x81), 2, 2),
+                    16,
+                    Body::Binary(Binary::Standard("[00 01]".into()))
+                ),
+                element(
+                    Header::new(Id::Unknown(0x1549A966), 5, 3),
+                    20,
+                    Body::Binary(Binary::Standard("[61 62 63]".into()))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn ebml_header_limits() {
+        // EBML header allowing 5-bytes IDs and 2-bytes sizes, followed by an
+        // element with a 5-bytes ID and another one with a 3-bytes size
+        const INPUT: &[u8] = &[
+            0x1A, 0x45, 0xDF, 0xA3, 0x8E, 0x42, 0xF2, 0x81, 0x05, 0x42, 0xF3, 0x81, 0x02, 0x42,
+            0x82, 0x83, 0x66, 0x6F, 0x6F, 0x08, 0x11, 0x22, 0x33, 0x44, 0x40, 0x02, 0xAB, 0xCD,
+            0x81, 0x20, 0x00, 0x01, 0xEF,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            elements[4..],
+            [
+                Element {
+                    header: Header {
+                        position: Some(19),
+                        ..Header::new(Id::Unknown(0x0811223344), 7, 2)
+                    },
+                    body: Body::Binary(Binary::Standard("[ab cd]".into())),
+                },
+                Element {
+                    header: Header {
+                        position: Some(28),
+                        ..Header::new(Id::corrupted(), 0, 5)
+                    },
+                    body: Body::Binary(Binary::Corrupted),
+                },
+            ]
+        );
+
+        // IDs longer than 4 bytes are invalid by default
+        let mut input = EBML_HEADER.to_vec();
+        input.extend_from_slice(&INPUT[19..28]);
+        let elements = ElementIterator::new(input.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(elements[8].header.id, Id::corrupted());
+    }
+
+    #[test]
+    fn stream_boundaries() {
+        // A Segment whose size covers the EBML header of the next stream
+        let mut input = EBML_HEADER.to_vec();
+        input.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0x88, 0x15, 0x49, 0xA9, 0x66, 0x80]);
+        input.extend_from_slice(EBML_HEADER);
+
+        let options = ParserOptions {
+            element_levels: true,
+            ..ParserOptions::default()
+        };
+        let elements = ElementIterator::new(input.as_slice())
+            .with_options(options)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(elements.len(), 19);
+        assert_eq!(
+            elements[10],
+            Element {
+                header: Header {
+                    position: Some(46),
+                    ..Header::new(Id::StreamBoundary, 0, 0)
+                },
+                body: Body::Binary(Binary::StreamBoundary(StreamBoundary {
+                    stream: 1,
+                    offset: 46,
+                })),
+            }
+        );
+        assert_eq!(elements[11].header.id, Id::Ebml);
+        assert_eq!(elements[11].header.level, Some(0));
+        assert_eq!(elements[12].header.level, Some(1));
+
+        let trees = crate::tree::build_element_trees(&elements);
+        assert_eq!(trees.len(), 4);
+    }
+
+    #[test]
+    fn block_frame_types() {
+        // A Cluster with a BlockGroup without ReferenceBlock, one with a
+        // ReferenceBlock to the past, and one with references to the past
+        // and the future
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0xA1, 0xA0, 0x86, 0xA1, 0x84, 0x81, 0x00, 0x00, 0x00, 0xA0,
+            0x89, 0xA1, 0x84, 0x81, 0x00, 0x01, 0x00, 0xFB, 0x81, 0xFF, 0xA0, 0x8C, 0xA1, 0x84,
+            0x81, 0x00, 0x02, 0x00, 0xFB, 0x81, 0xFE, 0xFB, 0x81, 0x01,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            elements
+                .iter()
+                .map(|element| element.header.id.clone())
+                .collect::<Vec<_>>(),
+            [
+                Id::Cluster,
+                Id::BlockGroup,
+                Id::Block,
+                Id::BlockGroup,
+                Id::Block,
+                Id::ReferenceBlock,
+                Id::BlockGroup,
+                Id::Block,
+                Id::ReferenceBlock,
+                Id::ReferenceBlock,
+            ]
+        );
+        let frame_types = elements
+            .iter()
+            .filter_map(|element| match &element.body {
+                Body::Binary(Binary::Block(block)) => block.frame_type(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(frame_types, [FrameType::I, FrameType::P, FrameType::B]);
+    }
+
+    #[test]
+    fn discard_padding() {
+        // An audio track at 48 kHz and a BlockGroup of it discarding 6.5 ms
+        const INPUT: &[u8] = &[
+            0x16, 0x54, 0xAE, 0x6B, 0x8D, 0xAE, 0x8B, 0xD7, 0x81, 0x01, 0xE1, 0x86, 0xB5, 0x84,
+            0x47, 0x3B, 0x80, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x8E, 0xA0, 0x8C, 0xA1, 0x84, 0x81,
+            0x00, 0x00, 0x00, 0x75, 0xA2, 0x83, 0x63, 0x2E, 0xA0,
+        ];
+        let element = ElementIterator::new(INPUT)
+            .map(Result::unwrap)
+            .find(|element| element.header.id == Id::DiscardPadding)
+            .unwrap();
+        assert_eq!(
+            element.header.discard_padding,
+            Some(DiscardPadding {
+                duration_ns: 6_500_000,
+                samples: Some(312),
+            })
+        );
+
+        // The track is unknown without the Tracks
+        let element = ElementIterator::new(&INPUT[18..])
+            .map(Result::unwrap)
+            .find(|element| element.header.id == Id::DiscardPadding)
+            .unwrap();
+        assert_eq!(element.header.discard_padding.unwrap().samples, None);
+    }
+
+    #[test]
+    fn element_paths() {
+        let options = ParserOptions {
+            element_paths: true,
+            ..ParserOptions::default()
+        };
+        let paths = ElementIterator::new(EBML_HEADER)
+            .with_options(options)
+            .map(|element| element.unwrap().header.path.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths[0], "\\EBML");
+        assert_eq!(paths[6], "\\EBML\\DocTypeVersion");
+    }
+
+    #[test]
+    fn element_levels() {
+        let options = ParserOptions {
+            element_levels: true,
+            ..ParserOptions::default()
+        };
+        let levels = ElementIterator::new(EBML_HEADER)
+            .with_options(options)
+            .map(|element| element.unwrap().header.level.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(levels, [0, 1, 1, 1, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn deprecated_elements() {
+        // BlockGroup with a BlockVirtual
+        const INPUT: &[u8] = &[0xA0, 0x83, 0xA2, 0x81, 0x00];
+        let options = ParserOptions {
+            deprecated_elements: true,
+            ..ParserOptions::default()
+        };
+        let deprecated = ElementIterator::new(INPUT)
+            .with_options(options)
+            .map(|element| element.unwrap().header.deprecated)
+            .collect::<Vec<_>>();
+        assert_eq!(deprecated, [false, true]);
+    }
+
+    #[test]
+    fn skip_clusters() {
+        // Two Clusters with a Timestamp each, followed by a Cues
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7, 0x81, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7,
+            0x81, 0x0A, 0x1C, 0x53, 0xBB, 0x6B, 0x80,
+        ];
+        let options = ParserOptions {
+            skip_clusters: true,
+            ..ParserOptions::default()
+        };
+        let elements = ElementIterator::new(INPUT)
+            .with_options(options)
+            .map(|element| {
+                let header = element.unwrap().header;
+                (header.id, header.position.unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            elements,
+            [(Id::Cluster, 0), (Id::Cluster, 8), (Id::Cues, 16)]
+        );
+    }
+    #[test]
+    fn skip_body() {
+        // Same input, where only the body of the first Cluster is skipped
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7, 0x81, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7,
+            0x81, 0x0A, 0x1C, 0x53, 0xBB, 0x6B, 0x80,
+        ];
+        let mut iterator = ElementIterator::new(INPUT);
+        let cluster = iterator.next().unwrap().unwrap();
+        iterator.skip_body(cluster.header.body_size.unwrap());
+        let elements = iterator
+            .map(|element| {
+                let header = element.unwrap().header;
+                (header.id, header.position.unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            elements,
+            [(Id::Cluster, 8), (Id::Timestamp, 13), (Id::Cues, 16)]
+        );
+    }
+}
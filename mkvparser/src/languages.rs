@@ -0,0 +1,319 @@
+//! Normalization of language elements
+//!
+//! Matroska stores languages either as ISO 639-2 codes, such as `ger` or
+//! `deu` in Language, or as BCP 47 tags, such as `de-DE` in LanguageBCP47.
+//! Both forms are normalized here to the canonical BCP 47 tag, along with the
+//! English name of the language, so that they can be compared at a glance.
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{elements::Id, Body, Element};
+
+/// A normalized language tag
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Language {
+    /// Canonical BCP 47 tag, such as `de-DE`
+    pub bcp47: String,
+    /// English name of the language, if known
+    pub name: Option<&'static str>,
+}
+
+// Shortest BCP 47 subtag, ISO 639-2 codes (bibliographic and terminologic,
+// when they differ) and English name of languages
+const LANGUAGES: &[(&str, &[&str], &str)] = &[
+    ("aa", &["aar"], "Afar"),
+    ("ab", &["abk"], "Abkhazian"),
+    ("ae", &["ave"], "Avestan"),
+    ("af", &["afr"], "Afrikaans"),
+    ("ak", &["aka"], "Akan"),
+    ("am", &["amh"], "Amharic"),
+    ("an", &["arg"], "Aragonese"),
+    ("ar", &["ara"], "Arabic"),
+    ("as", &["asm"], "Assamese"),
+    ("av", &["ava"], "Avaric"),
+    ("ay", &["aym"], "Aymara"),
+    ("az", &["aze"], "Azerbaijani"),
+    ("ba", &["bak"], "Bashkir"),
+    ("be", &["bel"], "Belarusian"),
+    ("bg", &["bul"], "Bulgarian"),
+    ("bi", &["bis"], "Bislama"),
+    ("bm", &["bam"], "Bambara"),
+    ("bn", &["ben"], "Bengali"),
+    ("bo", &["tib", "bod"], "Tibetan"),
+    ("br", &["bre"], "Breton"),
+    ("bs", &["bos"], "Bosnian"),
+    ("ca", &["cat"], "Catalan"),
+    ("ce", &["che"], "Chechen"),
+    ("ch", &["cha"], "Chamorro"),
+    ("co", &["cos"], "Corsican"),
+    ("cr", &["cre"], "Cree"),
+    ("cs", &["cze", "ces"], "Czech"),
+    ("cu", &["chu"], "Church Slavic"),
+    ("cv", &["chv"], "Chuvash"),
+    ("cy", &["wel", "cym"], "Welsh"),
+    ("da", &["dan"], "Danish"),
+    ("de", &["ger", "deu"], "German"),
+    ("dv", &["div"], "Divehi"),
+    ("dz", &["dzo"], "Dzongkha"),
+    ("ee", &["ewe"], "Ewe"),
+    ("el", &["gre", "ell"], "Greek"),
+    ("en", &["eng"], "English"),
+    ("eo", &["epo"], "Esperanto"),
+    ("es", &["spa"], "Spanish"),
+    ("et", &["est"], "Estonian"),
+    ("eu", &["baq", "eus"], "Basque"),
+    ("fa", &["per", "fas"], "Persian"),
+    ("ff", &["ful"], "Fulah"),
+    ("fi", &["fin"], "Finnish"),
+    ("fj", &["fij"], "Fijian"),
+    ("fo", &["fao"], "Faroese"),
+    ("fr", &["fre", "fra"], "French"),
+    ("fy", &["fry"], "Western Frisian"),
+    ("ga", &["gle"], "Irish"),
+    ("gd", &["gla"], "Scottish Gaelic"),
+    ("gl", &["glg"], "Galician"),
+    ("gn", &["grn"], "Guarani"),
+    ("gu", &["guj"], "Gujarati"),
+    ("gv", &["glv"], "Manx"),
+    ("ha", &["hau"], "Hausa"),
+    ("he", &["heb"], "Hebrew"),
+    ("hi", &["hin"], "Hindi"),
+    ("ho", &["hmo"], "Hiri Motu"),
+    ("hr", &["hrv"], "Croatian"),
+    ("ht", &["hat"], "Haitian"),
+    ("hu", &["hun"], "Hungarian"),
+    ("hy", &["arm", "hye"], "Armenian"),
+    ("hz", &["her"], "Herero"),
+    ("ia", &["ina"], "Interlingua"),
+    ("id", &["ind"], "Indonesian"),
+    ("ie", &["ile"], "Interlingue"),
+    ("ig", &["ibo"], "Igbo"),
+    ("ii", &["iii"], "Sichuan Yi"),
+    ("ik", &["ipk"], "Inupiaq"),
+    ("io", &["ido"], "Ido"),
+    ("is", &["ice", "isl"], "Icelandic"),
+    ("it", &["ita"], "Italian"),
+    ("iu", &["iku"], "Inuktitut"),
+    ("ja", &["jpn"], "Japanese"),
+    ("jv", &["jav"], "Javanese"),
+    ("ka", &["geo", "kat"], "Georgian"),
+    ("kg", &["kon"], "Kongo"),
+    ("ki", &["kik"], "Kikuyu"),
+    ("kj", &["kua"], "Kuanyama"),
+    ("kk", &["kaz"], "Kazakh"),
+    ("kl", &["kal"], "Kalaallisut"),
+    ("km", &["khm"], "Khmer"),
+    ("kn", &["kan"], "Kannada"),
+    ("ko", &["kor"], "Korean"),
+    ("kr", &["kau"], "Kanuri"),
+    ("ks", &["kas"], "Kashmiri"),
+    ("ku", &["kur"], "Kurdish"),
+    ("kv", &["kom"], "Komi"),
+    ("kw", &["cor"], "Cornish"),
+    ("ky", &["kir"], "Kirghiz"),
+    ("la", &["lat"], "Latin"),
+    ("lb", &["ltz"], "Luxembourgish"),
+    ("lg", &["lug"], "Ganda"),
+    ("li", &["lim"], "Limburgish"),
+    ("ln", &["lin"], "Lingala"),
+    ("lo", &["lao"], "Lao"),
+    ("lt", &["lit"], "Lithuanian"),
+    ("lu", &["lub"], "Luba-Katanga"),
+    ("lv", &["lav"], "Latvian"),
+    ("mg", &["mlg"], "Malagasy"),
+    ("mh", &["mah"], "Marshallese"),
+    ("mi", &["mao", "mri"], "Maori"),
+    ("mk", &["mac", "mkd"], "Macedonian"),
+    ("ml", &["mal"], "Malayalam"),
+    ("mn", &["mon"], "Mongolian"),
+    ("mr", &["mar"], "Marathi"),
+    ("ms", &["may", "msa"], "Malay"),
+    ("mt", &["mlt"], "Maltese"),
+    ("my", &["bur", "mya"], "Burmese"),
+    ("na", &["nau"], "Nauru"),
+    ("nb", &["nob"], "Norwegian Bokmål"),
+    ("nd", &["nde"], "North Ndebele"),
+    ("ne", &["nep"], "Nepali"),
+    ("ng", &["ndo"], "Ndonga"),
+    ("nl", &["dut", "nld"], "Dutch"),
+    ("nn", &["nno"], "Norwegian Nynorsk"),
+    ("no", &["nor"], "Norwegian"),
+    ("nr", &["nbl"], "South Ndebele"),
+    ("nv", &["nav"], "Navajo"),
+    ("ny", &["nya"], "Chichewa"),
+    ("oc", &["oci"], "Occitan"),
+    ("oj", &["oji"], "Ojibwa"),
+    ("om", &["orm"], "Oromo"),
+    ("or", &["ori"], "Oriya"),
+    ("os", &["oss"], "Ossetian"),
+    ("pa", &["pan"], "Punjabi"),
+    ("pi", &["pli"], "Pali"),
+    ("pl", &["pol"], "Polish"),
+    ("ps", &["pus"], "Pashto"),
+    ("pt", &["por"], "Portuguese"),
+    ("qu", &["que"], "Quechua"),
+    ("rm", &["roh"], "Romansh"),
+    ("rn", &["run"], "Rundi"),
+    ("ro", &["rum", "ron"], "Romanian"),
+    ("ru", &["rus"], "Russian"),
+    ("rw", &["kin"], "Kinyarwanda"),
+    ("sa", &["san"], "Sanskrit"),
+    ("sc", &["srd"], "Sardinian"),
+    ("sd", &["snd"], "Sindhi"),
+    ("se", &["sme"], "Northern Sami"),
+    ("sg", &["sag"], "Sango"),
+    ("si", &["sin"], "Sinhala"),
+    ("sk", &["slo", "slk"], "Slovak"),
+    ("sl", &["slv"], "Slovenian"),
+    ("sm", &["smo"], "Samoan"),
+    ("sn", &["sna"], "Shona"),
+    ("so", &["som"], "Somali"),
+    ("sq", &["alb", "sqi"], "Albanian"),
+    ("sr", &["srp"], "Serbian"),
+    ("ss", &["ssw"], "Swati"),
+    ("st", &["sot"], "Southern Sotho"),
+    ("su", &["sun"], "Sundanese"),
+    ("sv", &["swe"], "Swedish"),
+    ("sw", &["swa"], "Swahili"),
+    ("ta", &["tam"], "Tamil"),
+    ("te", &["tel"], "Telugu"),
+    ("tg", &["tgk"], "Tajik"),
+    ("th", &["tha"], "Thai"),
+    ("ti", &["tir"], "Tigrinya"),
+    ("tk", &["tuk"], "Turkmen"),
+    ("tl", &["tgl"], "Tagalog"),
+    ("tn", &["tsn"], "Tswana"),
+    ("to", &["ton"], "Tonga"),
+    ("tr", &["tur"], "Turkish"),
+    ("ts", &["tso"], "Tsonga"),
+    ("tt", &["tat"], "Tatar"),
+    ("tw", &["twi"], "Twi"),
+    ("ty", &["tah"], "Tahitian"),
+    ("ug", &["uig"], "Uyghur"),
+    ("uk", &["ukr"], "Ukrainian"),
+    ("ur", &["urd"], "Urdu"),
+    ("uz", &["uzb"], "Uzbek"),
+    ("ve", &["ven"], "Venda"),
+    ("vi", &["vie"], "Vietnamese"),
+    ("vo", &["vol"], "Volapük"),
+    ("wa", &["wln"], "Walloon"),
+    ("wo", &["wol"], "Wolof"),
+    ("xh", &["xho"], "Xhosa"),
+    ("yi", &["yid"], "Yiddish"),
+    ("yo", &["yor"], "Yoruba"),
+    ("za", &["zha"], "Zhuang"),
+    ("zh", &["chi", "zho"], "Chinese"),
+    ("zu", &["zul"], "Zulu"),
+    // Languages without a two-letter code
+    ("fil", &[], "Filipino"),
+    ("haw", &[], "Hawaiian"),
+    ("yue", &[], "Cantonese"),
+    ("mis", &[], "Uncoded languages"),
+    ("mul", &[], "Multiple languages"),
+    ("und", &[], "Undetermined"),
+    ("zxx", &[], "No linguistic content"),
+];
+
+/// Normalize an ISO 639-2 code or a BCP 47 tag, such as `ger` or `de-de`.
+///
+/// ISO 639-2 codes are replaced by their two-letter equivalent when there is
+/// one and subtags get their canonical case. Returns `None` for empty tags.
+pub fn normalize_language(tag: &str) -> Option<Language> {
+    let mut subtags = tag.split(['-', '_']);
+    let language = subtags.next()?.to_ascii_lowercase();
+    if language.is_empty() {
+        return None;
+    }
+
+    let known = LANGUAGES
+        .iter()
+        .find(|(subtag, codes, _)| *subtag == language || codes.contains(&language.as_str()));
+    let mut bcp47 = known.map_or(language, |(subtag, _, _)| subtag.to_string());
+    for subtag in subtags {
+        bcp47.push('-');
+        let is_alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+        match subtag.len() {
+            // Script, such as Latn
+            4 if is_alphabetic => {
+                bcp47.push_str(&subtag[..1].to_ascii_uppercase());
+                bcp47.push_str(&subtag[1..].to_ascii_lowercase());
+            }
+            // Region, such as DE or 419
+            2 if is_alphabetic => bcp47.push_str(&subtag.to_ascii_uppercase()),
+            3 if subtag.chars().all(|c| c.is_ascii_digit()) => bcp47.push_str(subtag),
+            _ => bcp47.push_str(&subtag.to_ascii_lowercase()),
+        }
+    }
+
+    Some(Language {
+        bcp47,
+        name: known.map(|(_, _, name)| *name),
+    })
+}
+
+/// Get the normalized language of Language, LanguageBCP47, ChapLanguage,
+/// ChapLanguageBCP47, TagLanguage and TagLanguageBCP47 elements
+pub(crate) fn element_language(element: &Element) -> Option<Language> {
+    match (&element.header.id, &element.body) {
+        (
+            Id::Language
+            | Id::LanguageBcp47
+            | Id::ChapLanguage
+            | Id::ChapLanguageBcp47
+            | Id::TagLanguage
+            | Id::TagLanguageBcp47,
+            Body::String(tag),
+        ) => normalize_language(tag),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_language() {
+        let language = |bcp47: &str, name| {
+            Some(Language {
+                bcp47: bcp47.to_string(),
+                name,
+            })
+        };
+        assert_eq!(normalize_language("ger"), language("de", Some("German")));
+        assert_eq!(normalize_language("deu"), language("de", Some("German")));
+        assert_eq!(
+            normalize_language("de-de"),
+            language("de-DE", Some("German"))
+        );
+        assert_eq!(
+            normalize_language("zh-hant-TW"),
+            language("zh-Hant-TW", Some("Chinese"))
+        );
+        assert_eq!(
+            normalize_language("es-419"),
+            language("es-419", Some("Spanish"))
+        );
+        assert_eq!(
+            normalize_language("und"),
+            language("und", Some("Undetermined"))
+        );
+        assert_eq!(normalize_language("qaa"), language("qaa", None));
+        assert_eq!(normalize_language(""), None);
+    }
+
+    #[test]
+    fn test_element_language() {
+        const INPUT: &[u8] = &[0x22, 0xB5, 0x9C, 0x83, 0x67, 0x65, 0x72];
+        let (_, element) = crate::parse_element(INPUT).unwrap();
+        assert_eq!(element.body, Body::String("ger".to_string()));
+        assert_eq!(
+            element.header.language,
+            Some(Language {
+                bcp47: "de".to_string(),
+                name: Some("German"),
+            })
+        );
+    }
+}
@@ -5,74 +5,176 @@
 //! Provides a set of Matroska structures and
 //! functions to parse Matroska elements.
 
-use std::ops::Not;
+use std::{cell::Cell, num::NonZeroUsize, ops::Not};
 
-use chrono::prelude::*;
+use base64::prelude::*;
+use chrono::{prelude::*, TimeDelta};
 use nom::combinator::peek;
 use nom::ToUsize;
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_with::skip_serializing_none;
 
+#[cfg(feature = "tokio")]
+pub mod asynchronous;
+pub mod attachments;
+pub mod blocks;
+pub mod borrowed;
+pub mod chapters;
+pub mod codecs;
+pub mod crc;
+pub mod cues;
+mod de;
+pub mod diagnostics;
 mod ebml;
 /// Matroska elements
 pub mod elements;
 /// Matroska enumerations
 pub mod enumerations;
 mod error;
+pub mod ffprobe;
+pub mod hdr;
+pub mod iter;
+pub mod languages;
+pub mod menus;
+pub mod model;
+mod options;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod projection;
+pub mod segments;
+pub mod sizes;
+pub mod stats;
+pub mod streamability;
+pub mod streaming;
+pub mod summary;
+mod table;
+pub mod tags;
+pub mod times;
+pub mod tracks;
 /// The tree module contains helpers for building tree
 /// structures from parsed elements
 pub mod tree;
+pub mod validate;
+pub mod verify;
+pub mod writer;
 
 use crate::elements::{Id, Type};
 use crate::enumerations::Enumeration;
 pub use error::Error;
+pub use options::{BinaryEncoding, ParserOptions};
 
 /// Result type helper
 pub type Result<T> = std::result::Result<T, Error>;
 type IResult<T, O> = Result<(T, O)>;
+type TakeResult<'a> = std::result::Result<(&'a [u8], &'a [u8]), nom::Err<()>>;
 
-fn take<'a>(
-    len: impl ToUsize,
-) -> impl Fn(&'a [u8]) -> std::result::Result<(&'a [u8], &'a [u8]), nom::Err<()>> {
+fn take<'a>(len: impl ToUsize) -> impl Fn(&'a [u8]) -> TakeResult<'a> {
     nom::bytes::streaming::take(len)
 }
 
+/// Maximum lengths of IDs and sizes allowed in a document, as declared by
+/// EBMLMaxIDLength and EBMLMaxSizeLength in its EBML header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EbmlLimits {
+    pub(crate) max_id_length: usize,
+    pub(crate) max_size_length: usize,
+}
+
+impl Default for EbmlLimits {
+    // IDs can only have up to 4 bytes in Matroska, and sizes up to 8 bytes
+    fn default() -> Self {
+        Self {
+            max_id_length: 4,
+            max_size_length: 8,
+        }
+    }
+}
+
 pub(crate) fn parse_id(input: &[u8]) -> IResult<&[u8], Id> {
+    parse_id_with_limits(input, &EbmlLimits::default())
+}
+
+// IDs longer than 8 bytes are not supported, whatever the limits allow
+pub(crate) fn parse_id_with_limits<'a>(
+    input: &'a [u8],
+    limits: &EbmlLimits,
+) -> IResult<&'a [u8], Id> {
     let (input, first_byte) = peek(take(1usize))(input)?;
     let first_byte = first_byte[0];
 
-    let num_bytes = count_leading_zero_bits(first_byte) + 1;
+    let num_bytes = count_leading_zero_bits(first_byte) as usize + 1;
 
-    // IDs can only have up to 4 bytes in Matroska
-    if num_bytes > 4 {
+    if num_bytes > limits.max_id_length.min(8) {
         return Err(Error::InvalidId);
     }
 
     let (input, varint_bytes) = take(num_bytes)(input)?;
-    let mut value_buffer = [0u8; 4];
-    value_buffer[(4 - varint_bytes.len())..].copy_from_slice(varint_bytes);
-    let id = u32::from_be_bytes(value_buffer);
+    let mut value_buffer = [0u8; 8];
+    value_buffer[(8 - varint_bytes.len())..].copy_from_slice(varint_bytes);
+    let id = u64::from_be_bytes(value_buffer);
 
     Ok((input, Id::new(id)))
 }
 
 /// Represents an [EBML Header](https://github.com/ietf-wg-cellar/ebml-specification/blob/master/specification.markdown#ebml-header)
 #[skip_serializing_none]
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Header {
     /// The Element ID
     pub id: Id,
     /// Size of the header itself
     pub header_size: usize,
-    /// Size of the Element Body
-    #[serde(skip_serializing)]
+    /// Size of the Element ID within the header, see
+    /// [`ParserOptions::header_field_sizes`]
+    pub id_size: Option<usize>,
+    /// Size of the Element Data Size within the header, see
+    /// [`ParserOptions::header_field_sizes`]
+    pub size_field_size: Option<usize>,
+    /// Size of the Element Body. It's not serialized, but computed from
+    /// `size` when deserializing an [`Element`].
+    #[serde(skip)]
     pub body_size: Option<usize>,
     /// Size of Header + Body
     #[serialize_always]
-    #[serde(serialize_with = "serialize_size")]
+    #[serde(
+        serialize_with = "serialize_size",
+        deserialize_with = "de::deserialize_size"
+    )]
     pub size: Option<usize>,
     /// Position in the input
     pub position: Option<usize>,
+    /// Size of Header + Body of elements with unknown size, computed from
+    /// where the next element outside of it begins. Non-Master elements
+    /// with unknown size, accepted with
+    /// [`ParserOptions::lenient_unknown_leaf_size`], also have their `size`
+    /// set to it.
+    pub computed_size: Option<usize>,
+    /// Path of the element in the input, e.g. `\Segment\Info\TimestampScale`
+    pub path: Option<String>,
+    /// Nesting level of the element, with top-level elements at level 0
+    pub level: Option<usize>,
+    /// Whether the element is absent from the input and only implied by its
+    /// default value
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub implied: bool,
+    /// Whether the element is no longer part of the latest version of the
+    /// DocType
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub deprecated: bool,
+    /// Normalized value of language elements, such as Language or
+    /// LanguageBCP47. It's recomputed when deserializing an [`Element`].
+    #[serde(skip_deserializing)]
+    pub language: Option<languages::Language>,
+    /// Duration of the audio discarded by a DiscardPadding element
+    pub discard_padding: Option<DiscardPadding>,
+    /// One-line summary of the HDR properties of Video elements, see
+    /// [`hdr::set_hdr_summaries`]
+    pub hdr: Option<String>,
+    /// Human-readable time of elements holding a time or a duration, see
+    /// [`times::set_human_times`]
+    pub time: Option<String>,
+    /// Human-readable size of the element, see [`sizes::set_human_sizes`]
+    pub human_size: Option<String>,
 }
 
 fn serialize_size<S: Serializer>(
@@ -92,9 +194,30 @@ impl Header {
         Self {
             id,
             header_size,
+            id_size: None,
+            size_field_size: None,
             body_size: Some(body_size),
             size: Some(header_size + body_size),
             position: None,
+            computed_size: None,
+            path: None,
+            level: None,
+            implied: false,
+            deprecated: false,
+            language: None,
+            discard_padding: None,
+            hdr: None,
+            time: None,
+            human_size: None,
+        }
+    }
+
+    // Split the header size into the sizes of the ID and of the size field,
+    // if requested
+    pub(crate) fn set_field_sizes(&mut self, id_size: usize, options: &ParserOptions) {
+        if options.header_field_sizes {
+            self.id_size = Some(id_size);
+            self.size_field_size = Some(self.header_size - id_size);
         }
     }
 
@@ -102,9 +225,21 @@ impl Header {
         Self {
             id,
             header_size,
+            id_size: None,
+            size_field_size: None,
             body_size: None,
             size: None,
             position: None,
+            computed_size: None,
+            path: None,
+            level: None,
+            implied: false,
+            deprecated: false,
+            language: None,
+            discard_padding: None,
+            hdr: None,
+            time: None,
+            human_size: None,
         }
     }
 }
@@ -119,14 +254,21 @@ fn count_leading_zero_bits(input: u8) -> u8 {
     8
 }
 
-fn parse_varint(first_input: &[u8]) -> IResult<&[u8], Option<usize>> {
+pub(crate) fn parse_varint(first_input: &[u8]) -> IResult<&[u8], Option<usize>> {
+    parse_varint_with_max_length(first_input, 8)
+}
+
+pub(crate) fn parse_varint_with_max_length(
+    first_input: &[u8],
+    max_length: usize,
+) -> IResult<&[u8], Option<usize>> {
     let (input, first_byte) = peek(take(1usize))(first_input)?;
     let first_byte = first_byte[0];
 
-    let vint_prefix_size = count_leading_zero_bits(first_byte) + 1;
+    let vint_prefix_size = count_leading_zero_bits(first_byte) as usize + 1;
 
     // Maximum 8 bytes, i.e. first byte can't be 0
-    if vint_prefix_size > 8 {
+    if vint_prefix_size > max_length.min(8) {
         return Err(Error::InvalidVarint);
     }
 
@@ -153,59 +295,375 @@ fn parse_varint(first_input: &[u8]) -> IResult<&[u8], Option<usize>> {
 
 /// Parse element header
 pub fn parse_header(input: &[u8]) -> IResult<&[u8], Header> {
+    parse_header_with_options(input, &ParserOptions::default())
+}
+
+/// Parse element header with the given options
+pub fn parse_header_with_options<'a>(
+    input: &'a [u8],
+    options: &ParserOptions,
+) -> IResult<&'a [u8], Header> {
+    parse_header_with_limits(input, options, &EbmlLimits::default(), &Cell::new(0))
+}
+
+// `scanned` keeps how far the body of a non-Master element with unknown size
+// was searched for its end, so that the search resumes there once more data
+// is available for the same element.
+pub(crate) fn parse_header_with_limits<'a>(
+    input: &'a [u8],
+    options: &ParserOptions,
+    limits: &EbmlLimits,
+    scanned: &Cell<usize>,
+) -> IResult<&'a [u8], Header> {
     let initial_len = input.len();
-    let (input, id) = parse_id(input)?;
-    let (input, body_size) = parse_varint(input)?;
+    let (input, id) = parse_id_with_limits(input, limits)?;
+    let id_size = initial_len - input.len();
+    let (input, body_size) = parse_varint_with_max_length(input, limits.max_size_length)?;
+
+    let header_size = initial_len - input.len();
+    if body_size.is_none() && options.lenient_unknown_leaf_size && id.get_type() != Type::Master {
+        let body_size = unknown_leaf_body_size(&id, input, limits, scanned)?;
+        let mut header = Header {
+            computed_size: Some(header_size + body_size),
+            ..Header::new(id, header_size, body_size)
+        };
+        header.set_field_sizes(id_size, options);
+        return Ok((input, header));
+    }
 
     // Only Segment and Cluster have unknownsizeallowed="1" in ebml_matroska.xml.
     // Also mentioned in https://www.w3.org/TR/mse-byte-stream-format-webm/
-    if body_size.is_none() && id != Id::Segment && id != Id::Cluster {
+    if body_size.is_none()
+        && id != Id::Segment
+        && id != Id::Cluster
+        && !(options.lenient_unknown_size && id.get_type() == Type::Master)
+    {
         return Err(Error::ForbiddenUnknownSize);
     }
 
-    let header_size = initial_len - input.len();
+    if let (Some(body_size), Some(max_element_size)) = (body_size, options.max_element_size) {
+        if body_size > max_element_size && id.get_type() != Type::Master {
+            return Err(Error::ElementTooLarge);
+        }
+    }
 
-    let header = match body_size {
+    let mut header = match body_size {
         Some(body_size) => Header::new(id, header_size, body_size),
         None => Header::with_unknown_size(id, header_size),
     };
+    header.set_field_sizes(id_size, options);
 
     Ok((input, header))
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-enum Lacing {
+// Non-Master elements with unknown size are only searched for their end
+// this far
+const MAX_UNKNOWN_LEAF_SIZE: usize = 1 << 24;
+
+// Size of the body of a non-Master element with unknown size, taken as
+// extending up to the next element that is either a sibling of it, according
+// to the schema, or a top-level element. Searching resumes from `scanned`,
+// which is updated when more data is needed and reset otherwise.
+fn unknown_leaf_body_size(
+    id: &Id,
+    body: &[u8],
+    limits: &EbmlLimits,
+    scanned: &Cell<usize>,
+) -> Result<usize> {
+    let result = find_unknown_leaf_end(id, body, limits, scanned.get());
+    scanned.set(match result {
+        Err(UnknownLeafEnd::NeedData(offset)) => offset,
+        _ => 0,
+    });
+    result.map_err(|e| match e {
+        UnknownLeafEnd::NeedData(_) => Error::NeedData(None),
+        UnknownLeafEnd::NotFound => Error::ForbiddenUnknownSize,
+    })
+}
+
+enum UnknownLeafEnd {
+    // More data is needed to tell whether an element starts at this offset
+    NeedData(usize),
+    NotFound,
+}
+
+fn find_unknown_leaf_end(
+    id: &Id,
+    body: &[u8],
+    limits: &EbmlLimits,
+    start: usize,
+) -> std::result::Result<usize, UnknownLeafEnd> {
+    let parent_path = |id: &Id| {
+        id.schema()
+            .and_then(|schema| schema.path.rsplit_once('\\'))
+            .map(|(path, _)| path)
+    };
+    let Some(parent) = parent_path(id) else {
+        return Err(UnknownLeafEnd::NotFound);
+    };
+
+    let end = body.len().min(MAX_UNKNOWN_LEAF_SIZE);
+    for offset in start..end {
+        let input = &body[offset..];
+        let (input, next) = match parse_id_with_limits(input, limits) {
+            Ok(parsed) => parsed,
+            Err(Error::NeedData(_)) => return Err(UnknownLeafEnd::NeedData(offset)),
+            Err(_) => continue,
+        };
+        if !SYNC_ELEMENT_IDS.contains(&next) && parent_path(&next) != Some(parent) {
+            continue;
+        }
+        // Like for resyncing, byte runs that merely look like an ID are
+        // ruled out by requiring a valid size after it, and for non-Master
+        // elements a body that ends within the data
+        let (input, size) = match parse_varint_with_max_length(input, limits.max_size_length) {
+            Ok(parsed) => parsed,
+            Err(Error::NeedData(_)) => return Err(UnknownLeafEnd::NeedData(offset)),
+            Err(_) => continue,
+        };
+        match (next.get_type(), size) {
+            (Type::Master, _) => return Ok(offset),
+            (_, Some(size)) if size <= input.len() => return Ok(offset),
+            (_, Some(size)) if offset + size < MAX_UNKNOWN_LEAF_SIZE => {
+                return Err(UnknownLeafEnd::NeedData(offset))
+            }
+            _ => continue,
+        }
+    }
+    if body.len() >= MAX_UNKNOWN_LEAF_SIZE {
+        Err(UnknownLeafEnd::NotFound)
+    } else {
+        Err(UnknownLeafEnd::NeedData(end))
+    }
+}
+
+/// Lacing of the frames of a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Lacing {
+    /// Xiph lacing
     Xiph,
+    /// EBML lacing
     Ebml,
+    /// Fixed-size lacing
     FixedSize,
 }
 
+/// Byte range of a frame contained in a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Offset of the frame payload. It is absolute when the block position
+    /// is known, or relative to the block body otherwise.
+    pub offset: usize,
+    /// Size of the frame payload
+    pub size: usize,
+}
+
+// Byte ranges of frames, where `data_offset` is where the first frame starts
+// in the block body.
+fn frame_ranges(
+    data_offset: usize,
+    body_size: usize,
+    frame_sizes: &Option<Vec<usize>>,
+) -> Vec<Frame> {
+    let frame_sizes = match frame_sizes {
+        Some(frame_sizes) => frame_sizes.clone(),
+        None => vec![body_size - data_offset],
+    };
+    let mut offset = data_offset;
+    frame_sizes
+        .into_iter()
+        .map(|size| {
+            let frame = Frame { offset, size };
+            offset += size;
+            frame
+        })
+        .collect()
+}
+
+/// Encryption status of a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionStatus {
+    /// The frame is not encrypted
+    Clear,
+    /// The whole frame is encrypted
+    Encrypted,
+    /// Only some partitions of the frame are encrypted
+    PartiallyEncrypted,
+}
+
+/// Encryption signal of a block from an encrypted track, as defined in
+/// [WebM Encryption](https://www.webmproject.org/docs/webm-encryption/)
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockEncryption {
+    /// Encryption status
+    pub status: EncryptionStatus,
+    /// Initialization vector, as a hex string
+    pub iv: Option<String>,
+    /// Offsets of the partitions, alternating between clear and encrypted
+    /// partitions
+    pub partitions: Option<Vec<u32>>,
+}
+
+fn parse_block_encryption(input: &[u8]) -> IResult<&[u8], BlockEncryption> {
+    let (input, signal) = take(1usize)(input)?;
+    let is_encrypted = signal[0] & 0b01 != 0;
+    let is_partitioned = signal[0] & 0b10 != 0;
+    if !is_encrypted {
+        return Ok((
+            input,
+            BlockEncryption {
+                status: EncryptionStatus::Clear,
+                iv: None,
+                partitions: None,
+            },
+        ));
+    }
+
+    let (input, iv) = take(8usize)(input)?;
+    let iv = iv.iter().map(|b| format!("{:02x}", b)).collect();
+    if !is_partitioned {
+        return Ok((
+            input,
+            BlockEncryption {
+                status: EncryptionStatus::Encrypted,
+                iv: Some(iv),
+                partitions: None,
+            },
+        ));
+    }
+
+    let (input, num_partitions) = take(1usize)(input)?;
+    let (input, offsets) = take(4 * num_partitions[0] as usize)(input)?;
+    let partitions = offsets
+        .chunks_exact(4)
+        .map(|offset| u32::from_be_bytes(offset.try_into().unwrap()))
+        .collect();
+    Ok((
+        input,
+        BlockEncryption {
+            status: EncryptionStatus::PartiallyEncrypted,
+            iv: Some(iv),
+            partitions: Some(partitions),
+        },
+    ))
+}
+
+/// Type of the frame of a Block, from the ReferenceBlock elements of its
+/// BlockGroup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameType {
+    /// Intra frame, without any ReferenceBlock
+    I,
+    /// Predicted frame, only referencing blocks in the past
+    P,
+    /// Bidirectional frame, referencing a block in the future
+    B,
+}
+
+impl FrameType {
+    /// Classify a frame given the values of its ReferenceBlock elements,
+    /// i.e. the timestamps of the referenced blocks relative to it
+    pub fn from_references(references: &[i64]) -> Self {
+        if references.is_empty() {
+            FrameType::I
+        } else if references.iter().any(|reference| *reference > 0) {
+            FrameType::B
+        } else {
+            FrameType::P
+        }
+    }
+}
+
 /// A Matroska [Block](https://www.matroska.org/technical/basics.html#block-structure)
 #[skip_serializing_none]
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Block {
     track_number: usize,
     timestamp: i16,
-    #[serde(skip_serializing_if = "Not::not")]
+    #[serde(default, skip_serializing_if = "Not::not")]
     invisible: bool,
+    frame_type: Option<FrameType>,
     lacing: Option<Lacing>,
-    num_frames: Option<u8>,
+    num_frames: Option<usize>,
+    frame_sizes: Option<Vec<usize>>,
+    // Size of the single frame of blocks without lacing, as the size of the
+    // header of the block depends on how its track number is coded
+    #[serde(skip)]
+    frame_size: Option<usize>,
+    frames: Option<Vec<Frame>>,
+    encryption: Option<BlockEncryption>,
+    absolute_time_ns: Option<i64>,
+    absolute_time: Option<String>,
 }
 
 /// A Matroska [SimpleBlock](https://www.matroska.org/technical/basics.html#simpleblock-structure)
 #[skip_serializing_none]
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SimpleBlock {
     track_number: usize,
     timestamp: i16,
-    #[serde(skip_serializing_if = "Not::not")]
+    #[serde(default, skip_serializing_if = "Not::not")]
     keyframe: bool,
-    #[serde(skip_serializing_if = "Not::not")]
+    #[serde(default, skip_serializing_if = "Not::not")]
     invisible: bool,
     lacing: Option<Lacing>,
-    #[serde(skip_serializing_if = "Not::not")]
+    #[serde(default, skip_serializing_if = "Not::not")]
     discardable: bool,
-    num_frames: Option<u8>,
+    num_frames: Option<usize>,
+    frame_sizes: Option<Vec<usize>>,
+    // Size of the single frame of blocks without lacing, as the size of the
+    // header of the block depends on how its track number is coded
+    #[serde(skip)]
+    frame_size: Option<usize>,
+    frames: Option<Vec<Frame>>,
+    encryption: Option<BlockEncryption>,
+    absolute_time_ns: Option<i64>,
+    absolute_time: Option<String>,
+}
+
+impl Block {
+    /// Type of the frame, once the BlockGroup of the Block has been parsed
+    pub fn frame_type(&self) -> Option<FrameType> {
+        self.frame_type
+    }
+}
+
+impl SimpleBlock {
+    /// Whether the block only contains keyframes
+    pub fn keyframe(&self) -> bool {
+        self.keyframe
+    }
+
+    /// Byte ranges of the contained frames, if requested with
+    /// [`ParserOptions::frame_ranges`]
+    pub fn frames(&self) -> Option<&[Frame]> {
+        self.frames.as_deref()
+    }
+}
+
+/// An element cut short by the end of the input, such as the last element
+/// of an incomplete download
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Truncated {
+    /// ID of the incomplete element
+    pub id: Id,
+    /// Size of Header + Body declared by the element, if it could be read
+    pub declared_size: Option<usize>,
+    /// Number of bytes of the element present in the input
+    pub available: usize,
+}
+
+/// The start of a new stream within the input, such as a second EBML
+/// header in a trimmed live capture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamBoundary {
+    /// Index of the new stream, the first stream of the input being 0
+    pub stream: usize,
+    /// Position of the EBML header starting the new stream
+    pub offset: usize,
 }
 
 /// Enumeration with possible binary value payloads
@@ -214,21 +672,214 @@ pub struct SimpleBlock {
 pub enum Binary {
     /// A standard binary payload that will not be parsed further
     Standard(String),
+    /// A binary payload fully serialized as base64, see
+    /// [`ParserOptions::binary_encoding`]
+    Base64(String),
     /// A SeekId payload
     SeekId(Id),
+    /// A 128-bit identifier, such as a SegmentUUID, formatted as a UUID
+    Uuid(String),
     /// A SimpleBlock
     SimpleBlock(SimpleBlock),
     /// A Block
     Block(Block),
-    /// Void
-    Void,
+    /// A BlockGroup composed into a single record, see
+    /// [`blocks::compose_block_groups`]
+    BlockGroup(Box<blocks::ComposedBlockGroup>),
+    /// A CodecPrivate payload decoded according to the CodecID of its track
+    CodecPrivate(codecs::CodecPrivate),
+    /// ITU-T T.35 metadata from a BlockAdditional
+    ItuT35(hdr::ItuT35),
+    /// Dolby Vision configuration from a BlockAddIDExtraData
+    DolbyVision(hdr::DolbyVisionConfiguration),
+    /// DVD commands from a ChapProcessData, as hexadecimal bytes
+    DvdCommands(Vec<String>),
+    /// A ProjectionPrivate payload decoded according to the ProjectionType
+    /// of its track
+    Projection(projection::ProjectionPrivate),
+    /// Void padding, with the size of its body
+    #[serde(serialize_with = "serialize_void")]
+    Void(usize),
     /// Represents the payload of a corrupted region of the file
     Corrupted,
+    /// Describes an element cut short by the end of the input
+    Truncated(Truncated),
+    /// Marks the start of a new stream
+    StreamBoundary(StreamBoundary),
+}
+
+fn serialize_void<S: Serializer>(size: &usize, s: S) -> std::result::Result<S::Ok, S::Error> {
+    s.collect_str(&format_args!("{size} bytes of padding"))
+}
+
+impl Binary {
+    // Track number of blocks
+    pub(crate) fn track_number(&self) -> Option<usize> {
+        match self {
+            Binary::SimpleBlock(block) => Some(block.track_number),
+            Binary::Block(block) => Some(block.track_number),
+            _ => None,
+        }
+    }
+
+    // Lacing of blocks with several frames
+    pub(crate) fn lacing(&self) -> Option<Lacing> {
+        match self {
+            Binary::SimpleBlock(block) => block.lacing,
+            Binary::Block(block) => block.lacing,
+            _ => None,
+        }
+    }
+
+    // Sizes of the frames of blocks, as found when their header was parsed
+    pub(crate) fn frame_sizes(&self) -> Option<Vec<usize>> {
+        let (frame_sizes, frame_size) = match self {
+            Binary::SimpleBlock(block) => (&block.frame_sizes, block.frame_size),
+            Binary::Block(block) => (&block.frame_sizes, block.frame_size),
+            _ => return None,
+        };
+        frame_sizes
+            .clone()
+            .or_else(|| frame_size.map(|frame_size| vec![frame_size]))
+    }
+
+    // Absolute time of blocks in nanoseconds, if the Cluster timestamp was
+    // known when they were parsed
+    pub(crate) fn absolute_time_ns(&self) -> Option<i64> {
+        match self {
+            Binary::SimpleBlock(block) => block.absolute_time_ns,
+            Binary::Block(block) => block.absolute_time_ns,
+            _ => None,
+        }
+    }
+
+    // Classify the frame of a Block given the ReferenceBlock values of its
+    // BlockGroup
+    pub(crate) fn set_frame_type(&mut self, references: &[i64]) {
+        if let Binary::Block(block) = self {
+            block.frame_type = Some(FrameType::from_references(references));
+        }
+    }
+
+    // Decode the encryption signal of a block from an encrypted track, given
+    // the block body
+    pub(crate) fn decode_encryption(&mut self, input: &[u8], body_size: usize) -> Result<()> {
+        let body = &input[..body_size.min(input.len())];
+        let (remaining, encryption) = match self {
+            Binary::SimpleBlock(block) => (
+                parse_simple_block(body, body_size)?.0,
+                &mut block.encryption,
+            ),
+            Binary::Block(block) => (parse_block(body, body_size)?.0, &mut block.encryption),
+            _ => return Ok(()),
+        };
+        match parse_block_encryption(remaining) {
+            Ok((_, block_encryption)) => *encryption = Some(block_encryption),
+            // The signal doesn't fit in the block, so just leave it undecoded
+            Err(Error::NeedData(_)) if body.len() == body_size => (),
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    // Set the absolute time of blocks, given the Timestamp of their Cluster
+    // and the TimestampScale of the Segment
+    pub(crate) fn set_absolute_time(&mut self, cluster_timestamp: u64, timestamp_scale: u64) {
+        let (timestamp, absolute_time_ns, absolute_time) = match self {
+            Binary::SimpleBlock(block) => (
+                block.timestamp,
+                &mut block.absolute_time_ns,
+                &mut block.absolute_time,
+            ),
+            Binary::Block(block) => (
+                block.timestamp,
+                &mut block.absolute_time_ns,
+                &mut block.absolute_time,
+            ),
+            _ => return,
+        };
+        let ticks = cluster_timestamp as i128 + timestamp as i128;
+        if let Ok(time_ns) = i64::try_from(ticks * timestamp_scale as i128) {
+            *absolute_time_ns = Some(time_ns);
+            *absolute_time = Some(format_time(time_ns));
+        }
+    }
+
+    // Make frame offsets absolute, given the position of the block body
+    pub(crate) fn offset_frames(&mut self, body_position: usize) {
+        let frames = match self {
+            Binary::SimpleBlock(block) => block.frames.as_mut(),
+            Binary::Block(block) => block.frames.as_mut(),
+            _ => None,
+        };
+        for frame in frames.into_iter().flatten() {
+            frame.offset += body_position;
+        }
+    }
 }
 
-fn parse_binary<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], Binary> {
+/// Duration of the audio discarded by a DiscardPadding element, e.g. to
+/// trim the end of an Opus track
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscardPadding {
+    /// Discarded duration in nanoseconds, serialized in milliseconds
+    #[serde(
+        rename = "milliseconds",
+        serialize_with = "serialize_milliseconds",
+        deserialize_with = "de::deserialize_milliseconds"
+    )]
+    pub duration_ns: i64,
+    /// Number of discarded samples, if the SamplingFrequency of the track
+    /// of the Block is known
+    pub samples: Option<i64>,
+}
+
+impl DiscardPadding {
+    // Only DiscardPadding elements have one. The number of samples is
+    // derived from the SamplingFrequency of the track, if given.
+    pub(crate) fn new(element: &Element, sampling_frequency: Option<f64>) -> Option<Self> {
+        let (Id::DiscardPadding, Body::Signed(duration_ns)) = (&element.header.id, &element.body)
+        else {
+            return None;
+        };
+        Some(Self {
+            duration_ns: *duration_ns,
+            samples: sampling_frequency
+                .map(|frequency| (*duration_ns as f64 * frequency / 1e9).round() as i64),
+        })
+    }
+}
+
+fn serialize_milliseconds<S: Serializer>(
+    duration_ns: &i64,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    s.serialize_f64(*duration_ns as f64 / 1e6)
+}
+
+// Format a time in nanoseconds as HH:MM:SS.mmm, rounded to the nearest
+// millisecond
+pub(crate) fn format_time(time_ns: i64) -> String {
+    let sign = if time_ns < 0 { "-" } else { "" };
+    let time_ms = (time_ns.unsigned_abs() + 500_000) / 1_000_000;
+    let seconds = time_ms / 1000;
+    format!(
+        "{sign}{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        time_ms % 1000
+    )
+}
+
+fn parse_binary<'a>(
+    header: &Header,
+    input: &'a [u8],
+    options: &ParserOptions,
+) -> IResult<&'a [u8], Binary> {
     let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
-    let (input, binary) = peek_binary(header, input)?;
+    let (input, binary) = peek_binary_with_options(header, input, options)?;
     // Actually consume the bytes from the body
     let (input, _) = take(body_size)(input)?;
     Ok((input, binary))
@@ -239,22 +890,78 @@ fn parse_binary<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], Binar
 /// It may be useful to parse just the first bytes of the binary body
 /// without requiring the whole binary to be loaded into memory.
 pub fn peek_binary<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], Binary> {
+    peek_binary_with_options(header, input, &ParserOptions::default())
+}
+
+/// Peek into Binary body without advancing the buffer, with the given options.
+pub fn peek_binary_with_options<'a>(
+    header: &Header,
+    input: &'a [u8],
+    options: &ParserOptions,
+) -> IResult<&'a [u8], Binary> {
     let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
 
     let binary = match header.id {
         Id::SeekId => Binary::SeekId(parse_id(input)?.1),
-        Id::SimpleBlock => Binary::SimpleBlock(parse_simple_block(input)?.1),
-        Id::Block => Binary::Block(parse_block(input)?.1),
-        Id::Void => Binary::Void,
-        _ => Binary::Standard(peek_standard_binary(input, body_size)?.1),
+        Id::SimpleBlock => {
+            let (remaining, mut block) = parse_simple_block(input, body_size)?;
+            if options.frame_ranges {
+                let data_offset = input.len() - remaining.len();
+                block.frames = Some(frame_ranges(data_offset, body_size, &block.frame_sizes));
+            }
+            Binary::SimpleBlock(block)
+        }
+        Id::Block => {
+            let (remaining, mut block) = parse_block(input, body_size)?;
+            if options.frame_ranges {
+                let data_offset = input.len() - remaining.len();
+                block.frames = Some(frame_ranges(data_offset, body_size, &block.frame_sizes));
+            }
+            Binary::Block(block)
+        }
+        Id::SegmentUuid
+        | Id::PrevUuid
+        | Id::NextUuid
+        | Id::SegmentFamily
+        | Id::ChapterSegmentUuid
+            if body_size == 16 =>
+        {
+            Binary::Uuid(peek_uuid(input)?.1)
+        }
+        Id::Void => Binary::Void(body_size),
+        _ if options.binary_encoding == BinaryEncoding::Base64
+            && body_size <= options.max_binary_preview =>
+        {
+            let (_, bytes) = peek(take(body_size))(input)?;
+            Binary::Base64(BASE64_STANDARD.encode(bytes))
+        }
+        _ => {
+            Binary::Standard(peek_standard_binary(input, body_size, options.max_binary_preview)?.1)
+        }
     };
 
     Ok((input, binary))
 }
 
-fn peek_standard_binary(input: &[u8], size: usize) -> IResult<&[u8], String> {
-    const MAX_LENGTH: usize = 64;
-    if size <= MAX_LENGTH {
+fn peek_uuid(input: &[u8]) -> IResult<&[u8], String> {
+    let (_, bytes) = peek(take(16usize))(input)?;
+    let hex = bytes
+        .iter()
+        .map(|n| format!("{:02x}", n))
+        .collect::<String>();
+    let uuid = format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    );
+    Ok((input, uuid))
+}
+
+fn peek_standard_binary(input: &[u8], size: usize, max_length: usize) -> IResult<&[u8], String> {
+    if size <= max_length {
         let (input, bytes) = peek(take(size))(input)?;
         let string_values = bytes
             .iter()
@@ -282,6 +989,14 @@ impl Unsigned {
     fn new(id: &Id, value: u64) -> Self {
         Enumeration::new(id, value).map_or(Self::Standard(value), Self::Enumeration)
     }
+
+    /// Get the underlying integer value
+    pub fn value(&self) -> u64 {
+        match self {
+            Self::Standard(value) => *value,
+            Self::Enumeration(enumeration) => enumeration.value(),
+        }
+    }
 }
 
 /// An [EBML Body](https://github.com/ietf-wg-cellar/ebml-specification/blob/master/specification.markdown#ebml-body)
@@ -331,6 +1046,122 @@ const SYNC_ELEMENT_IDS: &[Id] = &[
     Id::Tags,
 ];
 
+// Elements within a Segment that are common enough to resynchronize to,
+// once the elements following them are validated.
+const ANCHOR_ELEMENT_IDS: &[Id] = &[
+    Id::TrackEntry,
+    Id::CuePoint,
+    Id::BlockGroup,
+    Id::SimpleBlock,
+];
+
+// Number of elements after an anchor that must be valid to resynchronize to it
+const VALIDATED_ELEMENTS: usize = 2;
+
+// Anchors larger than this are not considered sane
+const MAX_ANCHOR_SIZE: usize = 1 << 24;
+
+// Where a corrupt region ends
+pub(crate) enum SyncPoint {
+    // A sync element or a validated anchor at the given offset
+    Found(usize),
+    // An anchor at the given offset that needs more data to be validated
+    Undecided(usize),
+    NotFound,
+}
+
+// Find where valid elements start again in a corrupt region. Sync elements
+// are trusted anywhere, while anchors are only considered past the first
+// byte, as the region starts with the element that failed to parse.
+pub(crate) fn find_sync_point(input: &[u8]) -> SyncPoint {
+    const SYNC_ID_LEN: usize = 4;
+
+    for offset in 0..input.len() {
+        let window = &input[offset..];
+        let is_sync_id = window.len() >= SYNC_ID_LEN
+            && SYNC_ELEMENT_IDS.iter().any(|sync_id| {
+                window[..SYNC_ID_LEN] == sync_id.get_value().unwrap().to_be_bytes()[4..]
+            });
+        // TODO: we might want to try and parse the element here, because if the
+        // the sync element header itself is corrupt (e.g. invalid varint), then
+        // the consuming side might step into an infinite loop.
+        if is_sync_id {
+            return SyncPoint::Found(offset);
+        }
+
+        let is_anchor_id = offset > 0
+            && ANCHOR_ELEMENT_IDS
+                .iter()
+                .any(|anchor_id| window[0] as u64 == anchor_id.get_value().unwrap());
+        if is_anchor_id {
+            match validate_anchor(window) {
+                Ok(true) => return SyncPoint::Found(offset),
+                Ok(false) => (),
+                Err(_) => return SyncPoint::Undecided(offset),
+            }
+        }
+    }
+    SyncPoint::NotFound
+}
+
+// Check that an anchor has a sane size and is followed by elements that can
+// be its children (for Master elements) or its siblings.
+fn validate_anchor(input: &[u8]) -> Result<bool> {
+    let Ok((input, header)) = parse_header(input) else {
+        return Ok(false);
+    };
+    let (Some(schema), Some(body_size)) = (header.id.schema(), header.body_size) else {
+        return Ok(false);
+    };
+    if body_size > MAX_ANCHOR_SIZE {
+        return Ok(false);
+    }
+
+    let (mut input, parent_path, mut size_remaining) = if header.id.get_type() == Type::Master {
+        (input, schema.path, body_size)
+    } else {
+        let Some((parent_path, _)) = schema.path.rsplit_once('\\') else {
+            return Ok(false);
+        };
+        (take(body_size)(input)?.0, parent_path, usize::MAX)
+    };
+    if size_remaining == 0 {
+        return Ok(false);
+    }
+
+    for index in 0..VALIDATED_ELEMENTS {
+        let (remaining, next) = match parse_header(input) {
+            Ok(parsed) => parsed,
+            Err(e @ Error::NeedData(_)) => return Err(e),
+            Err(_) => return Ok(false),
+        };
+        if SYNC_ELEMENT_IDS.contains(&next.id) {
+            return Ok(true);
+        }
+        let is_expected = matches!(next.id, Id::Void | Id::Crc32)
+            || next.id.schema().is_some_and(|schema| {
+                schema
+                    .path
+                    .rsplit_once('\\')
+                    .is_some_and(|(path, _)| path == parent_path)
+            });
+        let Some(size) = next
+            .size
+            .filter(|size| is_expected && *size <= size_remaining)
+        else {
+            return Ok(false);
+        };
+        size_remaining -= size;
+        if size_remaining == 0 {
+            break;
+        }
+        if index + 1 < VALIDATED_ELEMENTS {
+            input = take(next.body_size.unwrap())(remaining)?.0;
+        }
+    }
+    Ok(true)
+}
+
 /// Parse corrupt area
 ///
 /// If we ever hit a damaged element, we can try to recover by finding
@@ -339,37 +1170,26 @@ const SYNC_ELEMENT_IDS: &[Id] = &[
 /// "Four-octet Element IDs are somewhat special in that they are useful
 /// for resynchronizing to major structures in the event of data corruption or loss."
 ///
+/// Elements that are frequent within a Segment, such as TrackEntry, CuePoint,
+/// BlockGroup and SimpleBlock, are also resynchronized to when the elements
+/// that follow them are valid, so that damage within a Cluster doesn't
+/// discard the rest of it.
+///
 /// This parser either stops once a valid sync id or consumes the whole buffer.
 /// It returns NeedData if the input is an empty slice.
 pub fn parse_corrupt(input: &[u8]) -> IResult<&[u8], Element> {
-    const SYNC_ID_LEN: usize = 4;
-
     if input.is_empty() {
-        return Err(Error::NeedData);
-    }
-
-    for (offset, window) in input.windows(SYNC_ID_LEN).enumerate() {
-        for sync_id in SYNC_ELEMENT_IDS {
-            let id_value = sync_id.get_value().unwrap();
-            let id_bytes = id_value.to_be_bytes();
-            if window == id_bytes {
-                // TODO: we might want to try and parse the element here, because if the
-                // the sync element header itself is corrupt (e.g. invalid varint), then
-                // the consuming side might step into an infinite loop.
-                return Ok((
-                    &input[offset..],
-                    Element {
-                        header: Header::new(Id::corrupted(), 0, offset),
-                        body: Body::Binary(Binary::Corrupted),
-                    },
-                ));
-            }
-        }
+        return Err(Error::NeedData(Some(NonZeroUsize::MIN)));
     }
+
+    let offset = match find_sync_point(input) {
+        SyncPoint::Found(offset) | SyncPoint::Undecided(offset) => offset,
+        SyncPoint::NotFound => input.len(),
+    };
     Ok((
-        &[],
+        &input[offset..],
         Element {
-            header: Header::new(Id::corrupted(), 0, input.len()),
+            header: Header::new(Id::corrupted(), 0, offset),
             body: Body::Binary(Binary::Corrupted),
         },
     ))
@@ -377,15 +1197,34 @@ pub fn parse_corrupt(input: &[u8]) -> IResult<&[u8], Element> {
 
 /// Parse an element
 pub fn parse_element(original_input: &[u8]) -> IResult<&[u8], Element> {
-    let (input, header) = parse_header(original_input)?;
-    let (input, body) = parse_body(&header, input)?;
+    parse_element_with_options(original_input, &ParserOptions::default())
+}
 
-    let element = Element { header, body };
+/// Parse an element with the given options
+pub fn parse_element_with_options<'a>(
+    original_input: &'a [u8],
+    options: &ParserOptions,
+) -> IResult<&'a [u8], Element> {
+    let (input, header) = parse_header_with_options(original_input, options)?;
+    let (input, body) = parse_body_with_options(&header, input, options)?;
+
+    let mut element = Element { header, body };
+    element.header.language = languages::element_language(&element);
+    element.header.discard_padding = DiscardPadding::new(&element, None);
     Ok((input, element))
 }
 
 /// Parse element body
 pub fn parse_body<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], Body> {
+    parse_body_with_options(header, input, &ParserOptions::default())
+}
+
+/// Parse element body with the given options
+pub fn parse_body_with_options<'a>(
+    header: &Header,
+    input: &'a [u8],
+    options: &ParserOptions,
+) -> IResult<&'a [u8], Body> {
     let element_type = header.id.get_type();
     let (input, body) = match element_type {
         Type::Master => (input, Body::Master),
@@ -414,7 +1253,7 @@ pub fn parse_body<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], Bod
             (input, Body::Date(value))
         }
         Type::Binary => {
-            let (input, value) = parse_binary(header, input)?;
+            let (input, value) = parse_binary(header, input, options)?;
             (input, Body::Binary(value))
         }
     };
@@ -434,33 +1273,35 @@ fn parse_string<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], Strin
 
 fn parse_date<'a>(header: &Header, input: &'a [u8]) -> IResult<&'a [u8], DateTime<Utc>> {
     let (input, timestamp_nanos_to_2001) = parse_int::<i64>(header, input)?;
-    let nanos_2001 = NaiveDate::from_ymd_opt(2001, 1, 1)
+    let epoch_2001 = NaiveDate::from_ymd_opt(2001, 1, 1)
         .ok_or(Error::InvalidDate)?
         .and_hms_opt(0, 0, 0)
         .ok_or(Error::InvalidDate)?
-        .timestamp_nanos_opt()
+        .and_utc();
+    let date = epoch_2001
+        .checked_add_signed(TimeDelta::nanoseconds(timestamp_nanos_to_2001))
         .ok_or(Error::InvalidDate)?;
-    let timestamp_seconds_to_1970 = (timestamp_nanos_to_2001 + nanos_2001) / 1_000_000_000;
-    Ok((
-        input,
-        Utc.from_utc_datetime(
-            &NaiveDateTime::from_timestamp_opt(timestamp_seconds_to_1970, 0)
-                .ok_or(Error::InvalidDate)?,
-        ),
-    ))
+    Ok((input, date))
 }
 
 trait Integer64FromBigEndianBytes {
+    // Whether values shorter than 8 bytes are sign-extended
+    const SIGNED: bool;
+
     fn from_be_bytes(input: [u8; 8]) -> Self;
 }
 
 impl Integer64FromBigEndianBytes for u64 {
+    const SIGNED: bool = false;
+
     fn from_be_bytes(input: [u8; 8]) -> Self {
         u64::from_be_bytes(input)
     }
 }
 
 impl Integer64FromBigEndianBytes for i64 {
+    const SIGNED: bool = true;
+
     fn from_be_bytes(input: [u8; 8]) -> Self {
         i64::from_be_bytes(input)
     }
@@ -477,7 +1318,8 @@ fn parse_int<'a, T: Integer64FromBigEndianBytes>(
 
     let (input, int_bytes) = take(body_size)(input)?;
 
-    let mut value_buffer = [0u8; 8];
+    let is_negative = T::SIGNED && int_bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let mut value_buffer = [if is_negative { 0xFF } else { 0 }; 8];
     value_buffer[(8 - int_bytes.len())..].copy_from_slice(int_bytes);
     let value = T::from_be_bytes(value_buffer);
 
@@ -521,8 +1363,71 @@ fn get_lacing(flags: u8) -> Option<Lacing> {
     }
 }
 
-fn parse_block(input: &[u8]) -> IResult<&[u8], Block> {
-    let (input, track_number) = parse_varint(input)?;
+// Parse the sizes of laced frames, where `data_size` is the size of the block
+// after the number of frames.
+fn parse_frame_sizes<'a>(
+    first_input: &'a [u8],
+    lacing: &Lacing,
+    num_frames: usize,
+    data_size: usize,
+) -> IResult<&'a [u8], Vec<usize>> {
+    let mut input = first_input;
+    let mut frame_sizes = Vec::with_capacity(num_frames);
+    match lacing {
+        Lacing::FixedSize => {
+            if !data_size.is_multiple_of(num_frames) {
+                return Err(Error::InvalidLacing);
+            }
+            frame_sizes.resize(num_frames, data_size / num_frames);
+            return Ok((input, frame_sizes));
+        }
+        Lacing::Xiph => {
+            for _ in 1..num_frames {
+                let mut frame_size = 0;
+                loop {
+                    let (next_input, byte) = take(1usize)(input)?;
+                    input = next_input;
+                    frame_size += byte[0] as usize;
+                    if byte[0] != 0xFF {
+                        break;
+                    }
+                }
+                frame_sizes.push(frame_size);
+            }
+        }
+        Lacing::Ebml => {
+            let mut frame_size = 0;
+            for index in 1..num_frames {
+                let (next_input, value) = parse_varint(input)?;
+                let value = value.ok_or(Error::InvalidLacing)?;
+                frame_size = if index == 1 {
+                    value
+                } else {
+                    // Following sizes are signed differences to the previous
+                    // size, with the value range shifted to be unsigned.
+                    let length = input.len() - next_input.len();
+                    let bias = (1 << (7 * length - 1)) - 1;
+                    (frame_size + value)
+                        .checked_sub(bias)
+                        .ok_or(Error::InvalidLacing)?
+                };
+                input = next_input;
+                frame_sizes.push(frame_size);
+            }
+        }
+    }
+
+    // The last frame takes the rest of the block
+    let laced_size = first_input.len() - input.len() + frame_sizes.iter().sum::<usize>();
+    let last_frame_size = data_size
+        .checked_sub(laced_size)
+        .ok_or(Error::InvalidLacing)?;
+    frame_sizes.push(last_frame_size);
+    Ok((input, frame_sizes))
+}
+
+fn parse_block(first_input: &[u8], size: usize) -> IResult<&[u8], Block> {
+    let (input, track_number) = parse_varint(first_input)?;
     let track_number = track_number.ok_or(Error::MissingTrackNumber)?;
     let (input, timestamp) = parse_i16(input)?;
     let (input, flags) = take(1usize)(input)?;
@@ -530,12 +1435,19 @@ fn parse_block(input: &[u8]) -> IResult<&[u8], Block> {
 
     let invisible = is_invisible(flags);
     let lacing = get_lacing(flags);
-    let (input, num_frames) = if lacing.is_some() {
-        let (input, next_byte) = take(1usize)(input)?;
-        let num_frames = next_byte[0];
-        (input, Some(num_frames + 1))
-    } else {
-        (input, None)
+    let (input, num_frames, frame_sizes, frame_size) = match lacing {
+        Some(ref lacing) => {
+            let (input, next_byte) = take(1usize)(input)?;
+            let num_frames = next_byte[0] as usize + 1;
+            let header_size = first_input.len() - input.len();
+            let data_size = size.checked_sub(header_size).ok_or(Error::InvalidLacing)?;
+            let (input, frame_sizes) = parse_frame_sizes(input, lacing, num_frames, data_size)?;
+            (input, Some(num_frames), Some(frame_sizes), None)
+        }
+        None => {
+            let header_size = first_input.len() - input.len();
+            (input, None, None, size.checked_sub(header_size))
+        }
     };
 
     Ok((
@@ -544,14 +1456,21 @@ fn parse_block(input: &[u8]) -> IResult<&[u8], Block> {
             track_number,
             timestamp,
             invisible,
+            frame_type: None,
             lacing,
             num_frames,
+            frame_sizes,
+            frame_size,
+            frames: None,
+            encryption: None,
+            absolute_time_ns: None,
+            absolute_time: None,
         },
     ))
 }
 
-fn parse_simple_block(input: &[u8]) -> IResult<&[u8], SimpleBlock> {
-    let (input, track_number) = parse_varint(input)?;
+fn parse_simple_block(first_input: &[u8], size: usize) -> IResult<&[u8], SimpleBlock> {
+    let (input, track_number) = parse_varint(first_input)?;
     let track_number = track_number.ok_or(Error::MissingTrackNumber)?;
     let (input, timestamp) = parse_i16(input)?;
     let (input, flags) = take(1usize)(input)?;
@@ -561,12 +1480,19 @@ fn parse_simple_block(input: &[u8]) -> IResult<&[u8], SimpleBlock> {
     let invisible = is_invisible(flags);
     let lacing = get_lacing(flags);
     let discardable = (flags & 0b1) != 0;
-    let (input, num_frames) = if lacing.is_some() {
-        let (input, next_byte) = take(1usize)(input)?;
-        let num_frames = next_byte[0];
-        (input, Some(num_frames + 1))
-    } else {
-        (input, None)
+    let (input, num_frames, frame_sizes, frame_size) = match lacing {
+        Some(ref lacing) => {
+            let (input, next_byte) = take(1usize)(input)?;
+            let num_frames = next_byte[0] as usize + 1;
+            let header_size = first_input.len() - input.len();
+            let data_size = size.checked_sub(header_size).ok_or(Error::InvalidLacing)?;
+            let (input, frame_sizes) = parse_frame_sizes(input, lacing, num_frames, data_size)?;
+            (input, Some(num_frames), Some(frame_sizes), None)
+        }
+        None => {
+            let header_size = first_input.len() - input.len();
+            (input, None, None, size.checked_sub(header_size))
+        }
     };
 
     Ok((
@@ -579,10 +1505,36 @@ fn parse_simple_block(input: &[u8]) -> IResult<&[u8], SimpleBlock> {
             lacing,
             discardable,
             num_frames,
+            frame_sizes,
+            frame_size,
+            frames: None,
+            encryption: None,
+            absolute_time_ns: None,
+            absolute_time: None,
         },
     ))
 }
 
+/// Split the body of a SimpleBlock or Block into the payloads of its
+/// frames, undoing lacing. The track number of the block is returned along
+/// with them.
+pub fn split_frames(body: &[u8]) -> Result<(usize, Vec<&[u8]>)> {
+    // SimpleBlocks only differ from Blocks by flags that don't change how
+    // their frames are laid out
+    let (mut data, block) = parse_block(body, body.len())?;
+    let frame_sizes = block.frame_sizes.unwrap_or_else(|| vec![data.len()]);
+    let mut frames = Vec::with_capacity(frame_sizes.len());
+    for size in frame_sizes {
+        if size > data.len() {
+            return Err(Error::InvalidLacing);
+        }
+        let (frame, remaining) = data.split_at(size);
+        frames.push(frame);
+        data = remaining;
+    }
+    Ok((block.track_number, frames))
+}
+
 /// Helper to add resiliency to corrupt inputs
 pub fn parse_element_or_corrupted(input: &[u8]) -> IResult<&[u8], Element> {
     parse_element(input).or_else(|_| parse_corrupt(input))
@@ -612,7 +1564,10 @@ mod tests {
         assert_eq!(parse_id(&[0x23, 0x83, 0xE3]), Ok((EMPTY, Id::FrameRate)));
 
         // 1 byte missing from FrameRate (3-bytes long)
-        assert_eq!(parse_id(&[0x23, 0x83]), Err(Error::NeedData));
+        assert_eq!(
+            parse_id(&[0x23, 0x83]),
+            Err(Error::NeedData(NonZeroUsize::new(1)))
+        );
 
         // Longer than 4 bytes
         const FAILURE_INPUT: &[u8] = &[0x08, 0x45, 0xDF, 0xA3];
@@ -625,6 +1580,36 @@ mod tests {
         assert_eq!(id.get_value().unwrap(), 0x19ABCDEF);
     }
 
+    #[test]
+    fn test_id_schema() {
+        assert_eq!(Id::TimestampScale.default_value(), Some("1000000"));
+        assert_eq!(Id::TimestampScale.value_range(), Some("not 0"));
+        assert_eq!(Id::TimestampScale.min_doctype_version(), Some(1));
+        assert!(!Id::TimestampScale.is_deprecated());
+        assert_eq!(Id::BlockAdditionMapping.min_doctype_version(), Some(4));
+        assert!(Id::SilentTracks.is_deprecated());
+        assert_eq!(Id::SilentTracks.max_doctype_version(), Some(0));
+        assert_eq!(Id::Unknown(0x19ABCDEF).min_doctype_version(), None);
+    }
+
+    #[test]
+    fn test_id_names() {
+        assert_eq!(Id::from_name("TimestampScale"), Some(Id::TimestampScale));
+        assert_eq!(Id::from_name("EBMLMaxIDLength"), Some(Id::EbmlMaxIdLength));
+        assert_eq!(Id::from_name("TimecodeScale"), None);
+        assert_eq!(Id::EbmlMaxIdLength.to_string(), "EBMLMaxIDLength");
+        assert_eq!(Id::Unknown(0x19ABCDEF).to_string(), "0x19ABCDEF");
+
+        for id in [Id::Cluster, Id::Unknown(0x19ABCDEF), Id::Corrupted] {
+            assert_eq!(id.to_string().parse(), Ok(id));
+        }
+        assert_eq!("0x1F43B675".parse(), Ok(Id::Cluster));
+        assert_eq!(
+            "Foo".parse::<Id>(),
+            Err(Error::UnknownElementName("Foo".to_string()))
+        );
+    }
+
     #[test]
     fn test_parse_varint() {
         assert_eq!(parse_varint(&[0x9F]), Ok((EMPTY, Some(31))));
@@ -721,12 +1706,203 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_with_options() {
+        // Tracks with unknown size
+        const TRACKS: &[u8] = &[0x16, 0x54, 0xAE, 0x6B, 0xFF];
+        assert_eq!(parse_header(TRACKS), Err(Error::ForbiddenUnknownSize));
+        let options = ParserOptions {
+            lenient_unknown_size: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_header_with_options(TRACKS, &options),
+            Ok((EMPTY, Header::with_unknown_size(Id::Tracks, 5)))
+        );
+
+        // Title with unknown size, followed by a MuxingApp
+        const TITLE: &[u8] = &[0x7B, 0xA9, 0xFF, 0x61, 0x62, 0x63, 0x4D, 0x80, 0x81, 0x78];
+        assert_eq!(parse_element(TITLE), Err(Error::ForbiddenUnknownSize));
+        let options = ParserOptions {
+            lenient_unknown_leaf_size: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_element_with_options(TITLE, &options),
+            Ok((
+                &TITLE[6..],
+                Element {
+                    header: Header {
+                        computed_size: Some(6),
+                        ..Header::new(Id::Title, 3, 3)
+                    },
+                    body: Body::Utf8("abc".to_string()),
+                }
+            ))
+        );
+        assert_eq!(
+            parse_element_with_options(&TITLE[..6], &options),
+            Err(Error::NeedData(None))
+        );
+
+        const DOC_TYPE: &[u8] = &[0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D];
+        let options = ParserOptions {
+            max_element_size: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_element_with_options(DOC_TYPE, &options),
+            Err(Error::ElementTooLarge)
+        );
+
+        // EBML header with a size coded on 2 bytes
+        const EBML: &[u8] = &[0x1A, 0x45, 0xDF, 0xA3, 0x40, 0x00];
+        let options = ParserOptions {
+            header_field_sizes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_header_with_options(EBML, &options),
+            Ok((
+                EMPTY,
+                Header {
+                    id_size: Some(4),
+                    size_field_size: Some(2),
+                    ..Header::new(Id::Ebml, 6, 0)
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unknown_leaf_body_size() {
+        let limits = EbmlLimits::default();
+        let scanned = Cell::new(0);
+
+        // A MuxingApp ID followed by an invalid size doesn't end the Title
+        const BODY: &[u8] = &[0x61, 0x4D, 0x80, 0x00, 0x62, 0x4D, 0x80, 0x81, 0x78];
+        assert_eq!(
+            unknown_leaf_body_size(&Id::Title, BODY, &limits, &scanned),
+            Ok(5)
+        );
+
+        // Nor does one whose body doesn't fit in the data, until it does
+        const PARTIAL: &[u8] = &[0x61, 0x4D, 0x80, 0x82, 0x78];
+        assert_eq!(
+            unknown_leaf_body_size(&Id::Title, PARTIAL, &limits, &scanned),
+            Err(Error::NeedData(None))
+        );
+        assert_eq!(scanned.get(), 1);
+        let complete = [PARTIAL, &[0x79]].concat();
+        assert_eq!(
+            unknown_leaf_body_size(&Id::Title, &complete, &limits, &scanned),
+            Ok(1)
+        );
+        assert_eq!(scanned.get(), 0);
+
+        // The search resumes from where it stopped
+        scanned.set(2);
+        assert_eq!(
+            unknown_leaf_body_size(&Id::Title, &[PARTIAL, BODY].concat(), &limits, &scanned),
+            Ok(10)
+        );
+    }
+
+    #[test]
+    fn test_parse_block_encryption() {
+        assert_eq!(
+            parse_block_encryption(&[0x00, 0xAB]),
+            Ok((
+                &[0xAB][..],
+                BlockEncryption {
+                    status: EncryptionStatus::Clear,
+                    iv: None,
+                    partitions: None
+                }
+            ))
+        );
+        assert_eq!(
+            parse_block_encryption(&[0x01, 0, 0, 0, 0, 0, 0, 0, 0x2A]),
+            Ok((
+                EMPTY,
+                BlockEncryption {
+                    status: EncryptionStatus::Encrypted,
+                    iv: Some("000000000000002a".into()),
+                    partitions: None
+                }
+            ))
+        );
+        assert_eq!(
+            parse_block_encryption(&[
+                0x03, 0, 0, 0, 0, 0, 0, 0, 0x2A, 0x02, 0, 0, 0, 0x10, 0, 0, 0, 0x20
+            ]),
+            Ok((
+                EMPTY,
+                BlockEncryption {
+                    status: EncryptionStatus::PartiallyEncrypted,
+                    iv: Some("000000000000002a".into()),
+                    partitions: Some(vec![0x10, 0x20])
+                }
+            ))
+        );
+        assert_eq!(
+            parse_block_encryption(&[0x01, 0]),
+            Err(Error::NeedData(NonZeroUsize::new(7)))
+        );
+    }
+
+    #[test]
+    fn test_frame_ranges() {
+        // SimpleBlock with two frames of 5 bytes using fixed-size lacing
+        let mut input = vec![0xA3, 0x8F, 0x81, 0x00, 0x00, 0x84, 0x01];
+        input.extend_from_slice(&[0; 10]);
+        let options = ParserOptions {
+            frame_ranges: true,
+            ..Default::default()
+        };
+
+        let (_, element) = parse_element_with_options(&input, &options).unwrap();
+        let Body::Binary(Binary::SimpleBlock(block)) = element.body else {
+            panic!("expected a SimpleBlock");
+        };
+        assert_eq!(
+            block.frames(),
+            Some(
+                &[
+                    Frame { offset: 5, size: 5 },
+                    Frame {
+                        offset: 10,
+                        size: 5
+                    }
+                ][..]
+            )
+        );
+
+        let element = crate::iter::ElementIterator::new(input.as_slice())
+            .with_options(options)
+            .next()
+            .unwrap()
+            .unwrap();
+        let Body::Binary(Binary::SimpleBlock(block)) = element.body else {
+            panic!("expected a SimpleBlock");
+        };
+        assert_eq!(block.frames().unwrap()[0], Frame { offset: 7, size: 5 });
+    }
+
     #[test]
     fn test_parse_int() {
         assert_eq!(
             parse_int(&Header::new(Id::EbmlVersion, 3, 1), &[0x01]),
             Ok((EMPTY, 1u64))
         );
+        assert_eq!(
+            parse_int(&Header::new(Id::ReferenceBlock, 1, 2), &[0xFF, 0xFE]),
+            Ok((EMPTY, -2i64))
+        );
+        assert_eq!(
+            parse_int(&Header::new(Id::ReferenceBlock, 1, 2), &[0x00, 0xFE]),
+            Ok((EMPTY, 254i64))
+        );
         assert_eq!(
             parse_int::<u64>(&Header::with_unknown_size(Id::EbmlVersion, 3), EMPTY),
             Err(Error::ForbiddenUnknownSize)
@@ -768,11 +1944,19 @@ mod tests {
     fn test_parse_binary() {
         const BODY: &[u8] = &[0x15, 0x49, 0xA9, 0x66];
         assert_eq!(
-            parse_binary(&Header::new(Id::SeekId, 3, 4), BODY),
+            parse_binary(
+                &Header::new(Id::SeekId, 3, 4),
+                BODY,
+                &ParserOptions::default()
+            ),
             Ok((EMPTY, Binary::SeekId(Id::Info)))
         );
         assert_eq!(
-            parse_binary(&Header::with_unknown_size(Id::SeekId, 3), EMPTY),
+            parse_binary(
+                &Header::with_unknown_size(Id::SeekId, 3),
+                EMPTY,
+                &ParserOptions::default()
+            ),
             Err(Error::ForbiddenUnknownSize)
         );
     }
@@ -791,7 +1975,22 @@ mod tests {
                 &[0x09, 0x76, 0x97, 0xbd, 0xca, 0xc9, 0x1e, 0x00]
             ),
             Ok((EMPTY, expected_datetime))
+        );
+
+        // Sub-second precision is kept
+        let (_, datetime) = parse_date(
+            &Header::new(Id::DateUtc, 1, 8),
+            &[0x09, 0x76, 0x97, 0xbd, 0xd2, 0x24, 0xeb, 0x15],
         )
+        .unwrap();
+        assert_eq!(
+            datetime,
+            expected_datetime + TimeDelta::nanoseconds(123_456_789)
+        );
+        assert_eq!(
+            serde_yaml::to_string(&datetime).unwrap().trim(),
+            "2022-08-11T08:27:15.123456789Z"
+        );
     }
 
     #[test]
@@ -891,29 +2090,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_split_frames() {
+        assert_eq!(
+            split_frames(&[0x81, 0x00, 0x00, 0x80, 0xAA, 0xBB]),
+            Ok((1, vec![[0xAA, 0xBB].as_slice()]))
+        );
+        assert_eq!(
+            split_frames(&[0x82, 0x00, 0x00, 0x02, 0x02, 0x01, 0x02, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE]),
+            Ok((2, vec![[0xAA].as_slice(), &[0xBB, 0xCC], &[0xDD, 0xEE]]))
+        );
+        assert_eq!(
+            split_frames(&[0x81, 0x00, 0x00, 0x02, 0x01, 0x05, 0xAA]),
+            Err(Error::InvalidLacing)
+        );
+    }
+
     #[test]
     fn test_parse_block() {
         assert_eq!(
-            parse_block(&[0x81, 0x0F, 0x7A, 0x00]),
+            parse_block(&[0x81, 0x0F, 0x7A, 0x00], 4),
             Ok((
                 EMPTY,
                 Block {
                     track_number: 1,
                     timestamp: 3962,
                     invisible: false,
+                    frame_type: None,
                     lacing: None,
-                    num_frames: None
+                    num_frames: None,
+                    frame_sizes: None,
+                    frame_size: Some(0),
+                    frames: None,
+                    encryption: None,
+                    absolute_time_ns: None,
+                    absolute_time: None,
+                }
+            ))
+        );
+
+        assert_eq!(
+            parse_block(&[0x81, 0x00, 0x00, 0x02, 0x02, 0xFF, 0x05, 0x0A], 308),
+            Ok((
+                EMPTY,
+                Block {
+                    track_number: 1,
+                    timestamp: 0,
+                    invisible: false,
+                    frame_type: None,
+                    lacing: Some(Lacing::Xiph),
+                    num_frames: Some(3),
+                    frame_sizes: Some(vec![260, 10, 30]),
+                    frame_size: None,
+                    frames: None,
+                    encryption: None,
+                    absolute_time_ns: None,
+                    absolute_time: None,
                 }
             ))
         );
 
-        assert_eq!(parse_block(UNKNOWN_VARINT), Err(Error::MissingTrackNumber));
+        assert_eq!(
+            parse_block(UNKNOWN_VARINT, 1),
+            Err(Error::MissingTrackNumber)
+        );
     }
 
     #[test]
     fn test_parse_simple_block() {
         assert_eq!(
-            parse_simple_block(&[0x81, 0x00, 0x53, 0x00]),
+            parse_simple_block(&[0x81, 0x00, 0x53, 0x00], 4),
             Ok((
                 EMPTY,
                 SimpleBlock {
@@ -924,12 +2170,65 @@ mod tests {
                     lacing: None,
                     discardable: false,
                     num_frames: None,
+                    frame_sizes: None,
+                    frame_size: Some(0),
+                    frames: None,
+                    encryption: None,
+                    absolute_time_ns: None,
+                    absolute_time: None,
+                }
+            ))
+        );
+
+        assert_eq!(
+            parse_simple_block(&[0x81, 0x00, 0x00, 0x86, 0x02, 0x8A, 0xC1], 34),
+            Ok((
+                EMPTY,
+                SimpleBlock {
+                    track_number: 1,
+                    timestamp: 0,
+                    keyframe: true,
+                    invisible: false,
+                    lacing: Some(Lacing::Ebml),
+                    discardable: false,
+                    num_frames: Some(3),
+                    frame_sizes: Some(vec![10, 12, 5]),
+                    frame_size: None,
+                    frames: None,
+                    encryption: None,
+                    absolute_time_ns: None,
+                    absolute_time: None,
                 }
             ))
         );
 
         assert_eq!(
-            parse_simple_block(UNKNOWN_VARINT),
+            parse_simple_block(&[0x81, 0x00, 0x00, 0x84, 0x01], 13)
+                .unwrap()
+                .1
+                .frame_sizes,
+            Some(vec![4, 4])
+        );
+        assert_eq!(
+            parse_simple_block(&[0x81, 0x00, 0x00, 0x84, 0x01], 12),
+            Err(Error::InvalidLacing)
+        );
+
+        // Track number coded on more bytes than needed
+        let block = parse_simple_block(&[0x40, 0x01, 0x00, 0x00, 0x80], 10)
+            .unwrap()
+            .1;
+        assert_eq!(Binary::SimpleBlock(block).frame_sizes(), Some(vec![5]));
+
+        // Fixed-size lacing of the maximum of 256 frames
+        let block = parse_simple_block(&[0x81, 0x00, 0x00, 0x84, 0xFF], 261)
+            .unwrap()
+            .1;
+        assert_eq!(block.num_frames, Some(256));
+        assert_eq!(block.frame_sizes, Some(vec![1; 256]));
+
+        assert_eq!(
+            parse_simple_block(UNKNOWN_VARINT, 1),
             Err(Error::MissingTrackNumber)
         );
     }
@@ -937,10 +2236,49 @@ mod tests {
     #[test]
     fn test_peek_standard_binary() -> Result<()> {
         let input = &[1, 2, 3];
-        assert_eq!(peek_standard_binary(input, 3)?.1, "[01 02 03]");
+        assert_eq!(peek_standard_binary(input, 3, 64)?.1, "[01 02 03]");
+        assert_eq!(peek_standard_binary(input, 3, 2)?.1, "3 bytes");
 
         let input = &[0; 5];
-        assert_eq!(peek_standard_binary(input, 65)?.1, "65 bytes");
+        assert_eq!(peek_standard_binary(input, 65, 64)?.1, "65 bytes");
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_uuid() -> Result<()> {
+        let input = (0..16).collect::<Vec<u8>>();
+        let header = Header::new(Id::SegmentUuid, 3, 16);
+        assert_eq!(
+            peek_binary(&header, &input)?.1,
+            Binary::Uuid("00010203-0405-0607-0809-0a0b0c0d0e0f".into())
+        );
+
+        // Identifiers with an invalid length are shown as standard binaries
+        let header = Header::new(Id::SegmentUuid, 3, 4);
+        assert_eq!(
+            peek_binary(&header, &input)?.1,
+            Binary::Standard("[00 01 02 03]".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_base64_binary() -> Result<()> {
+        let options = ParserOptions {
+            binary_encoding: BinaryEncoding::Base64,
+            max_binary_preview: 4,
+            ..ParserOptions::default()
+        };
+        let header = Header::new(Id::CodecPrivate, 3, 4);
+        assert_eq!(
+            peek_binary_with_options(&header, &[0xDE, 0xAD, 0xBE, 0xEF], &options)?.1,
+            Binary::Base64("3q2+7w==".into())
+        );
+        let header = Header::new(Id::CodecPrivate, 3, 5);
+        assert_eq!(
+            peek_binary_with_options(&header, &[0; 5], &options)?.1,
+            Binary::Standard("5 bytes".into())
+        );
         Ok(())
     }
 
@@ -975,4 +2313,29 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn test_parse_corrupt_anchors() {
+        // A BlockGroup with a Block and a BlockDuration
+        const BLOCK_GROUP: &[u8] = &[
+            0xA0, 0x8A, 0xA1, 0x85, 0x81, 0x00, 0x00, 0x00, 0x2A, 0x9B, 0x81, 0x01,
+        ];
+        let corrupted = |size| Element {
+            header: Header::new(Id::corrupted(), 0, size),
+            body: Body::Binary(Binary::Corrupted),
+        };
+
+        let input = [&[0x42, 0x87, 0x90, 0x01], BLOCK_GROUP].concat();
+        assert_eq!(parse_corrupt(&input), Ok((BLOCK_GROUP, corrupted(4))));
+
+        // Not enough data to validate the BlockGroup yet
+        assert!(matches!(
+            find_sync_point(&input[..8]),
+            SyncPoint::Undecided(4)
+        ));
+
+        // A SimpleBlock that is not followed by a valid element
+        let input = [0x42, 0x87, 0x90, 0x01, 0xA3, 0x81, 0x00, 0x00, 0x00];
+        assert_eq!(parse_corrupt(&input), Ok((EMPTY, corrupted(9))));
+    }
 }
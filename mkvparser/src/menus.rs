@@ -0,0 +1,62 @@
+//! Decoding of chapter process commands
+//!
+//! The commands of a ChapProcess are interpreted according to its
+//! ChapProcessCodecID, so they are only decoded by the element iterators.
+use crate::{take, Error, IResult};
+
+/// ChapProcessCodecID of the DVD command set
+pub const DVD_CHAP_PROCESS_CODEC_ID: u64 = 1;
+
+/// Size of a DVD virtual machine command
+const DVD_COMMAND_SIZE: usize = 8;
+
+/// Split the ChapProcessData of the DVD command set into commands, formatted
+/// as hexadecimal bytes such as `[30 02 00 00 00 01 00 00]`.
+///
+/// The data starts with the number of commands, followed by the 8-bytes
+/// commands.
+pub fn parse_dvd_commands(input: &[u8]) -> IResult<&[u8], Vec<String>> {
+    let (input, count) = take(1usize)(input)?;
+    let (input, commands) = take(count[0] as usize * DVD_COMMAND_SIZE)(input)?;
+    if !input.is_empty() {
+        return Err(Error::Parser);
+    }
+    let commands = commands
+        .chunks(DVD_COMMAND_SIZE)
+        .map(|command| {
+            let bytes = command
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>();
+            format!("[{}]", bytes.join(" "))
+        })
+        .collect();
+    Ok((input, commands))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_dvd_commands() {
+        const INPUT: &[u8] = &[
+            0x02, 0x30, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x20, 0x04, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x03,
+        ];
+        assert_eq!(
+            parse_dvd_commands(INPUT).unwrap().1,
+            [
+                "[30 02 00 00 00 01 00 00]".to_string(),
+                "[20 04 00 00 00 00 00 03]".to_string()
+            ]
+        );
+        assert_eq!(
+            parse_dvd_commands(&INPUT[..9]),
+            Err(Error::NeedData(NonZeroUsize::new(8)))
+        );
+        assert_eq!(parse_dvd_commands(&[0x00, 0x00]), Err(Error::Parser));
+    }
+}
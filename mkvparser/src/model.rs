@@ -0,0 +1,1622 @@
+//! Strongly-typed view over the most commonly used Matroska elements
+//!
+//! A [`Document`] is built from parsed elements and exposes the segment
+//! information, tracks, cues, chapters, attachments and tags as plain
+//! structs, so that they can be used without walking element trees by ID.
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    io::{Read, Seek},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    codecs::CodecPrivate,
+    elements::Id,
+    enumerations::{ChapProcessTime, ContentCompAlgo, TrackPlaneType, TrackType},
+    iter::ElementIterator,
+    tree::{build_element_trees, ElementTree},
+    Binary, Body, Element, Result,
+};
+
+/// Default TimestampScale value, in nanoseconds
+pub const DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+
+fn children(tree: &ElementTree) -> &[ElementTree] {
+    match tree {
+        ElementTree::Master(master) => &master.children,
+        ElementTree::Normal(_) => &[],
+    }
+}
+
+fn tree_id(tree: &ElementTree) -> &Id {
+    match tree {
+        ElementTree::Master(master) => &master.header.id,
+        ElementTree::Normal(element) => &element.header.id,
+    }
+}
+
+fn find(trees: &[ElementTree], id: Id) -> Option<&ElementTree> {
+    trees.iter().find(|tree| *tree_id(tree) == id)
+}
+
+fn find_all(trees: &[ElementTree], id: Id) -> impl Iterator<Item = &ElementTree> {
+    trees.iter().filter(move |tree| *tree_id(tree) == id)
+}
+
+fn body(trees: &[ElementTree], id: Id) -> Option<&Body> {
+    match find(trees, id)? {
+        ElementTree::Normal(element) => Some(&element.body),
+        ElementTree::Master(_) => None,
+    }
+}
+
+fn unsigned(trees: &[ElementTree], id: Id) -> Option<u64> {
+    match body(trees, id)? {
+        Body::Unsigned(value) => Some(value.value()),
+        _ => None,
+    }
+}
+
+fn flag(trees: &[ElementTree], id: Id, default: bool) -> bool {
+    unsigned(trees, id).map_or(default, |value| value != 0)
+}
+
+fn float(trees: &[ElementTree], id: Id) -> Option<f64> {
+    match body(trees, id)? {
+        Body::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn string(trees: &[ElementTree], id: Id) -> Option<String> {
+    match body(trees, id)? {
+        Body::String(value) | Body::Utf8(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn date(trees: &[ElementTree], id: Id) -> Option<DateTime<Utc>> {
+    match body(trees, id)? {
+        Body::Date(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn binary(trees: &[ElementTree], id: Id) -> Option<Binary> {
+    match body(trees, id)? {
+        Body::Binary(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// General information about a Segment, from the Info element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SegmentInfo {
+    /// Timestamp scale in nanoseconds
+    pub timestamp_scale: u64,
+    /// Duration of the Segment, in units of `timestamp_scale`
+    pub duration: Option<f64>,
+    /// Date of the origin of timestamp
+    pub date_utc: Option<DateTime<Utc>>,
+    /// General name of the Segment
+    pub title: Option<String>,
+    /// Muxing application or library
+    pub muxing_app: Option<String>,
+    /// Writing application
+    pub writing_app: Option<String>,
+    /// Unique ID of the Segment
+    pub segment_uuid: Option<Binary>,
+}
+
+impl SegmentInfo {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            timestamp_scale: unsigned(trees, Id::TimestampScale).unwrap_or(DEFAULT_TIMESTAMP_SCALE),
+            duration: float(trees, Id::Duration),
+            date_utc: date(trees, Id::DateUtc),
+            title: string(trees, Id::Title),
+            muxing_app: string(trees, Id::MuxingApp),
+            writing_app: string(trees, Id::WritingApp),
+            segment_uuid: binary(trees, Id::SegmentUuid),
+        }
+    }
+
+    /// Duration of the Segment in nanoseconds
+    pub fn duration_ns(&self) -> Option<f64> {
+        self.duration
+            .map(|duration| duration * self.timestamp_scale as f64)
+    }
+}
+
+/// Video settings of a track
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Video {
+    /// Width of the encoded frames
+    pub pixel_width: Option<u64>,
+    /// Height of the encoded frames
+    pub pixel_height: Option<u64>,
+    /// Width of the video frames to display
+    pub display_width: Option<u64>,
+    /// Height of the video frames to display
+    pub display_height: Option<u64>,
+}
+
+impl Video {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            pixel_width: unsigned(trees, Id::PixelWidth),
+            pixel_height: unsigned(trees, Id::PixelHeight),
+            display_width: unsigned(trees, Id::DisplayWidth),
+            display_height: unsigned(trees, Id::DisplayHeight),
+        }
+    }
+}
+
+/// Audio settings of a track
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Audio {
+    /// Sampling frequency in Hz
+    pub sampling_frequency: f64,
+    /// Real output sampling frequency in Hz
+    pub output_sampling_frequency: Option<f64>,
+    /// Number of channels
+    pub channels: u64,
+    /// Bits per sample
+    pub bit_depth: Option<u64>,
+}
+
+impl Audio {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            sampling_frequency: float(trees, Id::SamplingFrequency).unwrap_or(8000.0),
+            output_sampling_frequency: float(trees, Id::OutputSamplingFrequency),
+            channels: unsigned(trees, Id::Channels).unwrap_or(1),
+            bit_depth: unsigned(trees, Id::BitDepth),
+        }
+    }
+}
+
+/// How BlockAdditional elements of a track are interpreted, from a
+/// BlockAdditionMapping element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockAdditionMapping {
+    /// BlockAddID value this mapping applies to
+    pub value: Option<u64>,
+    /// Human-readable name
+    pub name: Option<String>,
+    /// Type of the data, such as ITU-T T.35 metadata or a four-character code
+    pub block_add_id_type: u64,
+    /// Extra data, such as a Dolby Vision configuration
+    pub extra_data: Option<Binary>,
+}
+
+impl BlockAdditionMapping {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            value: unsigned(trees, Id::BlockAddIdValue),
+            name: string(trees, Id::BlockAddIdName),
+            block_add_id_type: unsigned(trees, Id::BlockAddIdType).unwrap_or(0),
+            extra_data: binary(trees, Id::BlockAddIdExtraData),
+        }
+    }
+}
+
+/// A track used by a virtual track, from a TrackPlane element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackPlane {
+    /// Unique ID of the track used
+    pub uid: Option<u64>,
+    /// How the track is used, such as the left eye of a 3D video
+    pub plane_type: Option<TrackPlaneType>,
+}
+
+impl TrackPlane {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            uid: unsigned(trees, Id::TrackPlaneUid),
+            plane_type: unsigned(trees, Id::TrackPlaneType).and_then(TrackPlaneType::new),
+        }
+    }
+}
+
+/// Compression of the frames of a track, from the ContentCompression
+/// element of a ContentEncoding
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContentCompression {
+    /// Compression algorithm
+    pub algorithm: Option<ContentCompAlgo>,
+    /// Size of the compression settings, such as the bytes stripped from
+    /// the start of each frame with header stripping
+    pub settings_size: Option<usize>,
+    /// Absolute position of the compression settings in the input. Only
+    /// known if elements have positions.
+    pub settings_position: Option<usize>,
+}
+
+impl ContentCompression {
+    fn new(trees: &[ElementTree]) -> Self {
+        let settings = find(trees, Id::ContentCompSettings).map(ElementTree::header);
+        Self {
+            algorithm: ContentCompAlgo::new(unsigned(trees, Id::ContentCompAlgo).unwrap_or(0)),
+            settings_size: settings.and_then(|header| header.body_size),
+            settings_position: settings.and_then(|header| {
+                header
+                    .position
+                    .map(|position| position + header.header_size)
+            }),
+        }
+    }
+}
+
+/// How a virtual track is built from other tracks, from a TrackOperation
+/// element
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackOperation {
+    /// Video tracks combined into planes, such as the two eyes of a 3D video
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub combine_planes: Vec<TrackPlane>,
+    /// Unique IDs of the tracks whose blocks are joined into this track
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub join_uids: Vec<u64>,
+}
+
+impl TrackOperation {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            combine_planes: find_all(trees, Id::TrackCombinePlanes)
+                .flat_map(|planes| find_all(children(planes), Id::TrackPlane))
+                .map(|plane| TrackPlane::new(children(plane)))
+                .collect(),
+            join_uids: find_all(trees, Id::TrackJoinBlocks)
+                .flat_map(|join_blocks| find_all(children(join_blocks), Id::TrackJoinUid))
+                .filter_map(|uid| unsigned(std::slice::from_ref(uid), Id::TrackJoinUid))
+                .collect(),
+        }
+    }
+}
+
+/// A track, from a TrackEntry element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackEntry {
+    /// Track number, as used in blocks
+    pub track_number: Option<u64>,
+    /// Unique ID of the track
+    pub track_uid: Option<u64>,
+    /// Type of the track
+    pub track_type: Option<TrackType>,
+    /// Codec ID
+    pub codec_id: Option<String>,
+    /// Codec private data
+    pub codec_private: Option<Binary>,
+    /// Size of the codec private data in bytes
+    pub codec_private_size: Option<usize>,
+    /// Absolute position of the codec private data in the input. Only known
+    /// if elements have positions.
+    pub codec_private_position: Option<usize>,
+    /// Human-readable name of the track
+    pub name: Option<String>,
+    /// Language of the track, in the Matroska languages form
+    pub language: String,
+    /// Language of the track, as a BCP 47 tag
+    pub language_bcp47: Option<String>,
+    /// Whether the track is usable
+    pub enabled: bool,
+    /// Whether the track is eligible for automatic selection
+    pub default: bool,
+    /// Whether the track is forced to be presented
+    pub forced: bool,
+    /// Whether the track is suitable for users with hearing impairments
+    pub hearing_impaired: bool,
+    /// Whether the track is suitable for users with visual impairments
+    pub visual_impaired: bool,
+    /// Whether the track is in the original language of the content
+    pub original: bool,
+    /// Whether the track contains commentary
+    pub commentary: bool,
+    /// Duration of each frame, in nanoseconds
+    pub default_duration: Option<u64>,
+    /// Codec-built-in delay, in nanoseconds
+    pub codec_delay: u64,
+    /// Time to seek back before the seek point, in nanoseconds
+    pub seek_pre_roll: u64,
+    /// Video settings
+    pub video: Option<Video>,
+    /// Audio settings
+    pub audio: Option<Audio>,
+    /// How BlockAdditional elements of the track are interpreted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub block_addition_mappings: Vec<BlockAdditionMapping>,
+    /// How the track is built from other tracks, for virtual tracks
+    pub operation: Option<TrackOperation>,
+    /// Compression of the frames of the track
+    pub content_compression: Option<ContentCompression>,
+}
+
+impl TrackEntry {
+    fn new(trees: &[ElementTree]) -> Self {
+        let codec_private = find(trees, Id::CodecPrivate).map(ElementTree::header);
+        Self {
+            track_number: unsigned(trees, Id::TrackNumber),
+            track_uid: unsigned(trees, Id::TrackUid),
+            track_type: unsigned(trees, Id::TrackType).and_then(TrackType::new),
+            codec_id: string(trees, Id::CodecId),
+            codec_private: binary(trees, Id::CodecPrivate),
+            codec_private_size: codec_private.and_then(|header| header.body_size),
+            codec_private_position: codec_private.and_then(|header| {
+                header
+                    .position
+                    .map(|position| position + header.header_size)
+            }),
+            name: string(trees, Id::Name),
+            language: string(trees, Id::Language).unwrap_or_else(|| "eng".to_string()),
+            language_bcp47: string(trees, Id::LanguageBcp47),
+            enabled: flag(trees, Id::FlagEnabled, true),
+            default: flag(trees, Id::FlagDefault, true),
+            forced: flag(trees, Id::FlagForced, false),
+            hearing_impaired: flag(trees, Id::FlagHearingImpaired, false),
+            visual_impaired: flag(trees, Id::FlagVisualImpaired, false),
+            original: flag(trees, Id::FlagOriginal, false),
+            commentary: flag(trees, Id::FlagCommentary, false),
+            default_duration: unsigned(trees, Id::DefaultDuration),
+            codec_delay: unsigned(trees, Id::CodecDelay).unwrap_or(0),
+            seek_pre_roll: unsigned(trees, Id::SeekPreRoll).unwrap_or(0),
+            video: find(trees, Id::Video).map(|video| Video::new(children(video))),
+            audio: find(trees, Id::Audio).map(|audio| Audio::new(children(audio))),
+            block_addition_mappings: find_all(trees, Id::BlockAdditionMapping)
+                .map(|mapping| BlockAdditionMapping::new(children(mapping)))
+                .collect(),
+            operation: find(trees, Id::TrackOperation)
+                .map(|operation| TrackOperation::new(children(operation))),
+            content_compression: find(trees, Id::ContentEncodings)
+                .into_iter()
+                .flat_map(|encodings| find_all(children(encodings), Id::ContentEncoding))
+                .find_map(|encoding| find(children(encoding), Id::ContentCompression))
+                .map(|compression| ContentCompression::new(children(compression))),
+        }
+    }
+
+    /// Whether CodecDelay is consistent with the codec configuration, if
+    /// it could be decoded from CodecPrivate. Only Opus is supported.
+    pub fn codec_delay_matches(&self) -> Option<bool> {
+        match &self.codec_private {
+            Some(Binary::CodecPrivate(CodecPrivate::Opus(opus_head))) => {
+                Some(opus_head.matches_codec_delay(self.codec_delay))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Overview of a track, with the properties usually shown to users
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackSummary {
+    /// Track number, as used in blocks
+    pub track_number: Option<u64>,
+    /// Type of the track
+    pub track_type: Option<TrackType>,
+    /// Codec ID
+    pub codec_id: Option<String>,
+    /// Width of the encoded frames of video tracks
+    pub pixel_width: Option<u64>,
+    /// Height of the encoded frames of video tracks
+    pub pixel_height: Option<u64>,
+    /// Number of channels of audio tracks
+    pub channels: Option<u64>,
+    /// Sampling frequency of audio tracks in Hz
+    pub sampling_frequency: Option<f64>,
+    /// Language of the track, as a BCP 47 tag if given or in the Matroska
+    /// languages form otherwise
+    pub language: String,
+    /// Whether the track is eligible for automatic selection
+    pub default: bool,
+    /// Whether the track is forced to be presented
+    pub forced: bool,
+    /// Duration of each frame, in nanoseconds
+    pub default_duration: Option<u64>,
+}
+
+impl TrackSummary {
+    /// Summarize a track
+    pub fn new(track: &TrackEntry) -> Self {
+        Self {
+            track_number: track.track_number,
+            track_type: track.track_type.clone(),
+            codec_id: track.codec_id.clone(),
+            pixel_width: track.video.as_ref().and_then(|video| video.pixel_width),
+            pixel_height: track.video.as_ref().and_then(|video| video.pixel_height),
+            channels: track.audio.as_ref().map(|audio| audio.channels),
+            sampling_frequency: track.audio.as_ref().map(|audio| audio.sampling_frequency),
+            language: track
+                .language_bcp47
+                .clone()
+                .unwrap_or_else(|| track.language.clone()),
+            default: track.default,
+            forced: track.forced,
+            default_duration: track.default_duration,
+        }
+    }
+}
+
+impl fmt::Display for TrackSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.track_number {
+            Some(track_number) => write!(f, "Track {track_number}")?,
+            None => write!(f, "Track ?")?,
+        }
+        if let Some(track_type) = &self.track_type {
+            write!(f, ": {}", track_type.label())?;
+        }
+        if let Some(codec_id) = &self.codec_id {
+            write!(f, ", {codec_id}")?;
+        }
+        if let (Some(width), Some(height)) = (self.pixel_width, self.pixel_height) {
+            write!(f, ", {width}x{height}")?;
+        }
+        if let Some(channels) = self.channels {
+            write!(f, ", {channels} channels")?;
+        }
+        if let Some(sampling_frequency) = self.sampling_frequency {
+            write!(f, ", {sampling_frequency} Hz")?;
+        }
+        write!(f, ", language {}", self.language)?;
+        if self.default {
+            write!(f, ", default")?;
+        }
+        if self.forced {
+            write!(f, ", forced")?;
+        }
+        if let Some(default_duration) = self.default_duration {
+            write!(f, ", {} ms per frame", default_duration as f64 / 1e6)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summarize the tracks of the first Segment of parsed elements
+pub fn summarize_tracks(elements: &[Element]) -> Vec<TrackSummary> {
+    Document::from_elements(elements)
+        .tracks
+        .iter()
+        .map(TrackSummary::new)
+        .collect()
+}
+
+/// A block referenced by a cue point, from a CueReference element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CueReference {
+    /// Timestamp of the referenced block, in Segment ticks
+    pub time: Option<u64>,
+    /// Position of the Cluster of the referenced block, relative to the
+    /// Segment data
+    pub cluster_position: Option<u64>,
+    /// Number of the referenced block in its Cluster, starting from 1
+    pub block_number: Option<u64>,
+    /// Position of the codec state of the referenced block, relative to
+    /// the Segment data
+    pub codec_state: Option<u64>,
+}
+
+impl CueReference {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            time: unsigned(trees, Id::CueRefTime),
+            cluster_position: unsigned(trees, Id::CueRefCluster),
+            block_number: unsigned(trees, Id::CueRefNumber),
+            codec_state: unsigned(trees, Id::CueRefCodecState),
+        }
+    }
+}
+
+/// Position of a track within a cue point
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CueTrackPositions {
+    /// Track number
+    pub track: Option<u64>,
+    /// Position of the Cluster, relative to the Segment data
+    pub cluster_position: Option<u64>,
+    /// Position of the block, relative to the Cluster data
+    pub relative_position: Option<u64>,
+    /// Duration of the block, in Segment ticks
+    pub duration: Option<u64>,
+    /// Number of the block in the Cluster, starting from 1
+    pub block_number: Option<u64>,
+    /// Other blocks needed to decode the block, such as subtitles that
+    /// started in a previous Cluster
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<CueReference>,
+}
+
+impl CueTrackPositions {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            track: unsigned(trees, Id::CueTrack),
+            cluster_position: unsigned(trees, Id::CueClusterPosition),
+            relative_position: unsigned(trees, Id::CueRelativePosition),
+            duration: unsigned(trees, Id::CueDuration),
+            block_number: unsigned(trees, Id::CueBlockNumber),
+            references: find_all(trees, Id::CueReference)
+                .map(|reference| CueReference::new(children(reference)))
+                .collect(),
+        }
+    }
+}
+
+/// A seek point, from a CuePoint element
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CuePoint {
+    /// Timestamp of the seek point, in Segment ticks
+    pub time: u64,
+    /// Positions for the different tracks
+    pub track_positions: Vec<CueTrackPositions>,
+}
+
+impl CuePoint {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            time: unsigned(trees, Id::CueTime).unwrap_or(0),
+            track_positions: find_all(trees, Id::CueTrackPositions)
+                .map(|positions| CueTrackPositions::new(children(positions)))
+                .collect(),
+        }
+    }
+}
+
+/// A chapter title in a given set of languages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChapterDisplay {
+    /// The title
+    pub string: String,
+    /// Languages of the title, in the Matroska languages form
+    pub languages: Vec<String>,
+    /// Languages of the title, as BCP 47 tags
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages_bcp47: Vec<String>,
+}
+
+impl ChapterDisplay {
+    fn new(trees: &[ElementTree]) -> Self {
+        let languages = find_all(trees, Id::ChapLanguage)
+            .filter_map(|language| string(std::slice::from_ref(language), Id::ChapLanguage))
+            .collect::<Vec<_>>();
+        Self {
+            string: string(trees, Id::ChapString).unwrap_or_default(),
+            languages: if languages.is_empty() {
+                vec!["eng".to_string()]
+            } else {
+                languages
+            },
+            languages_bcp47: find_all(trees, Id::ChapLanguageBcp47)
+                .filter_map(|language| {
+                    string(std::slice::from_ref(language), Id::ChapLanguageBcp47)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A command to process, from a ChapProcessCommand element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChapterCommand {
+    /// When the command is processed
+    pub time: Option<ChapProcessTime>,
+    /// The command, decoded for the DVD command set
+    pub data: Option<Binary>,
+}
+
+impl ChapterCommand {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            time: unsigned(trees, Id::ChapProcessTime).and_then(ChapProcessTime::new),
+            data: binary(trees, Id::ChapProcessData),
+        }
+    }
+}
+
+/// Commands of a chapter for a given codec, from a ChapProcess element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChapterProcess {
+    /// Codec of the commands, such as 1 for the DVD command set
+    pub codec_id: u64,
+    /// Data for the codec, such as the DVD level
+    pub private: Option<Binary>,
+    /// Commands of the chapter
+    pub commands: Vec<ChapterCommand>,
+}
+
+impl ChapterProcess {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            codec_id: unsigned(trees, Id::ChapProcessCodecId).unwrap_or(0),
+            private: binary(trees, Id::ChapProcessPrivate),
+            commands: find_all(trees, Id::ChapProcessCommand)
+                .map(|command| ChapterCommand::new(children(command)))
+                .collect(),
+        }
+    }
+}
+
+/// A chapter, from a ChapterAtom element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChapterAtom {
+    /// Unique ID of the chapter
+    pub uid: Option<u64>,
+    /// Start time in nanoseconds, not scaled by the TimestampScale
+    pub time_start: u64,
+    /// End time in nanoseconds, not scaled by the TimestampScale
+    pub time_end: Option<u64>,
+    /// Whether the chapter is hidden
+    pub hidden: bool,
+    /// Whether the chapter is enabled
+    pub enabled: bool,
+    /// Titles of the chapter
+    pub displays: Vec<ChapterDisplay>,
+    /// Commands to process for the chapter, such as DVD menu commands
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<ChapterProcess>,
+    /// Nested chapters
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ChapterAtom>,
+}
+
+impl ChapterAtom {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            uid: unsigned(trees, Id::ChapterUid),
+            time_start: unsigned(trees, Id::ChapterTimeStart).unwrap_or(0),
+            time_end: unsigned(trees, Id::ChapterTimeEnd),
+            hidden: flag(trees, Id::ChapterFlagHidden, false),
+            enabled: flag(trees, Id::ChapterFlagEnabled, true),
+            displays: find_all(trees, Id::ChapterDisplay)
+                .map(|display| ChapterDisplay::new(children(display)))
+                .collect(),
+            processes: find_all(trees, Id::ChapProcess)
+                .map(|process| ChapterProcess::new(children(process)))
+                .collect(),
+            children: find_all(trees, Id::ChapterAtom)
+                .map(|atom| ChapterAtom::new(children(atom)))
+                .collect(),
+        }
+    }
+}
+
+/// A set of chapters, from an EditionEntry element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditionEntry {
+    /// Unique ID of the edition
+    pub uid: Option<u64>,
+    /// Whether the edition is hidden
+    pub hidden: bool,
+    /// Whether the edition should be used as the default one
+    pub default: bool,
+    /// Whether the chapters can be defined multiple times and their order
+    /// should be followed
+    pub ordered: bool,
+    /// Chapters of the edition
+    pub chapters: Vec<ChapterAtom>,
+}
+
+impl EditionEntry {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            uid: unsigned(trees, Id::EditionUid),
+            hidden: flag(trees, Id::EditionFlagHidden, false),
+            default: flag(trees, Id::EditionFlagDefault, false),
+            ordered: flag(trees, Id::EditionFlagOrdered, false),
+            chapters: find_all(trees, Id::ChapterAtom)
+                .map(|atom| ChapterAtom::new(children(atom)))
+                .collect(),
+        }
+    }
+}
+
+/// A chapter in a flattened timeline of chapters
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineChapter {
+    /// Unique ID of the edition the chapter is in
+    pub edition_uid: Option<u64>,
+    /// Unique ID of the chapter
+    pub uid: Option<u64>,
+    /// Nesting level of the chapter, with chapters of editions at level 0
+    pub level: usize,
+    /// Start time in nanoseconds
+    pub start: u64,
+    /// End time in nanoseconds. When absent from the chapter, it is the
+    /// start of the next chapter at the same level, or the end of the parent
+    /// chapter (or of the Segment) for the last one.
+    pub end: Option<u64>,
+    /// Titles of the chapter by language, preferring BCP 47 tags
+    pub titles: BTreeMap<String, String>,
+    /// Whether the chapter or one of its parents is hidden
+    pub hidden: bool,
+    /// Whether the chapter and all its parents are enabled
+    pub enabled: bool,
+}
+
+// Append chapters and their nested chapters to the timeline, inheriting
+// from the parent chapter, or from a placeholder for the edition
+fn flatten_chapters(
+    chapters: &[ChapterAtom],
+    parent: &TimelineChapter,
+    level: usize,
+    timeline: &mut Vec<TimelineChapter>,
+) {
+    let mut chapters = chapters.iter().collect::<Vec<_>>();
+    chapters.sort_by_key(|chapter| chapter.time_start);
+    for (index, chapter) in chapters.iter().enumerate() {
+        let end = chapter.time_end.or_else(|| match chapters.get(index + 1) {
+            Some(next) => Some(next.time_start),
+            None => parent.end,
+        });
+        let titles = chapter
+            .displays
+            .iter()
+            .flat_map(|display| {
+                let languages = if display.languages_bcp47.is_empty() {
+                    &display.languages
+                } else {
+                    &display.languages_bcp47
+                };
+                languages
+                    .iter()
+                    .map(|language| (language.clone(), display.string.clone()))
+            })
+            .collect();
+        let entry = TimelineChapter {
+            edition_uid: parent.edition_uid,
+            uid: chapter.uid,
+            level,
+            start: chapter.time_start,
+            end,
+            titles,
+            hidden: parent.hidden || chapter.hidden,
+            enabled: parent.enabled && chapter.enabled,
+        };
+        timeline.push(entry.clone());
+        flatten_chapters(&chapter.children, &entry, level + 1, timeline);
+    }
+}
+
+/// A metadata name/value pair, from a SimpleTag element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimpleTag {
+    /// Name of the tag, such as `TITLE`
+    pub name: String,
+    /// Language of the value, in the Matroska languages form
+    pub language: String,
+    /// Language of the value, as a BCP 47 tag
+    pub language_bcp47: Option<String>,
+    /// Whether this is the default value for its name and target
+    pub default: bool,
+    /// Value of the tag as a string
+    pub string: Option<String>,
+    /// Value of the tag as binary data
+    pub binary: Option<Binary>,
+    /// Tags about this tag, such as the URL of an ARTIST
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SimpleTag>,
+}
+
+impl SimpleTag {
+    fn new(trees: &[ElementTree]) -> Self {
+        Self {
+            name: string(trees, Id::TagName).unwrap_or_default(),
+            language: string(trees, Id::TagLanguage).unwrap_or_else(|| "und".to_string()),
+            language_bcp47: string(trees, Id::TagLanguageBcp47),
+            default: flag(trees, Id::TagDefault, true),
+            string: string(trees, Id::TagString),
+            binary: binary(trees, Id::TagBinary),
+            children: find_all(trees, Id::SimpleTag)
+                .map(|simple_tag| SimpleTag::new(children(simple_tag)))
+                .collect(),
+        }
+    }
+}
+
+/// Metadata about a set of targets, from a Tag element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tag {
+    /// Logical level of the targets, such as 50 for a movie or an album
+    pub target_type_value: u64,
+    /// Informational name of the level, such as `MOVIE` or `ALBUM`
+    pub target_type: Option<String>,
+    /// Unique IDs of the tracks the tag applies to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub track_uids: Vec<u64>,
+    /// Unique IDs of the editions the tag applies to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub edition_uids: Vec<u64>,
+    /// Unique IDs of the chapters the tag applies to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chapter_uids: Vec<u64>,
+    /// Unique IDs of the attachments the tag applies to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachment_uids: Vec<u64>,
+    /// Name/value pairs of the tag
+    pub simple_tags: Vec<SimpleTag>,
+}
+
+impl Tag {
+    fn new(trees: &[ElementTree]) -> Self {
+        let targets = find(trees, Id::Targets).map_or(&[][..], children);
+        // A UID of 0 means that the tag applies to everything at that level
+        let uids = |id: Id| {
+            find_all(targets, id.clone())
+                .filter_map(|uid| unsigned(std::slice::from_ref(uid), id.clone()))
+                .filter(|uid| *uid != 0)
+                .collect()
+        };
+        Self {
+            target_type_value: unsigned(targets, Id::TargetTypeValue).unwrap_or(50),
+            target_type: string(targets, Id::TargetType),
+            track_uids: uids(Id::TagTrackUid),
+            edition_uids: uids(Id::TagEditionUid),
+            chapter_uids: uids(Id::TagChapterUid),
+            attachment_uids: uids(Id::TagAttachmentUid),
+            simple_tags: find_all(trees, Id::SimpleTag)
+                .map(|simple_tag| SimpleTag::new(children(simple_tag)))
+                .collect(),
+        }
+    }
+
+    /// The targets the tag applies to, which is the whole Segment when no
+    /// track, edition, chapter or attachment is targeted
+    pub fn targets(&self) -> Vec<TagTarget> {
+        let targets = self
+            .track_uids
+            .iter()
+            .map(|uid| TagTarget::Track(*uid))
+            .chain(self.edition_uids.iter().map(|uid| TagTarget::Edition(*uid)))
+            .chain(self.chapter_uids.iter().map(|uid| TagTarget::Chapter(*uid)))
+            .chain(
+                self.attachment_uids
+                    .iter()
+                    .map(|uid| TagTarget::Attachment(*uid)),
+            )
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            vec![TagTarget::Segment]
+        } else {
+            targets
+        }
+    }
+}
+
+/// What a tag applies to, serialized as `segment` or as the kind of target
+/// and its unique ID, such as `track:1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TagTarget {
+    /// The whole Segment
+    Segment,
+    /// A track, by its unique ID
+    Track(u64),
+    /// An edition, by its unique ID
+    Edition(u64),
+    /// A chapter, by its unique ID
+    Chapter(u64),
+    /// An attachment, by its unique ID
+    Attachment(u64),
+}
+
+impl fmt::Display for TagTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagTarget::Segment => write!(f, "segment"),
+            TagTarget::Track(uid) => write!(f, "track:{uid}"),
+            TagTarget::Edition(uid) => write!(f, "edition:{uid}"),
+            TagTarget::Chapter(uid) => write!(f, "chapter:{uid}"),
+            TagTarget::Attachment(uid) => write!(f, "attachment:{uid}"),
+        }
+    }
+}
+
+impl Serialize for TagTarget {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+// Append the string values of tags, with names of nested tags prefixed by
+// the names of their parents, such as ARTIST/URL
+fn flatten_simple_tags(
+    simple_tags: &[SimpleTag],
+    prefix: &str,
+    values: &mut Vec<(String, String)>,
+) {
+    for simple_tag in simple_tags {
+        let name = format!("{prefix}{}", simple_tag.name);
+        if let Some(string) = &simple_tag.string {
+            values.push((name.clone(), string.clone()));
+        }
+        flatten_simple_tags(&simple_tag.children, &format!("{name}/"), values);
+    }
+}
+
+/// A file attached to the Segment, such as cover art or a font, from an
+/// AttachedFile element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttachedFile {
+    /// Filename of the attached file
+    pub file_name: String,
+    /// Media type of the file, such as `image/jpeg`
+    pub mime_type: String,
+    /// Human-readable description of the file
+    pub description: Option<String>,
+    /// Unique ID of the attached file
+    pub uid: Option<u64>,
+    /// Size of the file data in bytes
+    pub data_size: Option<usize>,
+    /// Absolute position of the file data in the input. Only known if
+    /// elements have positions.
+    pub data_position: Option<usize>,
+}
+
+impl AttachedFile {
+    fn new(trees: &[ElementTree]) -> Self {
+        let data = find(trees, Id::FileData).map(ElementTree::header);
+        Self {
+            file_name: string(trees, Id::FileName).unwrap_or_default(),
+            mime_type: string(trees, Id::FileMimeType).unwrap_or_default(),
+            description: string(trees, Id::FileDescription),
+            uid: unsigned(trees, Id::FileUid),
+            data_size: data.and_then(|header| header.body_size),
+            data_position: data.and_then(|header| {
+                header
+                    .position
+                    .map(|position| position + header.header_size)
+            }),
+        }
+    }
+}
+
+/// A reference to a top-level element, from a Seek element
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeekEntry {
+    /// ID of the referenced element
+    pub id: Id,
+    /// Position of the element, relative to the Segment data
+    pub position: u64,
+    /// Absolute position of the element in the input
+    pub absolute_position: Option<u64>,
+}
+
+impl SeekEntry {
+    fn new(trees: &[ElementTree], segment_data_position: Option<u64>) -> Option<Self> {
+        let Some(Binary::SeekId(id)) = binary(trees, Id::SeekId) else {
+            return None;
+        };
+        let position = unsigned(trees, Id::SeekPosition)?;
+        Some(Self {
+            id,
+            position,
+            absolute_position: segment_data_position.map(|offset| offset + position),
+        })
+    }
+}
+
+/// Typed view of a Matroska Segment
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Document {
+    /// Entries of the SeekHead
+    pub seek_head: Vec<SeekEntry>,
+    /// Segment information
+    pub info: Option<SegmentInfo>,
+    /// Tracks in the Segment
+    pub tracks: Vec<TrackEntry>,
+    /// Cue points for seeking
+    pub cues: Vec<CuePoint>,
+    /// Chapter editions
+    pub chapters: Vec<EditionEntry>,
+    /// Attached files
+    pub attachments: Vec<AttachedFile>,
+    /// Metadata tags
+    pub tags: Vec<Tag>,
+    /// Absolute position of the Segment data, which positions in Cues and
+    /// SeekHead are relative to. Only known if elements have positions.
+    pub segment_data_position: Option<u64>,
+}
+
+impl Document {
+    /// Build a document from a series of parsed elements.
+    ///
+    /// Only the first Segment is considered.
+    pub fn from_elements(elements: &[Element]) -> Self {
+        Self::from_trees(&build_element_trees(elements))
+    }
+
+    /// Build a document from element trees.
+    ///
+    /// Only the first Segment is considered. If there is no Segment, the
+    /// top-level elements are looked up instead.
+    pub fn from_trees(trees: &[ElementTree]) -> Self {
+        let segment_tree = find(trees, Id::Segment);
+        let segment = segment_tree.map_or(trees, children);
+        let segment_data_position = match segment_tree {
+            Some(ElementTree::Master(master)) => master
+                .header
+                .position
+                .map(|position| (position + master.header.header_size) as u64),
+            _ => None,
+        };
+        Self {
+            seek_head: find_all(segment, Id::SeekHead)
+                .flat_map(|seek_head| find_all(children(seek_head), Id::Seek))
+                .filter_map(|seek| SeekEntry::new(children(seek), segment_data_position))
+                .collect(),
+            info: find(segment, Id::Info).map(|info| SegmentInfo::new(children(info))),
+            tracks: find_all(segment, Id::Tracks)
+                .flat_map(|tracks| find_all(children(tracks), Id::TrackEntry))
+                .map(|track| TrackEntry::new(children(track)))
+                .collect(),
+            cues: find_all(segment, Id::Cues)
+                .flat_map(|cues| find_all(children(cues), Id::CuePoint))
+                .map(|cue_point| CuePoint::new(children(cue_point)))
+                .collect(),
+            chapters: find_all(segment, Id::Chapters)
+                .flat_map(|chapters| find_all(children(chapters), Id::EditionEntry))
+                .map(|edition| EditionEntry::new(children(edition)))
+                .collect(),
+            attachments: find_all(segment, Id::Attachments)
+                .flat_map(|attachments| find_all(children(attachments), Id::AttachedFile))
+                .map(|attached_file| AttachedFile::new(children(attached_file)))
+                .collect(),
+            tags: find_all(segment, Id::Tags)
+                .flat_map(|tags| find_all(children(tags), Id::Tag))
+                .map(|tag| Tag::new(children(tag)))
+                .collect(),
+            segment_data_position,
+        }
+    }
+
+    /// Read a document from a seekable reader without going through the
+    /// Clusters.
+    ///
+    /// Elements are parsed up to the first Cluster, then the top-level
+    /// elements referenced by the SeekHead which were not found yet (e.g.
+    /// Cues at the end of the file) are parsed by jumping to them.
+    pub fn read<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut iterator = ElementIterator::seekable(reader);
+        let mut elements = Vec::new();
+        for element in iterator.by_ref() {
+            let element = element?;
+            if element.header.id == Id::Cluster {
+                break;
+            }
+            elements.push(element);
+        }
+
+        let document = Self::from_elements(&elements);
+        let mut visited = elements
+            .iter()
+            .filter_map(|element| element.header.position)
+            .collect::<HashSet<_>>();
+        for entry in &document.seek_head {
+            let Some(position) = entry.absolute_position else {
+                continue;
+            };
+            if entry.id == Id::Cluster || !visited.insert(position as usize) {
+                continue;
+            }
+            elements.extend(iterator.read_element_at(position)?);
+        }
+        Ok(Self::from_elements(&elements))
+    }
+
+    /// Timestamp scale of the Segment in nanoseconds
+    pub fn timestamp_scale(&self) -> u64 {
+        self.info
+            .as_ref()
+            .map_or(DEFAULT_TIMESTAMP_SCALE, |info| info.timestamp_scale)
+    }
+
+    /// Flatten the chapters of each edition into a list, with nested
+    /// chapters following their parents and chapters at the same level
+    /// ordered by start time.
+    ///
+    /// Chapter times are in nanoseconds, as they are not scaled by the
+    /// TimestampScale.
+    pub fn chapter_timeline(&self) -> Vec<TimelineChapter> {
+        self.chapters
+            .iter()
+            .flat_map(|edition| self.edition_timeline(edition))
+            .collect()
+    }
+
+    /// Flatten the chapters of an edition into a list, as in
+    /// [`Document::chapter_timeline`]
+    pub fn edition_timeline(&self, edition: &EditionEntry) -> Vec<TimelineChapter> {
+        let segment_end = self
+            .info
+            .as_ref()
+            .and_then(SegmentInfo::duration_ns)
+            .map(|duration| duration as u64);
+        let root = TimelineChapter {
+            edition_uid: edition.uid,
+            uid: None,
+            level: 0,
+            start: 0,
+            end: segment_end,
+            titles: BTreeMap::new(),
+            hidden: edition.hidden,
+            enabled: true,
+        };
+        let mut timeline = Vec::new();
+        flatten_chapters(&edition.chapters, &root, 0, &mut timeline);
+        timeline
+    }
+
+    /// Collect the string values of tags by the target they apply to.
+    ///
+    /// Names of nested tags are prefixed by the names of their parents, such
+    /// as `ARTIST/URL`, and values are listed in the order of the tags.
+    pub fn tags_by_target(&self) -> BTreeMap<TagTarget, Vec<(String, String)>> {
+        let mut tags_by_target = BTreeMap::<TagTarget, Vec<_>>::new();
+        for tag in &self.tags {
+            let mut values = Vec::new();
+            flatten_simple_tags(&tag.simple_tags, "", &mut values);
+            for target in tag.targets() {
+                tags_by_target
+                    .entry(target)
+                    .or_default()
+                    .extend(values.iter().cloned());
+            }
+        }
+        tags_by_target
+    }
+
+    /// Find the absolute position of the Cluster to start from in order to
+    /// reach the given time in nanoseconds, using the Cues.
+    ///
+    /// Returns `None` if there is no cue point at or before that time, or
+    /// if the position of the Segment is unknown.
+    pub fn seek_to_time(&self, time_ns: u64) -> Option<u64> {
+        let segment_data_position = self.segment_data_position?;
+        let timestamp_scale = self.timestamp_scale();
+        self.cues
+            .iter()
+            .filter(|cue_point| cue_point.time.saturating_mul(timestamp_scale) <= time_ns)
+            .max_by_key(|cue_point| cue_point.time)?
+            .track_positions
+            .iter()
+            .find_map(|positions| positions.cluster_position)
+            .map(|cluster_position| segment_data_position + cluster_position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{borrowed::ElementRefIterator, enumerations::Enumeration, Header, Unsigned};
+
+    fn element(id: Id, header_size: usize, body_size: usize, body: Body) -> Element {
+        Element {
+            header: Header::new(id, header_size, body_size),
+            body,
+        }
+    }
+
+    #[test]
+    fn test_document() {
+        let elements = [
+            element(Id::Segment, 5, 43, Body::Master),
+            element(Id::Info, 2, 12, Body::Master),
+            element(
+                Id::TimestampScale,
+                4,
+                2,
+                Body::Unsigned(Unsigned::Standard(1000)),
+            ),
+            element(Id::Duration, 2, 4, Body::Float(5000.0)),
+            element(Id::Tracks, 5, 24, Body::Master),
+            element(Id::TrackEntry, 2, 22, Body::Master),
+            element(Id::TrackNumber, 2, 1, Body::Unsigned(Unsigned::Standard(1))),
+            element(
+                Id::TrackType,
+                2,
+                1,
+                Body::Unsigned(Unsigned::Enumeration(Enumeration::TrackType(
+                    TrackType::Audio,
+                ))),
+            ),
+            element(Id::CodecId, 2, 6, Body::String("A_OPUS".into())),
+            element(Id::FlagDefault, 2, 1, Body::Unsigned(Unsigned::Standard(0))),
+            element(Id::Audio, 2, 3, Body::Master),
+            element(Id::Channels, 2, 1, Body::Unsigned(Unsigned::Standard(2))),
+        ];
+        let document = Document::from_elements(&elements);
+
+        let info = document.info.as_ref().unwrap();
+        assert_eq!(info.timestamp_scale, 1000);
+        assert_eq!(info.duration_ns(), Some(5_000_000.0));
+        assert_eq!(
+            document.tracks,
+            vec![TrackEntry {
+                track_number: Some(1),
+                track_uid: None,
+                track_type: Some(TrackType::Audio),
+                codec_id: Some("A_OPUS".into()),
+                codec_private: None,
+                codec_private_size: None,
+                codec_private_position: None,
+                name: None,
+                language: "eng".into(),
+                language_bcp47: None,
+                enabled: true,
+                default: false,
+                forced: false,
+                hearing_impaired: false,
+                visual_impaired: false,
+                original: false,
+                commentary: false,
+                default_duration: None,
+                codec_delay: 0,
+                seek_pre_roll: 0,
+                video: None,
+                audio: Some(Audio {
+                    sampling_frequency: 8000.0,
+                    output_sampling_frequency: None,
+                    channels: 2,
+                    bit_depth: None,
+                }),
+                block_addition_mappings: vec![],
+                operation: None,
+                content_compression: None,
+            }]
+        );
+        assert!(document.cues.is_empty());
+
+        let summaries = summarize_tracks(&elements);
+        assert_eq!(
+            summaries,
+            [TrackSummary {
+                track_number: Some(1),
+                track_type: Some(TrackType::Audio),
+                codec_id: Some("A_OPUS".into()),
+                pixel_width: None,
+                pixel_height: None,
+                channels: Some(2),
+                sampling_frequency: Some(8000.0),
+                language: "eng".into(),
+                default: false,
+                forced: false,
+                default_duration: None,
+            }]
+        );
+        assert_eq!(
+            summaries[0].to_string(),
+            "Track 1: audio, A_OPUS, 2 channels, 8000 Hz, language eng"
+        );
+    }
+
+    #[test]
+    fn test_chapters_and_cues() {
+        const INPUT: &[u8] = &[
+            // Chapters
+            0x10, 0x43, 0xA7, 0x70, 0x95, // EditionEntry
+            0x45, 0xB9, 0x92, // ChapterAtom
+            0xB6, 0x90, 0x73, 0xC4, 0x81, 0x07, 0x91, 0x81, 0x00, // ChapterDisplay
+            0x80, 0x87, 0x85, 0x85, 0x49, 0x6E, 0x74, 0x72, 0x6F, // Cues
+            0x1C, 0x53, 0xBB, 0x6B, 0x8D, // CuePoint
+            0xBB, 0x8B, 0xB3, 0x81, 0x0A, // CueTrackPositions
+            0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x20,
+        ];
+        let elements = ElementRefIterator::new(INPUT)
+            .map(|element| element.unwrap().to_element().unwrap())
+            .collect::<Vec<_>>();
+        let document = Document::from_elements(&elements);
+        assert_eq!(
+            document.chapters,
+            vec![EditionEntry {
+                uid: None,
+                hidden: false,
+                default: false,
+                ordered: false,
+                chapters: vec![ChapterAtom {
+                    uid: Some(7),
+                    time_start: 0,
+                    time_end: None,
+                    hidden: false,
+                    enabled: true,
+                    displays: vec![ChapterDisplay {
+                        string: "Intro".into(),
+                        languages: vec!["eng".into()],
+                        languages_bcp47: vec![],
+                    }],
+                    processes: vec![],
+                    children: vec![],
+                }],
+            }]
+        );
+        assert_eq!(
+            document.cues,
+            vec![CuePoint {
+                time: 10,
+                track_positions: vec![CueTrackPositions {
+                    track: Some(1),
+                    cluster_position: Some(32),
+                    relative_position: None,
+                    duration: None,
+                    block_number: None,
+                    references: vec![],
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_chapter_timeline() {
+        let chapter = |uid, time_start, hidden, displays, children| ChapterAtom {
+            uid: Some(uid),
+            time_start,
+            time_end: None,
+            hidden,
+            enabled: true,
+            displays,
+            processes: vec![],
+            children,
+        };
+        let display = |string: &str, language: &str| ChapterDisplay {
+            string: string.into(),
+            languages: vec![language.into()],
+            languages_bcp47: vec![],
+        };
+        let mut document = Document::from_elements(&[]);
+        document.info = Some(SegmentInfo {
+            duration: Some(100.0),
+            ..SegmentInfo::new(&[])
+        });
+        document.chapters = vec![EditionEntry {
+            uid: Some(1),
+            hidden: false,
+            default: true,
+            ordered: false,
+            chapters: vec![
+                chapter(
+                    3,
+                    50_000_000,
+                    true,
+                    vec![display("End", "eng")],
+                    vec![chapter(4, 60_000_000, false, vec![], vec![])],
+                ),
+                chapter(
+                    2,
+                    0,
+                    false,
+                    vec![display("Start", "eng"), display("Anfang", "ger")],
+                    vec![],
+                ),
+            ],
+        }];
+
+        let timeline = document.chapter_timeline();
+        let summary = timeline
+            .iter()
+            .map(|chapter| (chapter.uid, chapter.level, chapter.start, chapter.end))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (Some(2), 0, 0, Some(50_000_000)),
+                (Some(3), 0, 50_000_000, Some(100_000_000)),
+                (Some(4), 1, 60_000_000, Some(100_000_000)),
+            ]
+        );
+        assert_eq!(
+            timeline[0].titles,
+            BTreeMap::from([
+                ("eng".to_string(), "Start".to_string()),
+                ("ger".to_string(), "Anfang".to_string())
+            ])
+        );
+        assert!(timeline[2].hidden);
+    }
+
+    #[test]
+    fn test_track_operation() {
+        const INPUT: &[u8] = &[
+            // TrackOperation
+            0xE2, 0x94, // TrackCombinePlanes
+            0xE3, 0x8D, // TrackPlane for the left eye
+            0xE4, 0x86, 0xE5, 0x81, 0x01, 0xE6, 0x81, 0x00, // TrackPlane without type
+            0xE4, 0x83, 0xE5, 0x81, 0x02, // TrackJoinBlocks
+            0xE9, 0x83, 0xED, 0x81, 0x03,
+        ];
+        let elements = ElementRefIterator::new(INPUT)
+            .map(|element| element.unwrap().to_element().unwrap())
+            .collect::<Vec<_>>();
+        let trees = build_element_trees(&elements);
+        assert_eq!(
+            TrackOperation::new(children(&trees[0])),
+            TrackOperation {
+                combine_planes: vec![
+                    TrackPlane {
+                        uid: Some(1),
+                        plane_type: Some(TrackPlaneType::LeftEye),
+                    },
+                    TrackPlane {
+                        uid: Some(2),
+                        plane_type: None,
+                    },
+                ],
+                join_uids: vec![3],
+            }
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        let string = |id, value: &str| element(id, 3, value.len(), Body::Utf8(value.into()));
+        let elements = [
+            element(Id::Tags, 5, 62, Body::Master),
+            element(Id::Tag, 3, 39, Body::Master),
+            element(Id::Targets, 3, 4, Body::Master),
+            element(Id::TagTrackUid, 3, 1, Body::Unsigned(Unsigned::Standard(1))),
+            element(Id::SimpleTag, 3, 29, Body::Master),
+            string(Id::TagName, "TITLE"),
+            string(Id::TagString, "Hello"),
+            element(Id::SimpleTag, 3, 10, Body::Master),
+            string(Id::TagName, "URL"),
+            string(Id::TagString, "x"),
+            element(Id::Tag, 3, 17, Body::Master),
+            element(Id::SimpleTag, 3, 14, Body::Master),
+            string(Id::TagName, "ENCODER"),
+            string(Id::TagString, "x"),
+        ];
+        let document = Document::from_elements(&elements);
+        assert_eq!(document.tags.len(), 2);
+        assert_eq!(document.tags[0].target_type_value, 50);
+        assert_eq!(document.tags[0].targets(), [TagTarget::Track(1)]);
+        assert_eq!(document.tags[1].targets(), [TagTarget::Segment]);
+
+        let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            document.tags_by_target(),
+            BTreeMap::from([
+                (TagTarget::Segment, vec![pair("ENCODER", "x")]),
+                (
+                    TagTarget::Track(1),
+                    vec![pair("TITLE", "Hello"), pair("TITLE/URL", "x")]
+                ),
+            ])
+        );
+        assert_eq!(TagTarget::Track(1).to_string(), "track:1");
+    }
+
+    #[test]
+    fn test_attachments() {
+        const INPUT: &[u8] = &[
+            // Attachments
+            0x19, 0x41, 0xA4, 0x69, 0xA1, // AttachedFile
+            0x61, 0xA7, 0x9E, // FileName
+            0x46, 0x6E, 0x85, 0x61, 0x2E, 0x70, 0x6E, 0x67, // FileMimeType
+            0x46, 0x60, 0x89, 0x69, 0x6D, 0x61, 0x67, 0x65, 0x2F, 0x70, 0x6E, 0x67,
+            // FileUID
+            0x46, 0xAE, 0x81, 0x01, // FileData
+            0x46, 0x5C, 0x83, 0x00, 0x01, 0x02,
+        ];
+
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let document = Document::from_elements(&elements);
+        assert_eq!(
+            document.attachments,
+            vec![AttachedFile {
+                file_name: "a.png".into(),
+                mime_type: "image/png".into(),
+                description: None,
+                uid: Some(1),
+                data_size: Some(3),
+                data_position: Some(35),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cue_references() {
+        const INPUT: &[u8] = &[
+            // CueTrackPositions
+            0xB7, 0x8B, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x20, // CueReference
+            0xDB, 0x83, 0x96, 0x81, 0x05,
+        ];
+        let elements = ElementRefIterator::new(INPUT)
+            .map(|element| element.unwrap().to_element().unwrap())
+            .collect::<Vec<_>>();
+        let trees = build_element_trees(&elements);
+        assert_eq!(
+            CueTrackPositions::new(children(&trees[0])).references,
+            vec![CueReference {
+                time: Some(5),
+                cluster_position: None,
+                block_number: None,
+                codec_state: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_seek_to_time() {
+        const INPUT: &[u8] = &[
+            // Segment
+            0x18, 0x53, 0x80, 0x67, 0xBB, // Cues
+            0x1C, 0x53, 0xBB, 0x6B, 0x9A, // CuePoint at 0, Cluster at 31
+            0xBB, 0x8B, 0xB3, 0x81, 0x00, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x1F,
+            // CuePoint at 10, Cluster at 45
+            0xBB, 0x8B, 0xB3, 0x81, 0x0A, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x2D,
+            // Cluster with Timestamp 0
+            0x1F, 0x43, 0xB6, 0x75, 0x89, 0xE7, 0x81, 0x00, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80,
+            // Cluster with Timestamp 10
+            0x1F, 0x43, 0xB6, 0x75, 0x89, 0xE7, 0x81, 0x0A, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80,
+        ];
+
+        let mut iterator = ElementIterator::seekable(Cursor::new(INPUT));
+        let elements = iterator
+            .by_ref()
+            .take_while(|element| element.as_ref().unwrap().header.id != Id::Cluster)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let document = Document::from_elements(&elements);
+        assert_eq!(document.segment_data_position, Some(5));
+        assert_eq!(document.seek_to_time(5_000_000), Some(36));
+        assert_eq!(document.seek_to_time(15_000_000), Some(50));
+
+        iterator.seek(50).unwrap();
+        let cluster = iterator.next().unwrap().unwrap();
+        assert_eq!(cluster.header.id, Id::Cluster);
+        assert_eq!(cluster.header.position, Some(50));
+        let timestamp = iterator.next().unwrap().unwrap();
+        assert_eq!(timestamp.body, Body::Unsigned(Unsigned::Standard(10)));
+    }
+
+    #[test]
+    fn test_read_with_seek_head() {
+        const INPUT: &[u8] = &[
+            // Segment
+            0x18, 0x53, 0x80, 0x67, 0xB3, // SeekHead
+            0x11, 0x4D, 0x9B, 0x74, 0x8E, // Seek
+            0x4D, 0xBB, 0x8B, 0x53, 0xAB, 0x84, 0x1C, 0x53, 0xBB, 0x6B, 0x53, 0xAC, 0x81, 0x21,
+            // Cluster with Timestamp 0
+            0x1F, 0x43, 0xB6, 0x75, 0x89, 0xE7, 0x81, 0x00, 0xA3, 0x84, 0x81, 0x00, 0x00, 0x80,
+            // Cues
+            0x1C, 0x53, 0xBB, 0x6B, 0x8D, // CuePoint at 0, Cluster at 19
+            0xBB, 0x8B, 0xB3, 0x81, 0x00, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x13,
+        ];
+
+        let document = Document::read(Cursor::new(INPUT)).unwrap();
+        assert_eq!(
+            document.seek_head,
+            vec![SeekEntry {
+                id: Id::Cues,
+                position: 33,
+                absolute_position: Some(38),
+            }]
+        );
+        assert_eq!(document.cues.len(), 1);
+        assert_eq!(document.seek_to_time(0), Some(24));
+    }
+}
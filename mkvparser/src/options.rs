@@ -0,0 +1,72 @@
+/// Options to tune limits and strictness of parsing
+///
+/// The default options match the behavior of functions not taking options,
+/// such as [`parse_element`](crate::parse_element).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Maximum nesting level of elements, with top-level elements at level 0.
+    /// Deeper elements are considered invalid.
+    pub max_depth: Option<usize>,
+    /// Maximum body size of non-Master elements. Larger elements are
+    /// considered invalid.
+    pub max_element_size: Option<usize>,
+    /// Binary payloads up to this size are fully serialized, while larger
+    /// ones are summarized by their size.
+    pub max_binary_preview: usize,
+    /// Encoding of the binary payloads that are fully serialized.
+    pub binary_encoding: BinaryEncoding,
+    /// Accept unknown sizes in any Master element, rather than only in
+    /// Segment and Cluster as allowed by the specification.
+    pub lenient_unknown_size: bool,
+    /// Accept unknown sizes in non-Master elements, which are then taken as
+    /// extending up to the next sibling or top-level element.
+    pub lenient_unknown_leaf_size: bool,
+    /// Resynchronize after invalid data and report it as Corrupted elements,
+    /// rather than failing with an error.
+    pub recover_corruption: bool,
+    /// Report the byte ranges of frames in SimpleBlock and Block elements.
+    pub frame_ranges: bool,
+    /// Report the path of elements, such as `\Segment\Info\TimestampScale`.
+    pub element_paths: bool,
+    /// Report the nesting level of elements.
+    pub element_levels: bool,
+    /// Report how many bytes of the header of elements are taken by the
+    /// Element ID and by the Element Data Size.
+    pub header_field_sizes: bool,
+    /// Mark elements that are no longer part of the latest version of the
+    /// DocType, such as FrameRate or BlockVirtual.
+    pub deprecated_elements: bool,
+    /// Skip the bodies of Clusters with a known size, so that only their
+    /// headers are reported. Clusters with unknown size are still parsed.
+    pub skip_clusters: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            max_element_size: None,
+            max_binary_preview: 64,
+            binary_encoding: BinaryEncoding::Hex,
+            lenient_unknown_size: false,
+            lenient_unknown_leaf_size: false,
+            recover_corruption: true,
+            frame_ranges: false,
+            element_paths: false,
+            element_levels: false,
+            header_field_sizes: false,
+            deprecated_elements: false,
+            skip_clusters: false,
+        }
+    }
+}
+
+/// Encoding of serialized binary payloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BinaryEncoding {
+    /// Space-separated hexadecimal bytes within brackets, such as `[de ad]`
+    #[default]
+    Hex,
+    /// Standard base64 with padding, such as `3q0=`
+    Base64,
+}
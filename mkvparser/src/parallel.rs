@@ -0,0 +1,104 @@
+//! Parallel parsing of Clusters with [rayon](https://docs.rs/rayon)
+//!
+//! Clusters hold almost all the data of a file, so once the elements before
+//! them (such as Tracks) are known, their bodies can be parsed independently
+//! in worker threads and merged back in order.
+use rayon::prelude::*;
+
+use crate::{elements::Id, iter::Decoder, Body, Element, ParserOptions, Result};
+
+// A portion of the elements, either already parsed or a Cluster body still
+// to be parsed.
+enum Part<'a> {
+    Parsed(Vec<Element>),
+    ClusterBody(Box<Decoder>, &'a [u8]),
+}
+
+// Get the next element from a decoder that has been fed the complete input
+fn next_element(decoder: &mut Decoder) -> Option<Result<Element>> {
+    decoder.next_element().or_else(|| {
+        decoder.finish();
+        decoder.next_element()
+    })
+}
+
+/// Parse all elements of an in-memory input, parsing the bodies of Clusters
+/// in parallel.
+///
+/// The result is the same as parsing the input with an
+/// [`ElementIterator`](crate::iter::ElementIterator), except that corrupt
+/// regions never extend across Cluster boundaries. Clusters with unknown
+/// size are parsed sequentially.
+pub fn parse_elements_parallel(input: &[u8], options: &ParserOptions) -> Result<Vec<Element>> {
+    let mut decoder = Decoder::new(options.clone());
+    decoder.feed(input);
+
+    let mut parts = Vec::new();
+    let mut elements = Vec::new();
+    while let Some(element) = next_element(&mut decoder) {
+        let element = element?;
+        let cluster_body = match (&element.header.id, &element.body) {
+            (Id::Cluster, Body::Master) if !options.skip_clusters => element
+                .header
+                .body_size
+                .zip(element.header.position)
+                .map(|(body_size, position)| (position + element.header.header_size, body_size)),
+            _ => None,
+        };
+        elements.push(element);
+
+        if let Some((body_position, body_size)) = cluster_body {
+            let body = &input[body_position.min(input.len())..];
+            let body = &body[..body_size.min(body.len())];
+            parts.push(Part::Parsed(std::mem::take(&mut elements)));
+            parts.push(Part::ClusterBody(
+                Box::new(decoder.fork(body_position)),
+                body,
+            ));
+            decoder.skip_body(body_size);
+        }
+    }
+    parts.push(Part::Parsed(elements));
+
+    let parsed = parts
+        .into_par_iter()
+        .map(|part| match part {
+            Part::Parsed(elements) => Ok(elements),
+            Part::ClusterBody(mut decoder, body) => {
+                decoder.feed(body);
+                std::iter::from_fn(|| next_element(&mut decoder)).collect()
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(parsed.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter::ElementIterator;
+
+    #[test]
+    fn test_parse_elements_parallel() {
+        // Segment with Tracks for an encrypted track 1 and two Clusters with
+        // a Timestamp and a SimpleBlock each
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x16, 0x54,
+            0xAE, 0x6B, 0x92, 0xAE, 0x90, 0xD7, 0x81, 0x01, 0x6D, 0x80, 0x8A, 0x62, 0x40, 0x87,
+            0x50, 0x35, 0x84, 0x47, 0xE1, 0x81, 0x05, 0x1F, 0x43, 0xB6, 0x75, 0x8A, 0xE7, 0x81,
+            0x00, 0xA3, 0x85, 0x81, 0x00, 0x00, 0x80, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x8A, 0xE7,
+            0x81, 0x0A, 0xA3, 0x85, 0x81, 0x00, 0x00, 0x80, 0x01,
+        ];
+        let options = ParserOptions {
+            element_levels: true,
+            ..ParserOptions::default()
+        };
+
+        let expected = ElementIterator::new(INPUT)
+            .with_options(options.clone())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(expected.len(), 14);
+        assert_eq!(parse_elements_parallel(INPUT, &options), Ok(expected));
+    }
+}
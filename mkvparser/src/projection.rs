@@ -0,0 +1,218 @@
+//! Decoding of ProjectionPrivate payloads of spherical videos
+//!
+//! ProjectionPrivate holds the body of the box of the
+//! [Spherical Video V2](https://github.com/google/spatial-media/blob/master/docs/spherical-video-v2-rfc.md)
+//! specification that matches the ProjectionType, so it is only decoded by
+//! the element iterators.
+use serde::Serialize;
+
+use crate::{
+    codecs::{parse_u32, parse_u8},
+    enumerations::ProjectionType,
+    take, Error, IResult,
+};
+
+/// A decoded ProjectionPrivate payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ProjectionPrivate {
+    /// Equirectangular projection box (equi)
+    Equirectangular(EquirectangularProjection),
+    /// Cubemap projection box (cbmp)
+    Cubemap(CubemapProjection),
+    /// Mesh projection box (mshp)
+    Mesh(MeshProjection),
+}
+
+/// Equirectangular projection, with the bounds of the cropped area of the
+/// sphere as 0.32 fixed-point fractions of the frame
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EquirectangularProjection {
+    /// Version of the box
+    pub version: u8,
+    /// Flags of the box
+    pub flags: u32,
+    /// Amount cropped from the top
+    pub projection_bounds_top: u32,
+    /// Amount cropped from the bottom
+    pub projection_bounds_bottom: u32,
+    /// Amount cropped from the left
+    pub projection_bounds_left: u32,
+    /// Amount cropped from the right
+    pub projection_bounds_right: u32,
+}
+
+/// Cubemap projection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CubemapProjection {
+    /// Version of the box
+    pub version: u8,
+    /// Flags of the box
+    pub flags: u32,
+    /// Layout of the faces, 0 being the default layout
+    pub layout: u32,
+    /// Number of pixels of padding around each face
+    pub padding: u32,
+}
+
+/// Mesh projection, whose meshes are summarized
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MeshProjection {
+    /// Version of the box
+    pub version: u8,
+    /// Flags of the box
+    pub flags: u32,
+    /// CRC-32 of the meshes
+    pub crc: u32,
+    /// Encoding of the meshes, such as `raw ` or `dfl8` for deflate
+    pub encoding: String,
+    /// Size of the encoded meshes
+    pub data_size: usize,
+}
+
+fn parse_full_box_header(input: &[u8]) -> IResult<&[u8], (u8, u32)> {
+    let (input, version) = parse_u8(input)?;
+    let (input, flags) = take(3usize)(input)?;
+    let flags = u32::from_be_bytes([0, flags[0], flags[1], flags[2]]);
+    Ok((input, (version, flags)))
+}
+
+fn parse_equirectangular(input: &[u8]) -> IResult<&[u8], EquirectangularProjection> {
+    let (input, (version, flags)) = parse_full_box_header(input)?;
+    let (input, projection_bounds_top) = parse_u32(input)?;
+    let (input, projection_bounds_bottom) = parse_u32(input)?;
+    let (input, projection_bounds_left) = parse_u32(input)?;
+    let (input, projection_bounds_right) = parse_u32(input)?;
+    Ok((
+        input,
+        EquirectangularProjection {
+            version,
+            flags,
+            projection_bounds_top,
+            projection_bounds_bottom,
+            projection_bounds_left,
+            projection_bounds_right,
+        },
+    ))
+}
+
+fn parse_cubemap(input: &[u8]) -> IResult<&[u8], CubemapProjection> {
+    let (input, (version, flags)) = parse_full_box_header(input)?;
+    let (input, layout) = parse_u32(input)?;
+    let (input, padding) = parse_u32(input)?;
+    Ok((
+        input,
+        CubemapProjection {
+            version,
+            flags,
+            layout,
+            padding,
+        },
+    ))
+}
+
+fn parse_mesh(input: &[u8]) -> IResult<&[u8], MeshProjection> {
+    let (input, (version, flags)) = parse_full_box_header(input)?;
+    let (input, crc) = parse_u32(input)?;
+    let (input, encoding) = take(4usize)(input)?;
+    if !encoding
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+    {
+        return Err(Error::Parser);
+    }
+    Ok((
+        &[],
+        MeshProjection {
+            version,
+            flags,
+            crc,
+            encoding: String::from_utf8_lossy(encoding).into_owned(),
+            data_size: input.len(),
+        },
+    ))
+}
+
+/// Decode a ProjectionPrivate payload given the ProjectionType of its
+/// track.
+///
+/// Returns `None` for rectangular projections, which have no private data.
+pub fn parse_projection_private(
+    projection_type: &ProjectionType,
+    input: &[u8],
+) -> Result<Option<ProjectionPrivate>, Error> {
+    let projection_private = match projection_type {
+        ProjectionType::Equirectangular => {
+            ProjectionPrivate::Equirectangular(parse_equirectangular(input)?.1)
+        }
+        ProjectionType::Cubemap => ProjectionPrivate::Cubemap(parse_cubemap(input)?.1),
+        ProjectionType::Mesh => ProjectionPrivate::Mesh(parse_mesh(input)?.1),
+        ProjectionType::Rectangular => return Ok(None),
+    };
+    Ok(Some(projection_private))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_projection_private() {
+        const EQUIRECTANGULAR: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            parse_projection_private(&ProjectionType::Equirectangular, EQUIRECTANGULAR),
+            Ok(Some(ProjectionPrivate::Equirectangular(
+                EquirectangularProjection {
+                    version: 0,
+                    flags: 0,
+                    projection_bounds_top: 0,
+                    projection_bounds_bottom: 0x4000_0000,
+                    projection_bounds_left: 0,
+                    projection_bounds_right: 0,
+                }
+            )))
+        );
+        assert_eq!(
+            parse_projection_private(&ProjectionType::Equirectangular, &EQUIRECTANGULAR[..18]),
+            Err(Error::NeedData(NonZeroUsize::new(2)))
+        );
+
+        const CUBEMAP: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04,
+        ];
+        assert_eq!(
+            parse_projection_private(&ProjectionType::Cubemap, CUBEMAP),
+            Ok(Some(ProjectionPrivate::Cubemap(CubemapProjection {
+                version: 0,
+                flags: 0,
+                layout: 0,
+                padding: 4,
+            })))
+        );
+
+        const MESH: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56, 0x78, b'd', b'f', b'l', b'8', 0x01, 0x02,
+            0x03,
+        ];
+        assert_eq!(
+            parse_projection_private(&ProjectionType::Mesh, MESH),
+            Ok(Some(ProjectionPrivate::Mesh(MeshProjection {
+                version: 0,
+                flags: 0,
+                crc: 0x1234_5678,
+                encoding: "dfl8".into(),
+                data_size: 3,
+            })))
+        );
+
+        assert_eq!(
+            parse_projection_private(&ProjectionType::Rectangular, &[]),
+            Ok(None)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Grouping of elements per Segment
+//!
+//! A file can hold several Segments, e.g. when WebM streams are
+//! concatenated. Each of them comes with its own EBML header, Tracks and
+//! timestamps, so they are better inspected separately.
+use std::ops::Range;
+
+use crate::{elements::Id, Element};
+
+/// The elements of one Segment of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentGroup {
+    /// Index of the Segment in the input, starting at 0
+    pub index: usize,
+    /// Byte range of the Segment element in the input, if its position and
+    /// size (or computed size) are known
+    pub byte_range: Option<Range<usize>>,
+    /// Indices of the elements of the group: the Segment and its
+    /// descendants, preceded by its EBML header and any other element found
+    /// since the previous Segment
+    pub elements: Range<usize>,
+}
+
+/// Split parsed elements per Segment. A group starts at each new stream,
+/// i.e. at a stream boundary or an EBML header, or at a Segment that follows another one without an EBML header in
+/// between. Elements that are not followed by a Segment are kept in the
+/// last group.
+pub fn group_by_segment(elements: &[Element]) -> Vec<SegmentGroup> {
+    if elements.is_empty() {
+        return Vec::new();
+    }
+    let mut starts = vec![0];
+    let mut has_segment = false;
+    for (index, element) in elements.iter().enumerate() {
+        let starts_stream = matches!(element.header.id, Id::StreamBoundary | Id::Ebml);
+        if has_segment && (starts_stream || element.header.id == Id::Segment) {
+            starts.push(index);
+            has_segment = false;
+        }
+        has_segment |= element.header.id == Id::Segment;
+    }
+
+    let ends = starts.iter().skip(1).copied().chain([elements.len()]);
+    starts
+        .iter()
+        .zip(ends)
+        .enumerate()
+        .map(|(index, (start, end))| {
+            let byte_range = elements[*start..end]
+                .iter()
+                .find(|element| element.header.id == Id::Segment)
+                .and_then(|segment| {
+                    let header = &segment.header;
+                    let size = header.size.or(header.computed_size)?;
+                    header.position.map(|position| position..position + size)
+                });
+            SegmentGroup {
+                index,
+                byte_range,
+                elements: *start..end,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Header};
+
+    #[test]
+    fn test_group_by_segment() {
+        let element = |id, position, size| Element {
+            header: Header {
+                position: Some(position),
+                ..Header::new(id, 4, size)
+            },
+            body: Body::Master,
+        };
+        let elements = [
+            element(Id::Ebml, 0, 0),
+            element(Id::Segment, 4, 10),
+            element(Id::Info, 8, 2),
+            element(Id::Segment, 18, 0),
+            element(Id::StreamBoundary, 22, 0),
+            element(Id::Ebml, 22, 0),
+            element(Id::Segment, 26, 0),
+            element(Id::Void, 30, 0),
+        ];
+        let groups = group_by_segment(&elements);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (
+                    group.index,
+                    group.byte_range.clone(),
+                    group.elements.clone()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (0, Some(4..18), 0..3),
+                (1, Some(18..22), 3..4),
+                (2, Some(26..30), 4..8)
+            ]
+        );
+
+        let groups = group_by_segment(&elements[..1]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].byte_range, None);
+        assert!(group_by_segment(&[]).is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+//! Human-readable sizes of elements, such as `1.5 MiB`
+use crate::Element;
+
+const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Format a size in bytes with binary units, such as `512 B`, `1.5 KiB` or
+/// `2.0 GiB`
+pub fn format_size(size: usize) -> String {
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Set the human-readable size of elements, from their size or, for
+/// elements with unknown size, from their computed size. Exact sizes are
+/// kept in `size` and `computed_size`.
+pub fn set_human_sizes(elements: &mut [Element]) {
+    for element in elements {
+        let header = &mut element.header;
+        header.human_size = header.size.or(header.computed_size).map(format_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024 + 100), "5.0 MiB");
+        assert_eq!(format_size(3 << 30), "3.0 GiB");
+        assert_eq!(format_size(2048 << 40), "2.0 PiB");
+    }
+}
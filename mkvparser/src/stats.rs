@@ -0,0 +1,312 @@
+//! Statistics about parsed elements
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{blocks::blocks, elements::Id, sizes::format_size, Element, Result};
+
+/// Accounting of the Void elements of a file, i.e. the slack left by the
+/// muxer for updating headers in place
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VoidStatistics {
+    /// Number of Void elements
+    pub count: usize,
+    /// Total size of Void elements, headers included
+    pub total_size: usize,
+    /// Size of the largest run of consecutive Void elements
+    pub largest_run: usize,
+}
+
+// Sizes are shown in bytes, or with binary units followed by the exact
+// byte count with the alternate flag, as in `{:#}`
+impl fmt::Display for VoidStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let size = |size| format!("{} ({size} bytes)", format_size(size));
+            return write!(
+                f,
+                "{} Void elements, {} in total, largest run of {}",
+                self.count,
+                size(self.total_size),
+                size(self.largest_run)
+            );
+        }
+        write!(
+            f,
+            "{} Void elements, {} bytes in total, largest run of {} bytes",
+            self.count, self.total_size, self.largest_run
+        )
+    }
+}
+
+/// Count the Void elements among parsed elements. Elements must be in the
+/// order they are parsed, so that consecutive Void elements form a run.
+pub fn void_statistics(elements: &[Element]) -> VoidStatistics {
+    let mut statistics = VoidStatistics::default();
+    let mut run = 0;
+    for element in elements {
+        if element.header.id != Id::Void {
+            run = 0;
+            continue;
+        }
+        let size = element.header.size.unwrap_or_default();
+        statistics.count += 1;
+        statistics.total_size += size;
+        run += size;
+        statistics.largest_run = statistics.largest_run.max(run);
+    }
+    statistics
+}
+
+/// Statistics of the blocks of a track, see [`track_statistics`]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackStatistics {
+    /// Track of the blocks
+    pub track_number: usize,
+    /// Number of SimpleBlocks and Blocks
+    pub blocks: usize,
+    /// Number of frames, laced ones included
+    pub frames: usize,
+    /// Number of blocks that are keyframes
+    pub keyframes: usize,
+    /// Total size of the frames
+    pub bytes: usize,
+    /// Size of the smallest frame
+    pub min_frame_size: Option<usize>,
+    /// Size of the largest frame
+    pub max_frame_size: Option<usize>,
+    /// Average bitrate in bits per second, over the time between the
+    /// first and the last block extended by one average block interval
+    pub average_bitrate: Option<f64>,
+    /// Highest bitrate in bits per second over a second of the timeline
+    pub peak_bitrate: Option<f64>,
+    /// Average time between keyframes in nanoseconds
+    pub average_keyframe_interval_ns: Option<f64>,
+}
+
+impl fmt::Display for TrackStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Track {}: {} blocks, {} frames, {} keyframes, {} bytes",
+            self.track_number, self.blocks, self.frames, self.keyframes, self.bytes
+        )?;
+        if let (Some(min), Some(max)) = (self.min_frame_size, self.max_frame_size) {
+            write!(f, ", frames of {min} to {max} bytes")?;
+        }
+        if let Some(bitrate) = self.average_bitrate {
+            write!(f, ", {:.1} kbit/s on average", bitrate / 1000.0)?;
+        }
+        if let Some(bitrate) = self.peak_bitrate {
+            write!(f, ", {:.1} kbit/s at peak", bitrate / 1000.0)?;
+        }
+        if let Some(interval) = self.average_keyframe_interval_ns {
+            write!(f, ", keyframe every {:.3} s", interval / 1e9)?;
+        }
+        Ok(())
+    }
+}
+
+/// Statistics of the blocks of each track, see [`track_statistics`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Statistics {
+    /// Statistics of each track, in ascending order of track number
+    pub tracks: Vec<TrackStatistics>,
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tracks = self.tracks.iter().map(ToString::to_string);
+        write!(f, "{}", tracks.collect::<Vec<_>>().join("\n"))
+    }
+}
+
+// Statistics of a track whose blocks are still being read
+#[derive(Default)]
+struct PendingTrack {
+    blocks: usize,
+    frames: usize,
+    keyframes: usize,
+    bytes: usize,
+    min_frame_size: Option<usize>,
+    max_frame_size: Option<usize>,
+    // Times of the first and last blocks
+    time_range: Option<(i64, i64)>,
+    // Times of the first and last keyframes
+    keyframe_range: Option<(i64, i64)>,
+    // Size of the frames of each second of the timeline
+    bytes_per_second: BTreeMap<i64, usize>,
+}
+
+// Extend a range of times with a new time
+fn extend(range: &mut Option<(i64, i64)>, time: i64) {
+    *range = Some(match *range {
+        Some((first, last)) => (first.min(time), last.max(time)),
+        None => (time, time),
+    });
+}
+
+impl PendingTrack {
+    fn into_statistics(self, track_number: usize) -> TrackStatistics {
+        // Average time between consecutive blocks within a time range
+        let average_interval = |range: Option<(i64, i64)>, count: usize| {
+            let (first, last) = range?;
+            (count > 1 && last > first).then(|| (last - first) as f64 / (count - 1) as f64)
+        };
+        let average_bitrate = average_interval(self.time_range, self.blocks).map(|interval| {
+            let (first, last) = self.time_range.unwrap_or_default();
+            self.bytes as f64 * 8.0 / (((last - first) as f64 + interval) / 1e9)
+        });
+        TrackStatistics {
+            track_number,
+            blocks: self.blocks,
+            frames: self.frames,
+            keyframes: self.keyframes,
+            bytes: self.bytes,
+            min_frame_size: self.min_frame_size,
+            max_frame_size: self.max_frame_size,
+            average_bitrate,
+            peak_bitrate: self
+                .bytes_per_second
+                .values()
+                .max()
+                .map(|bytes| *bytes as f64 * 8.0),
+            average_keyframe_interval_ns: average_interval(self.keyframe_range, self.keyframes),
+        }
+    }
+}
+
+/// Scan the blocks of Clusters and compute statistics of each track: byte
+/// and frame counts, frame sizes, bitrates and keyframe interval.
+///
+/// Elements must be in the order they are parsed, as for
+/// [`blocks`](crate::blocks::blocks). Times, and so bitrates and keyframe
+/// intervals, are only known for blocks whose Cluster has a Timestamp.
+pub fn track_statistics<I>(elements: I) -> Result<Statistics>
+where
+    I: IntoIterator<Item = Result<Element>>,
+{
+    let mut tracks = BTreeMap::<usize, PendingTrack>::new();
+    for block in blocks(elements) {
+        let block = block?;
+        let track = tracks.entry(block.track_number).or_default();
+        track.blocks += 1;
+        track.frames += block.frame_sizes.len();
+        if block.keyframe {
+            track.keyframes += 1;
+        }
+        let bytes = block.frame_sizes.iter().sum::<usize>();
+        track.bytes += bytes;
+        for &size in &block.frame_sizes {
+            track.min_frame_size = Some(track.min_frame_size.map_or(size, |min| min.min(size)));
+            track.max_frame_size = Some(track.max_frame_size.map_or(size, |max| max.max(size)));
+        }
+        if let Some(time_ns) = block.time_ns {
+            extend(&mut track.time_range, time_ns);
+            if block.keyframe {
+                extend(&mut track.keyframe_range, time_ns);
+            }
+            *track
+                .bytes_per_second
+                .entry(time_ns.div_euclid(1_000_000_000))
+                .or_default() += bytes;
+        }
+    }
+    Ok(Statistics {
+        tracks: tracks
+            .into_iter()
+            .map(|(track_number, track)| track.into_statistics(track_number))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Binary, Body, Header};
+
+    #[test]
+    fn test_void_statistics() {
+        let void = |body_size| Element {
+            header: Header::new(Id::Void, 2, body_size),
+            body: Body::Binary(Binary::Void(body_size)),
+        };
+        let elements = [
+            void(10),
+            Element {
+                header: Header::new(Id::Info, 4, 0),
+                body: Body::Master,
+            },
+            void(20),
+            void(5),
+        ];
+        let statistics = void_statistics(&elements);
+        assert_eq!(
+            statistics,
+            VoidStatistics {
+                count: 3,
+                total_size: 41,
+                largest_run: 29,
+            }
+        );
+        assert_eq!(
+            statistics.to_string(),
+            "3 Void elements, 41 bytes in total, largest run of 29 bytes"
+        );
+        assert_eq!(
+            format!("{statistics:#}"),
+            "3 Void elements, 41 B (41 bytes) in total, largest run of 29 B (29 bytes)"
+        );
+        assert_eq!(void_statistics(&[]), VoidStatistics::default());
+    }
+
+    #[test]
+    fn test_track_statistics() {
+        // Cluster with Timestamp 0, keyframes of track 1 at 0 and 1000 with
+        // 4 and 6 bytes of frame, a frame of 2 bytes at 500 in between, and
+        // a keyframe of track 2 at 0 with 1 byte of frame
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0xA8, 0xE7, 0x81, 0x00, 0xA3, 0x88, 0x81, 0x00, 0x00, 0x80,
+            0x01, 0x02, 0x03, 0x04, 0xA3, 0x86, 0x81, 0x01, 0xF4, 0x00, 0x01, 0x02, 0xA3, 0x8A,
+            0x81, 0x03, 0xE8, 0x80, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xA3, 0x85, 0x82, 0x00,
+            0x00, 0x80, 0x01,
+        ];
+        let statistics = track_statistics(ElementIterator::new(INPUT)).unwrap();
+        assert_eq!(
+            statistics.tracks,
+            [
+                TrackStatistics {
+                    track_number: 1,
+                    blocks: 3,
+                    frames: 3,
+                    keyframes: 2,
+                    bytes: 12,
+                    min_frame_size: Some(2),
+                    max_frame_size: Some(6),
+                    average_bitrate: Some(64.0),
+                    peak_bitrate: Some(48.0),
+                    average_keyframe_interval_ns: Some(1e9),
+                },
+                TrackStatistics {
+                    track_number: 2,
+                    blocks: 1,
+                    frames: 1,
+                    keyframes: 1,
+                    bytes: 1,
+                    min_frame_size: Some(1),
+                    max_frame_size: Some(1),
+                    average_bitrate: None,
+                    peak_bitrate: Some(8.0),
+                    average_keyframe_interval_ns: None,
+                },
+            ]
+        );
+        assert_eq!(
+            statistics.tracks[0].to_string(),
+            "Track 1: 3 blocks, 3 frames, 2 keyframes, 12 bytes, frames of 2 to 6 bytes, \
+             0.1 kbit/s on average, 0.0 kbit/s at peak, keyframe every 1.000 s"
+        );
+    }
+}
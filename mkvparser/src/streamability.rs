@@ -0,0 +1,232 @@
+//! Whether a file is laid out for byte-range adaptive streaming, such as
+//! DASH: headers and Cues before the Clusters, Clusters starting with
+//! keyframes and lasting about the same time
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    blocks::{blocks, clusters},
+    elements::Id,
+    format_time,
+    table::write_table,
+    Element,
+};
+
+/// Where a top-level element is compared to the Clusters
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Placement {
+    /// The top-level element
+    pub id: Id,
+    /// Position of the element in the input, if present
+    pub position: Option<usize>,
+    /// Whether the element is present before the first Cluster
+    pub before_clusters: bool,
+}
+
+/// Streaming properties of a file, see [`streamability`]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Streamability {
+    /// Placement of Info, Tracks, Chapters and Attachments if present, and
+    /// of SeekHead and Cues even if missing
+    pub elements: Vec<Placement>,
+    /// Number of Clusters
+    pub clusters: usize,
+    /// Positions of the Clusters whose first block isn't a keyframe
+    pub clusters_without_leading_keyframe: Vec<usize>,
+    /// Shortest time between consecutive Clusters, in nanoseconds
+    pub min_cluster_duration_ns: Option<i64>,
+    /// Longest time between consecutive Clusters, in nanoseconds
+    pub max_cluster_duration_ns: Option<i64>,
+    /// Average time between consecutive Clusters, in nanoseconds
+    pub average_cluster_duration_ns: Option<i64>,
+    /// Whether the time between consecutive Clusters varies by at most a
+    /// tenth of the average
+    pub uniform_cluster_durations: bool,
+    /// Whether the file can be streamed by byte ranges: headers and Cues
+    /// before the Clusters, Clusters starting with keyframes and uniform
+    /// Cluster durations
+    pub streamable: bool,
+}
+
+impl fmt::Display for Streamability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .elements
+            .iter()
+            .map(|placement| {
+                let (position, status) = match placement.position {
+                    Some(position) if placement.before_clusters => {
+                        (format!("{position:#x}"), "before Clusters")
+                    }
+                    Some(position) => (format!("{position:#x}"), "after Clusters"),
+                    None => (String::new(), "missing"),
+                };
+                vec![placement.id.to_string(), position, status.to_string()]
+            })
+            .collect::<Vec<_>>();
+        write_table(f, &["element", "position", "placement"], &rows)?;
+
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        write!(
+            f,
+            "\n\nClusters starting with a keyframe: {} of {}",
+            self.clusters - self.clusters_without_leading_keyframe.len(),
+            self.clusters
+        )?;
+        if let (Some(min), Some(max), Some(average)) = (
+            self.min_cluster_duration_ns,
+            self.max_cluster_duration_ns,
+            self.average_cluster_duration_ns,
+        ) {
+            write!(
+                f,
+                "\nCluster durations: {} to {}, {} on average",
+                format_time(min),
+                format_time(max),
+                format_time(average)
+            )?;
+        }
+        write!(
+            f,
+            "\nUniform Cluster durations: {}\nStreamable: {}",
+            yes_no(self.uniform_cluster_durations),
+            yes_no(self.streamable)
+        )
+    }
+}
+
+/// Check whether parsed elements are laid out for byte-range streaming.
+///
+/// Elements must be in the order they were parsed, with their positions set
+/// and the bodies of the Clusters parsed.
+pub fn streamability(elements: &[Element]) -> Streamability {
+    let position_of = |id: Id| {
+        elements
+            .iter()
+            .find(|element| element.header.id == id)
+            .and_then(|element| element.header.position)
+    };
+    let first_cluster = position_of(Id::Cluster);
+    let placements = [
+        (Id::Info, true),
+        (Id::Tracks, true),
+        (Id::Chapters, false),
+        (Id::Attachments, false),
+        (Id::SeekHead, true),
+        (Id::Cues, true),
+    ]
+    .into_iter()
+    .filter_map(|(id, always)| {
+        let position = position_of(id.clone());
+        (always || position.is_some()).then(|| Placement {
+            before_clusters: position.is_some_and(|position| {
+                first_cluster.is_none_or(|first_cluster| position < first_cluster)
+            }),
+            id,
+            position,
+        })
+    })
+    .collect::<Vec<_>>();
+
+    let summaries = clusters(elements.iter().map(Ok))
+        .filter_map(|cluster| cluster.ok())
+        .collect::<Vec<_>>();
+    let blocks = blocks(elements.iter().map(Ok))
+        .filter_map(|block| {
+            let block = block.ok()?;
+            Some((block.position?, block.keyframe))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let cluster_positions = summaries
+        .iter()
+        .filter_map(|cluster| cluster.position)
+        .collect::<Vec<_>>();
+    let clusters_without_leading_keyframe = cluster_positions
+        .iter()
+        .enumerate()
+        .filter(|(index, position)| {
+            let end = cluster_positions
+                .get(index + 1)
+                .copied()
+                .unwrap_or(usize::MAX);
+            blocks
+                .range(**position..end)
+                .next()
+                .is_some_and(|(_, keyframe)| !keyframe)
+        })
+        .map(|(_, position)| *position)
+        .collect::<Vec<_>>();
+
+    let durations = summaries
+        .windows(2)
+        .filter_map(|pair| Some(pair[1].time_ns? - pair[0].time_ns?))
+        .collect::<Vec<_>>();
+    let min = durations.iter().min().copied();
+    let max = durations.iter().max().copied();
+    let average =
+        (!durations.is_empty()).then(|| durations.iter().sum::<i64>() / durations.len() as i64);
+    let uniform_cluster_durations = match (min, max, average) {
+        (Some(min), Some(max), Some(average)) => (max - min) * 10 <= average,
+        _ => true,
+    };
+
+    // A SeekHead is optional, but Cues are needed to find the Clusters
+    let streamable = placements.iter().all(|placement| {
+        placement.before_clusters || (placement.id == Id::SeekHead && placement.position.is_none())
+    }) && clusters_without_leading_keyframe.is_empty()
+        && uniform_cluster_durations;
+    Streamability {
+        elements: placements,
+        clusters: summaries.len(),
+        clusters_without_leading_keyframe,
+        min_cluster_duration_ns: min,
+        max_cluster_duration_ns: max,
+        average_cluster_duration_ns: average,
+        uniform_cluster_durations,
+        streamable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_streamability() {
+        // Segment with Info and Tracks, Clusters at 0, 1 and 3 s of which
+        // the second starts with a non-keyframe, and Cues at the end
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xE7, 0x15, 0x49, 0xA9, 0x66, 0x88, 0x4D, 0x80, 0x81, 0x78,
+            0x57, 0x41, 0x81, 0x79, 0x16, 0x54, 0xAE, 0x6B, 0x93, 0xAE, 0x91, 0xD7, 0x81, 0x01,
+            0x73, 0xC5, 0x81, 0x01, 0x83, 0x81, 0x01, 0x86, 0x85, 0x56, 0x5F, 0x56, 0x50, 0x39,
+            0x1F, 0x43, 0xB6, 0x75, 0x8B, 0xE7, 0x82, 0x00, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x00,
+            0x80, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x8B, 0xE7, 0x82, 0x03, 0xE8, 0xA3, 0x85, 0x81,
+            0x00, 0x00, 0x00, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x8B, 0xE7, 0x82, 0x0B, 0xB8, 0xA3,
+            0x85, 0x81, 0x00, 0x00, 0x80, 0x00, 0x1C, 0x53, 0xBB, 0x6B, 0x8D, 0xBB, 0x8B, 0xB3,
+            0x81, 0x00, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x30,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let streamability = streamability(&elements);
+        assert_eq!(streamability.clusters_without_leading_keyframe, [58]);
+        assert_eq!(
+            streamability.to_string(),
+            "element   position  placement\n\
+             Info      0x5       before Clusters\n\
+             Tracks    0x12      before Clusters\n\
+             SeekHead            missing\n\
+             Cues      0x5a      after Clusters\n\
+             \n\
+             Clusters starting with a keyframe: 2 of 3\n\
+             Cluster durations: 00:00:01.000 to 00:00:02.000, 00:00:01.500 on average\n\
+             Uniform Cluster durations: no\n\
+             Streamable: no"
+        );
+    }
+}
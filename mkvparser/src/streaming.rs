@@ -0,0 +1,106 @@
+//! Push-style parsing of elements from byte chunks
+//!
+//! This is useful when the input is not available as a reader, such as
+//! data received from a socket during a live capture: chunks of any size are
+//! fed as they arrive and elements are pulled out as soon as they are complete.
+use std::num::NonZeroUsize;
+
+use crate::{iter::Decoder, Element, ParserOptions, Result};
+
+/// A parser that is fed with chunks of the input and emits complete elements.
+///
+/// Like [`ElementIterator`](crate::iter::ElementIterator), corrupt regions are
+/// reported as `Corrupted` elements and every element has its `position` set.
+/// Bytes from binary bodies that are not needed are discarded as they are fed.
+#[derive(Default)]
+pub struct StreamingParser {
+    decoder: Decoder,
+}
+
+impl StreamingParser {
+    /// Create a new streaming parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given options for parsing
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.decoder.set_options(options);
+        self
+    }
+
+    /// Feed the next chunk of the input
+    pub fn feed(&mut self, data: &[u8]) {
+        self.decoder.feed(data);
+    }
+
+    /// Signal that the input has ended, so that any incomplete data left is
+    /// emitted as a `Truncated` element if it starts an element cut short by
+    /// the end, as a `Corrupted` element otherwise, or as an error if
+    /// corruption recovery is disabled.
+    pub fn finish(&mut self) {
+        self.decoder.finish();
+    }
+
+    /// Number of bytes to feed before the next element can be complete, if
+    /// known. It is set once `next_element()` returns `None` for lack of
+    /// data, and decreases as chunks are fed.
+    pub fn needed(&self) -> Option<NonZeroUsize> {
+        self.decoder.needed()
+    }
+
+    /// Get the next complete element. Returns `None` if more data needs to
+    /// be fed, or once the input has been fully parsed after `finish()`.
+    pub fn next_element(&mut self) -> Option<Result<Element>> {
+        self.decoder.next_element()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iter::ElementIterator;
+
+    #[test]
+    fn test_feed_byte_by_byte() {
+        // EBML header followed by a Void with a 16-bytes body
+        let mut input = vec![
+            0x1A, 0x45, 0xDF, 0xA3, 0x88, 0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D, 0xEC, 0x90,
+        ];
+        input.extend_from_slice(&[0; 16]);
+
+        let mut parser = StreamingParser::new();
+        let mut elements = Vec::new();
+        for byte in &input {
+            parser.feed(&[*byte]);
+            while let Some(element) = parser.next_element() {
+                elements.push(element.unwrap());
+            }
+        }
+        parser.finish();
+        assert!(parser.next_element().is_none());
+
+        let expected = ElementIterator::new(input.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(elements, expected);
+    }
+
+    #[test]
+    fn test_needed_bytes() {
+        let mut parser = StreamingParser::new();
+        assert_eq!(parser.needed(), None);
+        // DocType with a 4-bytes body, fed in parts
+        for (chunk, needed) in [(&[0x42, 0x82][..], 1), (&[0x84], 4), (&[0x77, 0x65], 2)] {
+            parser.feed(chunk);
+            assert!(parser.next_element().is_none());
+            assert_eq!(parser.needed(), NonZeroUsize::new(needed));
+        }
+        parser.feed(&[0x62, 0x6D]);
+        assert_eq!(parser.needed(), None);
+        assert_eq!(
+            parser.next_element().unwrap().unwrap().body,
+            crate::Body::String("webm".to_string())
+        );
+    }
+}
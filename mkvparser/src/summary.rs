@@ -0,0 +1,149 @@
+//! Compact overview of a file: its DocType, duration, applications, tracks,
+//! chapters and attachments
+use std::fmt;
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    elements::Id,
+    format_time,
+    model::{ChapterAtom, Document, TrackSummary},
+    Body, Element,
+};
+
+/// Overview of the first Segment of a file, along with its EBML header
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    /// DocType of the EBML header, such as `matroska` or `webm`
+    pub doc_type: Option<String>,
+    /// DocTypeVersion of the EBML header
+    pub doc_type_version: Option<u64>,
+    /// Duration of the Segment in nanoseconds
+    pub duration_ns: Option<i64>,
+    /// Duration of the Segment as HH:MM:SS.mmm
+    pub duration: Option<String>,
+    /// Muxing application or library
+    pub muxing_app: Option<String>,
+    /// Writing application
+    pub writing_app: Option<String>,
+    /// Overview of each track
+    pub tracks: Vec<TrackSummary>,
+    /// Number of chapters of all editions, nested chapters included
+    pub chapters: usize,
+    /// Number of attached files
+    pub attachments: usize,
+}
+
+fn count_chapters(atoms: &[ChapterAtom]) -> usize {
+    atoms
+        .iter()
+        .map(|atom| 1 + count_chapters(&atom.children))
+        .sum()
+}
+
+/// Summarize parsed elements. Only the first Segment is considered.
+pub fn summarize(elements: &[Element]) -> Summary {
+    let mut doc_type = None;
+    let mut doc_type_version = None;
+    for element in elements {
+        match (&element.header.id, &element.body) {
+            (Id::Segment, _) => break,
+            (Id::DocType, Body::String(value)) => doc_type = Some(value.clone()),
+            (Id::DocTypeVersion, Body::Unsigned(value)) => doc_type_version = Some(value.value()),
+            _ => {}
+        }
+    }
+    let document = Document::from_elements(elements);
+    let info = document.info.as_ref();
+    let duration_ns = info
+        .and_then(|info| info.duration_ns())
+        .map(|duration| duration.round() as i64);
+    Summary {
+        doc_type,
+        doc_type_version,
+        duration_ns,
+        duration: duration_ns.map(format_time),
+        muxing_app: info.and_then(|info| info.muxing_app.clone()),
+        writing_app: info.and_then(|info| info.writing_app.clone()),
+        tracks: document.tracks.iter().map(TrackSummary::new).collect(),
+        chapters: document
+            .chapters
+            .iter()
+            .map(|edition| count_chapters(&edition.chapters))
+            .sum(),
+        attachments: document.attachments.len(),
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DocType: {}", self.doc_type.as_deref().unwrap_or("?"))?;
+        if let Some(version) = self.doc_type_version {
+            write!(f, ", version {version}")?;
+        }
+        writeln!(f)?;
+        if let Some(duration) = &self.duration {
+            writeln!(f, "Duration: {duration}")?;
+        }
+        if let Some(muxing_app) = &self.muxing_app {
+            writeln!(f, "Muxing application: {muxing_app}")?;
+        }
+        if let Some(writing_app) = &self.writing_app {
+            writeln!(f, "Writing application: {writing_app}")?;
+        }
+        for track in &self.tracks {
+            writeln!(f, "{track}")?;
+        }
+        writeln!(f, "Chapters: {}", self.chapters)?;
+        write!(f, "Attachments: {}", self.attachments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Header, Unsigned};
+
+    fn element(id: Id, header_size: usize, body_size: usize, body: Body) -> Element {
+        Element {
+            header: Header::new(id, header_size, body_size),
+            body,
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let elements = [
+            element(Id::Ebml, 5, 11, Body::Master),
+            element(Id::DocType, 3, 4, Body::String("webm".into())),
+            element(
+                Id::DocTypeVersion,
+                3,
+                1,
+                Body::Unsigned(Unsigned::Standard(4)),
+            ),
+            element(Id::Segment, 5, 46, Body::Master),
+            element(Id::Info, 2, 17, Body::Master),
+            element(Id::Duration, 2, 4, Body::Float(1500.0)),
+            element(Id::WritingApp, 3, 8, Body::Utf8("mkvdump".into())),
+            element(Id::Tracks, 5, 24, Body::Master),
+            element(Id::TrackEntry, 2, 22, Body::Master),
+            element(Id::TrackNumber, 2, 1, Body::Unsigned(Unsigned::Standard(1))),
+            element(Id::CodecId, 2, 6, Body::String("A_OPUS".into())),
+        ];
+        let summary = summarize(&elements);
+        assert_eq!(summary.doc_type.as_deref(), Some("webm"));
+        assert_eq!(summary.duration_ns, Some(1_500_000_000));
+        assert_eq!(
+            summary.to_string(),
+            "DocType: webm, version 4\n\
+             Duration: 00:00:01.500\n\
+             Writing application: mkvdump\n\
+             Track 1, A_OPUS, language eng, default\n\
+             Chapters: 0\n\
+             Attachments: 0"
+        );
+    }
+}
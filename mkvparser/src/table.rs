@@ -0,0 +1,31 @@
+// Plain text tables, with columns aligned on their widest cell
+use std::fmt;
+
+pub(crate) fn write_table(
+    f: &mut fmt::Formatter<'_>,
+    columns: &[&str],
+    rows: &[Vec<String>],
+) -> fmt::Result {
+    let mut widths = columns
+        .iter()
+        .map(|column| column.len())
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let header = columns.iter().map(|column| column.to_string()).collect();
+    for (index, row) in std::iter::once(&header).chain(rows).enumerate() {
+        if index > 0 {
+            writeln!(f)?;
+        }
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
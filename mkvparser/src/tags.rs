@@ -0,0 +1,161 @@
+//! Tags of a file grouped by what they apply to, with their targets
+//! resolved to the tracks, chapters and attachments of the file
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    model::{Document, TagTarget},
+    Element,
+};
+
+/// A name/value pair of a tag. Names of nested tags are prefixed by the
+/// names of their parents, such as `ARTIST/URL`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagValue {
+    /// Name of the tag
+    pub name: String,
+    /// String value of the tag
+    pub value: String,
+}
+
+/// Tags applying to a target, see [`tag_groups`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagGroup {
+    /// What the tags apply to
+    pub target: TagTarget,
+    /// Description of the target, such as `Track 1 (uid 123)`
+    pub description: String,
+    /// Name/value pairs in the order of the tags
+    pub tags: Vec<TagValue>,
+}
+
+/// Tags of a file, grouped by target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagList {
+    /// Groups of tags, with the Segment first and then tracks, editions,
+    /// chapters and attachments by unique ID
+    pub groups: Vec<TagGroup>,
+}
+
+impl fmt::Display for TagList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, group) in self.groups.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", group.description)?;
+            for tag in &group.tags {
+                write!(f, "\n  {}: {}", tag.name, tag.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Describe a target with what it resolves to in the document
+fn describe(document: &Document, target: TagTarget) -> String {
+    match target {
+        TagTarget::Segment => "Segment".to_string(),
+        TagTarget::Track(uid) => {
+            let track = document
+                .tracks
+                .iter()
+                .find(|track| track.track_uid == Some(uid));
+            match track.and_then(|track| track.track_number) {
+                Some(track_number) => format!("Track {track_number} (uid {uid})"),
+                None => format!("Track (uid {uid}, not found)"),
+            }
+        }
+        TagTarget::Edition(uid) => {
+            let found = document
+                .chapters
+                .iter()
+                .any(|edition| edition.uid == Some(uid));
+            if found {
+                format!("Edition (uid {uid})")
+            } else {
+                format!("Edition (uid {uid}, not found)")
+            }
+        }
+        TagTarget::Chapter(uid) => {
+            let chapter = document
+                .chapter_timeline()
+                .into_iter()
+                .find(|chapter| chapter.uid == Some(uid));
+            match chapter {
+                Some(chapter) => match chapter.titles.values().next() {
+                    Some(title) => format!("Chapter {title} (uid {uid})"),
+                    None => format!("Chapter (uid {uid})"),
+                },
+                None => format!("Chapter (uid {uid}, not found)"),
+            }
+        }
+        TagTarget::Attachment(uid) => {
+            let attachment = document
+                .attachments
+                .iter()
+                .find(|attachment| attachment.uid == Some(uid));
+            match attachment {
+                Some(attachment) => format!("Attachment {} (uid {uid})", attachment.file_name),
+                None => format!("Attachment (uid {uid}, not found)"),
+            }
+        }
+    }
+}
+
+/// Group the string values of the tags of the first Segment of parsed
+/// elements by target, nested tags included
+pub fn tag_groups(elements: &[Element]) -> TagList {
+    let document = Document::from_elements(elements);
+    let groups = document
+        .tags_by_target()
+        .into_iter()
+        .map(|(target, values)| TagGroup {
+            target,
+            description: describe(&document, target),
+            tags: values
+                .into_iter()
+                .map(|(name, value)| TagValue { name, value })
+                .collect(),
+        })
+        .collect();
+    TagList { groups }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_tag_groups() {
+        // Track 1 with UID 11, a Segment tag with a nested tag, and tags of
+        // track 11 and of a missing chapter
+        const INPUT: &[u8] = &[
+            0x16, 0x54, 0xAE, 0x6B, 0x89, 0xAE, 0x87, 0xD7, 0x81, 0x01, 0x73, 0xC5, 0x81, 0x0B,
+            0x12, 0x54, 0xC3, 0x67, 0xF1, 0x73, 0x73, 0xB5, 0x67, 0xC8, 0xB2, 0x45, 0xA3, 0x86,
+            0x41, 0x52, 0x54, 0x49, 0x53, 0x54, 0x44, 0x87, 0x87, 0x53, 0x6F, 0x6D, 0x65, 0x6F,
+            0x6E, 0x65, 0x67, 0xC8, 0x9C, 0x45, 0xA3, 0x83, 0x55, 0x52, 0x4C, 0x44, 0x87, 0x93,
+            0x68, 0x74, 0x74, 0x70, 0x73, 0x3A, 0x2F, 0x2F, 0x65, 0x78, 0x61, 0x6D, 0x70, 0x6C,
+            0x65, 0x2E, 0x63, 0x6F, 0x6D, 0x73, 0x73, 0x99, 0x63, 0xC0, 0x84, 0x63, 0xC5, 0x81,
+            0x0B, 0x67, 0xC8, 0x8F, 0x45, 0xA3, 0x85, 0x54, 0x49, 0x54, 0x4C, 0x45, 0x44, 0x87,
+            0x84, 0x4D, 0x61, 0x69, 0x6E, 0x73, 0x73, 0x9A, 0x63, 0xC0, 0x84, 0x63, 0xC4, 0x81,
+            0x07, 0x67, 0xC8, 0x90, 0x45, 0xA3, 0x85, 0x54, 0x49, 0x54, 0x4C, 0x45, 0x44, 0x87,
+            0x85, 0x49, 0x6E, 0x74, 0x72, 0x6F,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            tag_groups(&elements).to_string(),
+            "Segment\n  \
+             ARTIST: Someone\n  \
+             ARTIST/URL: https://example.com\n\
+             Track 1 (uid 11)\n  \
+             TITLE: Main\n\
+             Chapter (uid 7, not found)\n  \
+             TITLE: Intro"
+        );
+    }
+}
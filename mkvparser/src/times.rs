@@ -0,0 +1,89 @@
+//! Human-readable times of elements holding a time or a duration
+//!
+//! Most of these elements count ticks of the TimestampScale of their
+//! Segment, so [`set_human_times`] needs the elements in parsing order.
+use crate::{elements::Id, format_time, model::DEFAULT_TIMESTAMP_SCALE, Body, Element};
+
+// Time in nanoseconds of a number of ticks
+fn scaled(ticks: f64, timestamp_scale: u64) -> Option<i64> {
+    let time_ns = (ticks * timestamp_scale as f64).round();
+    (time_ns.abs() < i64::MAX as f64).then_some(time_ns as i64)
+}
+
+/// Set the human-readable time, such as `00:01:30.500`, of elements
+/// holding a time or a duration: Cluster Timestamps, Duration, CueTime,
+/// CueDuration, BlockDuration, ChapterTimeStart and ChapterTimeEnd, and of
+/// SimpleBlocks and Blocks with an absolute timestamp.
+///
+/// Elements must be in the order they are parsed. Times in ticks are scaled
+/// by the last TimestampScale of their Segment, which defaults to 1
+/// millisecond.
+pub fn set_human_times(elements: &mut [Element]) {
+    let mut timestamp_scale = DEFAULT_TIMESTAMP_SCALE;
+    for element in elements {
+        let time_ns = match (&element.header.id, &element.body) {
+            (Id::Segment, _) => {
+                timestamp_scale = DEFAULT_TIMESTAMP_SCALE;
+                None
+            }
+            (Id::TimestampScale, Body::Unsigned(value)) => {
+                timestamp_scale = value.value();
+                None
+            }
+            (
+                Id::Timestamp | Id::CueTime | Id::CueDuration | Id::BlockDuration,
+                Body::Unsigned(value),
+            ) => scaled(value.value() as f64, timestamp_scale),
+            (Id::Duration, Body::Float(value)) => scaled(*value, timestamp_scale),
+            (Id::ChapterTimeStart | Id::ChapterTimeEnd, Body::Unsigned(value)) => {
+                i64::try_from(value.value()).ok()
+            }
+            (Id::SimpleBlock | Id::Block, Body::Binary(binary)) => binary.absolute_time_ns(),
+            _ => None,
+        };
+        if let Some(time_ns) = time_ns {
+            element.header.time = Some(format_time(time_ns));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_set_human_times() {
+        // Segment with an Info of TimestampScale 1000000 and Duration 2000,
+        // and a Cluster with Timestamp 1500 and a SimpleBlock 10 ticks later
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xA2, 0x15, 0x49, 0xA9, 0x66, 0x8E, 0x2A, 0xD7, 0xB1, 0x83,
+            0x0F, 0x42, 0x40, 0x44, 0x89, 0x84, 0x44, 0xFA, 0x00, 0x00, 0x1F, 0x43, 0xB6, 0x75,
+            0x8A, 0xE7, 0x82, 0x05, 0xDC, 0xA3, 0x84, 0x81, 0x00, 0x0A, 0x80,
+        ];
+        let mut elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        set_human_times(&mut elements);
+        let times = elements
+            .iter()
+            .map(|element| element.header.time.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            times,
+            [
+                None,
+                None,
+                None,
+                Some("00:00:02.000"),
+                None,
+                Some("00:00:01.500"),
+                Some("00:00:01.510"),
+            ]
+        );
+
+        // Times are rounded to the nearest millisecond
+        assert_eq!(format_time(5_025_999_600_000), "01:23:46.000");
+        assert_eq!(format_time(-1_499_999), "-00:00:00.001");
+    }
+}
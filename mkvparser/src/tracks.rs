@@ -0,0 +1,129 @@
+//! Table of the tracks of a file, for a quick look at their codecs,
+//! languages, flags and dimensions
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    model::{Document, TrackEntry},
+    table::write_table,
+    Element,
+};
+
+/// Tracks of a file, see [`track_table`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackTable {
+    /// Tracks in the order of the Tracks element
+    pub tracks: Vec<TrackEntry>,
+}
+
+// Flags of a track that differ from the usual, such as `default,forced`
+fn flags(track: &TrackEntry) -> String {
+    let flags = [
+        (track.default, "default"),
+        (track.forced, "forced"),
+        (!track.enabled, "disabled"),
+        (track.hearing_impaired, "hearing-impaired"),
+        (track.visual_impaired, "visual-impaired"),
+        (track.original, "original"),
+        (track.commentary, "commentary"),
+    ];
+    let flags = flags.iter().filter(|(set, _)| *set).map(|(_, flag)| *flag);
+    flags.collect::<Vec<_>>().join(",")
+}
+
+// Dimensions such as `1920x1080`, if both are known
+fn dimensions(width: Option<u64>, height: Option<u64>) -> String {
+    match (width, height) {
+        (Some(width), Some(height)) => format!("{width}x{height}"),
+        _ => String::new(),
+    }
+}
+
+impl fmt::Display for TrackTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .tracks
+            .iter()
+            .map(|track| {
+                let video = track.video.as_ref();
+                vec![
+                    track
+                        .track_number
+                        .map(|number| number.to_string())
+                        .unwrap_or_default(),
+                    track
+                        .track_uid
+                        .map(|uid| uid.to_string())
+                        .unwrap_or_default(),
+                    track
+                        .track_type
+                        .as_ref()
+                        .map(|track_type| track_type.label().to_string())
+                        .unwrap_or_default(),
+                    track.codec_id.clone().unwrap_or_default(),
+                    track
+                        .language_bcp47
+                        .clone()
+                        .unwrap_or_else(|| track.language.clone()),
+                    flags(track),
+                    dimensions(
+                        video.and_then(|video| video.pixel_width),
+                        video.and_then(|video| video.pixel_height),
+                    ),
+                    dimensions(
+                        video.and_then(|video| video.display_width),
+                        video.and_then(|video| video.display_height),
+                    ),
+                    track
+                        .audio
+                        .as_ref()
+                        .map(|audio| audio.sampling_frequency.to_string())
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        write_table(
+            f,
+            &[
+                "number", "uid", "type", "codec", "language", "flags", "pixels", "display", "rate",
+            ],
+            &rows,
+        )
+    }
+}
+
+/// List the tracks of the first Segment of parsed elements
+pub fn track_table(elements: &[Element]) -> TrackTable {
+    TrackTable {
+        tracks: Document::from_elements(elements).tracks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_track_table() {
+        // Tracks with a forced video track of 640x360 displayed as 640x480
+        // and a disabled audio track at 48000 Hz in German
+        const INPUT: &[u8] = &[
+            0x16, 0x54, 0xAE, 0x6B, 0xBA, 0xAE, 0x9E, 0xD7, 0x81, 0x01, 0x83, 0x81, 0x01, 0x55,
+            0xAA, 0x81, 0x01, 0xE0, 0x92, 0xB0, 0x82, 0x02, 0x80, 0xBA, 0x82, 0x01, 0x68, 0x54,
+            0xB0, 0x82, 0x02, 0x80, 0x54, 0xBA, 0x82, 0x01, 0xE0, 0xAE, 0x98, 0xD7, 0x81, 0x02,
+            0x83, 0x81, 0x02, 0xB9, 0x81, 0x00, 0x22, 0xB5, 0x9C, 0x83, 0x67, 0x65, 0x72, 0xE1,
+            0x86, 0xB5, 0x84, 0x47, 0x3B, 0x80, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            track_table(&elements).to_string(),
+            "number  uid  type   codec  language  flags             pixels   display  rate\n\
+             1            video         eng       default,forced    640x360  640x480\n\
+             2            audio         ger       default,disabled                    48000"
+        );
+    }
+}
@@ -1,21 +1,26 @@
 //! The tree module contains helpers for building tree
 //! structures from parsed elements
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 
-use crate::{Body, Element, Header, Id};
+use crate::{
+    elements::{ElementSchema, Type, SCHEMA_IDS},
+    validate::parse_number,
+    Body, Element, Header, Id, Unsigned,
+};
 
 /// A Master Element that owns its children for diplaying
 /// it in an element tree
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct MasterElement {
     #[serde(flatten)]
-    header: Header,
-    children: Vec<ElementTree>,
+    pub(crate) header: Header,
+    pub(crate) children: Vec<ElementTree>,
 }
 
 /// An Element Tree can either be a leaf or a Master
 /// element.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum ElementTree {
     /// A Normal Element that represents a leaf in the tree
@@ -24,68 +29,531 @@ pub enum ElementTree {
     Master(MasterElement),
 }
 
+impl MasterElement {
+    /// The header of the Master element
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The elements directly contained in the Master element
+    pub fn children(&self) -> &[ElementTree] {
+        &self.children
+    }
+}
+
+impl ElementTree {
+    /// The header of the element
+    pub fn header(&self) -> &Header {
+        match self {
+            ElementTree::Normal(element) => &element.header,
+            ElementTree::Master(master) => &master.header,
+        }
+    }
+
+    /// The body of the element, or `None` for Master elements
+    pub fn body(&self) -> Option<&Body> {
+        match self {
+            ElementTree::Normal(element) => Some(&element.body),
+            ElementTree::Master(_) => None,
+        }
+    }
+
+    /// The elements directly contained in this element, which are only
+    /// present in Master elements
+    pub fn children(&self) -> &[ElementTree] {
+        match self {
+            ElementTree::Normal(_) => &[],
+            ElementTree::Master(master) => &master.children,
+        }
+    }
+
+    /// All elements contained in this element, at any depth, in the order
+    /// they appear in the input
+    pub fn descendants(&self) -> Descendants<'_> {
+        Descendants {
+            stack: vec![self.children().iter()],
+        }
+    }
+
+    /// Find the first element with the given ID within this element, at any
+    /// depth
+    pub fn find(&self, id: &Id) -> Option<&ElementTree> {
+        self.descendants().find(|tree| tree.header().id == *id)
+    }
+
+    /// Find all elements with the given ID within this element, at any depth
+    pub fn find_all<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = &'a ElementTree> {
+        self.descendants()
+            .filter(move |tree| tree.header().id == *id)
+    }
+
+    /// The value of an Unsigned Integer element
+    pub fn as_unsigned(&self) -> Option<u64> {
+        match self.body()? {
+            Body::Unsigned(value) => Some(value.value()),
+            _ => None,
+        }
+    }
+
+    /// The value of a Signed Integer element
+    pub fn as_signed(&self) -> Option<i64> {
+        match self.body()? {
+            Body::Signed(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value of a Float element
+    pub fn as_float(&self) -> Option<f64> {
+        match self.body()? {
+            Body::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value of a String or UTF-8 String element
+    pub fn as_string(&self) -> Option<&str> {
+        match self.body()? {
+            Body::String(value) | Body::Utf8(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value of a Date element
+    pub fn as_date(&self) -> Option<DateTime<Utc>> {
+        match self.body()? {
+            Body::Date(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the descendants of an element, in depth-first order.
+///
+/// Created with [`ElementTree::descendants`].
+pub struct Descendants<'a> {
+    stack: Vec<std::slice::Iter<'a, ElementTree>>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = &'a ElementTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(tree) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+                continue;
+            };
+            self.stack.push(tree.children().iter());
+            return Some(tree);
+        }
+    }
+}
+
 impl Id {
-    fn can_be_children_of(&self, other: &Id) -> bool {
-        !matches!((self, other), (Id::Cluster, Id::Cluster) | (Id::Ebml, _))
+    // Used to find where elements with unknown size end, which can only be
+    // Segment and Cluster, unless parsing leniently.
+    pub(crate) fn can_be_children_of(&self, other: &Id) -> bool {
+        match other {
+            Id::Segment => !matches!(self, Id::Ebml | Id::Segment | Id::StreamBoundary),
+            Id::Cluster => !matches!(
+                self,
+                Id::Ebml
+                    | Id::StreamBoundary
+                    | Id::Segment
+                    | Id::SeekHead
+                    | Id::Info
+                    | Id::Tracks
+                    | Id::Cues
+                    | Id::Chapters
+                    | Id::Tags
+                    | Id::Attachments
+                    | Id::Cluster
+            ),
+            _ => !matches!(self, Id::Ebml | Id::StreamBoundary),
+        }
     }
 }
 
-/// Build element trees from a series of elements
-pub fn build_element_trees(elements: &[Element]) -> Vec<ElementTree> {
-    let mut trees = Vec::<ElementTree>::new();
+/// Compute the sizes of Master elements with unknown size.
+///
+/// Such elements extend up to the next element that can't be one of their
+/// children, or up to `input_size` for the last ones. Elements must have
+/// their positions set.
+pub fn compute_unknown_sizes(elements: &mut [Element], input_size: usize) {
+    // Indices of open Master elements and where they end, if known
+    let mut open_masters = Vec::<(usize, Option<usize>)>::new();
+
+    for index in 0..elements.len() {
+        let Some(position) = elements[index].header.position else {
+            continue;
+        };
+        let id = &elements[index].header.id;
+        let closed = open_masters
+            .iter()
+            .position(|(master_index, end)| match end {
+                Some(end) => position >= *end,
+                None => !id.can_be_children_of(&elements[*master_index].header.id),
+            });
+        if let Some(closed) = closed {
+            for (master_index, _) in open_masters.drain(closed..) {
+                set_computed_size(&mut elements[master_index], position);
+            }
+        }
+
+        if let Body::Master = elements[index].body {
+            let end = elements[index].header.size.map(|size| position + size);
+            open_masters.push((index, end));
+        }
+    }
+
+    for (master_index, _) in open_masters {
+        set_computed_size(&mut elements[master_index], input_size);
+    }
+}
+
+fn set_computed_size(element: &mut Element, end: usize) {
+    let header = &mut element.header;
+    if let (None, Some(position)) = (header.size, header.position) {
+        header.computed_size = Some(end.saturating_sub(position));
+    }
+}
+
+// Elements that the schema places directly within a parent
+pub(crate) fn schema_children(
+    parent: &ElementSchema,
+) -> impl Iterator<Item = (&'static Id, &'static ElementSchema)> + '_ {
+    SCHEMA_IDS.iter().filter_map(move |id| {
+        let schema = id.schema()?;
+        let (path, _) = schema.path.rsplit_once('\\')?;
+        (path == parent.path).then_some((id, schema))
+    })
+}
+
+/// Insert mandatory elements that are absent from their parent but have a
+/// default value in the EBML Schema.
+///
+/// The inserted elements hold the default value, are marked as `implied` and
+/// have no size in the input.
+pub fn insert_implied_elements(trees: &mut [ElementTree]) {
+    for tree in trees {
+        let ElementTree::Master(master) = tree else {
+            continue;
+        };
+        insert_implied_elements(&mut master.children);
+
+        let Some(schema) = master.header.id.schema() else {
+            continue;
+        };
+        for (id, child_schema) in schema_children(schema) {
+            let is_present = master.children.iter().any(|child| match child {
+                ElementTree::Normal(element) => element.header.id == *id,
+                ElementTree::Master(master) => master.header.id == *id,
+            });
+            if is_present || child_schema.min_occurs == 0 {
+                continue;
+            }
+            if let Some(body) = child_schema
+                .default
+                .and_then(|value| default_body(id, value))
+            {
+                master.children.push(ElementTree::Normal(Element {
+                    header: Header {
+                        implied: true,
+                        ..Header::new(id.clone(), 0, 0)
+                    },
+                    body,
+                }));
+            }
+        }
+    }
+}
 
+fn default_body(id: &Id, value: &str) -> Option<Body> {
+    let body = match id.get_type() {
+        Type::Unsigned => Body::Unsigned(Unsigned::new(id, value.parse().ok()?)),
+        Type::Signed => Body::Signed(value.parse().ok()?),
+        Type::Float => Body::Float(parse_number(value)?),
+        Type::String => Body::String(value.to_string()),
+        Type::Utf8 => Body::Utf8(value.to_string()),
+        _ => return None,
+    };
+    Some(body)
+}
+
+/// Keep only the elements with one of the `include` IDs, along with their
+/// ancestors and descendants, and remove the elements with one of the
+/// `exclude` IDs, along with their descendants. An empty `include` keeps
+/// all elements that aren't excluded.
+pub fn filter_elements(elements: &mut Vec<Element>, include: &[Id], exclude: &[Id]) {
+    let mut children_counts = vec![0; elements.len()];
+    count_children(elements, &mut children_counts);
+    let mut keep = vec![false; elements.len()];
+    mark_kept(elements, &children_counts, &mut keep, include, exclude);
+    let mut keep = keep.into_iter();
+    elements.retain(|_| keep.next().unwrap_or(false));
+}
+
+// Mark the elements to keep, returning whether any of them is kept
+fn mark_kept(
+    elements: &[Element],
+    children_counts: &[usize],
+    keep: &mut [bool],
+    include: &[Id],
+    exclude: &[Id],
+) -> bool {
+    let mut any_kept = false;
     let mut index = 0;
     while index < elements.len() {
-        let element = &elements[index];
-        match element.body {
-            Body::Master => {
-                // parse_header() already handles Unknown sizes.
-                let mut size_remaining = element.header.body_size.unwrap_or(usize::MAX);
-
-                let mut children = Vec::<Element>::new();
-                while size_remaining > 0 {
-                    index += 1;
-
-                    if let Some(next_child) = elements.get(index) {
-                        if !next_child.header.id.can_be_children_of(&element.header.id) {
-                            index -= 1;
-                            break;
-                        }
-
-                        size_remaining -= if let Body::Master = next_child.body {
-                            // Master elements' body size should not count in the recursion
-                            // as the children would duplicate the size count, so
-                            // we only consider the header size on the calculation.
-                            next_child.header.header_size
-                        } else {
-                            next_child
-                                .header
-                                .size
-                                .expect("Only Master elements can have unknown size")
-                        };
-                        children.push(next_child.clone());
-                    } else {
-                        // Elements have ended before reaching the size of the master element
-                        break;
-                    }
-                }
-                trees.push(ElementTree::Master(MasterElement {
-                    header: element.header.clone(),
-                    children: build_element_trees(&children),
-                }));
+        let id = &elements[index].header.id;
+        let children = index + 1..index + 1 + children_counts[index];
+        if !exclude.contains(id) {
+            // Everything within an included element is included
+            let include = if include.contains(id) { &[] } else { include };
+            let children_kept = mark_kept(
+                &elements[children.clone()],
+                &children_counts[children.clone()],
+                &mut keep[children.clone()],
+                include,
+                exclude,
+            );
+            keep[index] = include.is_empty() || children_kept;
+            any_kept |= keep[index];
+        }
+        index = children.end;
+    }
+    any_kept
+}
+
+/// Same as [`filter_elements`], for element trees
+pub fn filter_element_trees(trees: &mut Vec<ElementTree>, include: &[Id], exclude: &[Id]) {
+    trees.retain_mut(|tree| {
+        let id = &tree.header().id;
+        if exclude.contains(id) {
+            return false;
+        }
+        let include = if include.contains(id) { &[] } else { include };
+        match tree {
+            ElementTree::Master(master) => {
+                filter_element_trees(&mut master.children, include, exclude);
+                include.is_empty() || !master.children.is_empty()
             }
-            _ => {
-                trees.push(ElementTree::Normal(element.clone()));
+            ElementTree::Normal(_) => include.is_empty(),
+        }
+    });
+}
+
+/// Remove the elements nested deeper than `max_depth`, top-level elements
+/// being at depth 0
+pub fn truncate_element_trees(trees: &mut [ElementTree], max_depth: usize) {
+    for tree in trees {
+        let ElementTree::Master(master) = tree else {
+            continue;
+        };
+        match max_depth.checked_sub(1) {
+            Some(max_depth) => truncate_element_trees(&mut master.children, max_depth),
+            None => master.children.clear(),
+        }
+    }
+}
+
+/// Build element trees from a series of elements
+pub fn build_element_trees(elements: &[Element]) -> Vec<ElementTree> {
+    build_element_trees_owned(elements.to_vec())
+}
+
+/// Build element trees from a series of elements, moving them into the trees
+/// rather than cloning them
+pub fn build_element_trees_owned(elements: Vec<Element>) -> Vec<ElementTree> {
+    let mut children_counts = vec![0; elements.len()];
+    count_children(&elements, &mut children_counts);
+
+    let len = elements.len();
+    let mut elements = elements.into_iter().zip(children_counts);
+    assemble_trees(&mut elements, len)
+}
+
+/// A view of an element and the elements it contains, borrowed from the
+/// series of parsed elements.
+///
+/// Unlike [`ElementTree`], children are found when traversing the view rather
+/// than copied, so it's cheaper to build when the tree is traversed only once,
+/// e.g. to be serialized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElementTreeRef<'a> {
+    // The element followed by all the elements it contains
+    elements: &'a [Element],
+}
+
+impl<'a> ElementTreeRef<'a> {
+    /// The element at the root of this tree
+    pub fn element(&self) -> &'a Element {
+        &self.elements[0]
+    }
+
+    /// The header of the element
+    pub fn header(&self) -> &'a Header {
+        &self.element().header
+    }
+
+    /// Whether the element is a Master element
+    pub fn is_master(&self) -> bool {
+        matches!(self.element().body, Body::Master)
+    }
+
+    /// The elements directly contained in this element
+    pub fn children(&self) -> impl Iterator<Item = ElementTreeRef<'a>> {
+        tree_refs(&self.elements[1..])
+    }
+
+    /// All elements contained in this element, at any depth, in the order
+    /// they appear in the input
+    pub fn descendants(&self) -> impl Iterator<Item = &'a Element> {
+        self.elements[1..].iter()
+    }
+
+    /// Copy this view into an owned [`ElementTree`]
+    pub fn to_element_tree(&self) -> ElementTree {
+        if self.is_master() {
+            ElementTree::Master(MasterElement {
+                header: self.header().clone(),
+                children: self
+                    .children()
+                    .map(|child| child.to_element_tree())
+                    .collect(),
+            })
+        } else {
+            ElementTree::Normal(self.element().clone())
+        }
+    }
+}
+
+impl Serialize for ElementTreeRef<'_> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        struct Children<'a>(ElementTreeRef<'a>);
+
+        impl Serialize for Children<'_> {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.collect_seq(self.0.children())
+            }
+        }
+
+        // Same layout as MasterElement
+        #[derive(Serialize)]
+        struct MasterElementRef<'a> {
+            #[serde(flatten)]
+            header: &'a Header,
+            children: Children<'a>,
+        }
+
+        if self.is_master() {
+            MasterElementRef {
+                header: self.header(),
+                children: Children(*self),
             }
+            .serialize(s)
+        } else {
+            self.element().serialize(s)
+        }
+    }
+}
+
+/// Build views of element trees over a series of elements, without copying
+/// them
+pub fn build_element_tree_refs(elements: &[Element]) -> Vec<ElementTreeRef<'_>> {
+    tree_refs(elements).collect()
+}
+
+// Split a series of elements into trees, looking for the children of Master
+// elements within the series only
+fn tree_refs(elements: &[Element]) -> impl Iterator<Item = ElementTreeRef<'_>> {
+    let mut index = 0;
+    std::iter::from_fn(move || {
+        let element = elements.get(index)?;
+        let count = match element.body {
+            Body::Master => children_count(elements, index),
+            _ => 0,
+        };
+        let tree = ElementTreeRef {
+            elements: &elements[index..index + 1 + count],
+        };
+        index += 1 + count;
+        Some(tree)
+    })
+}
+
+// Count the elements within each Master element, including nested ones.
+// Children of a Master element are looked for within its parent only.
+fn count_children(elements: &[Element], counts: &mut [usize]) {
+    let mut index = 0;
+    while index < elements.len() {
+        if let Body::Master = elements[index].body {
+            let count = children_count(elements, index);
+            let children = index + 1..index + 1 + count;
+            counts[index] = count;
+            count_children(&elements[children.clone()], &mut counts[children]);
+            index += count;
         }
         index += 1;
     }
+}
+
+fn children_count(elements: &[Element], index: usize) -> usize {
+    let element = &elements[index];
+    // parse_header() already handles Unknown sizes.
+    let mut size_remaining = element.header.body_size.unwrap_or(usize::MAX);
+
+    let mut count = 0;
+    for next_child in &elements[index + 1..] {
+        if size_remaining == 0 || !next_child.header.id.can_be_children_of(&element.header.id) {
+            break;
+        }
+        size_remaining -= if let Body::Master = next_child.body {
+            // Master elements' body size should not count in the recursion
+            // as the children would duplicate the size count, so
+            // we only consider the header size on the calculation.
+            next_child.header.header_size
+        } else {
+            next_child
+                .header
+                .size
+                .expect("Only Master elements can have unknown size")
+        };
+        count += 1;
+    }
+    count
+}
+
+// Take the next `count` elements (including nested ones) out of `elements`
+// as trees, given the number of children of each element
+fn assemble_trees(
+    elements: &mut impl Iterator<Item = (Element, usize)>,
+    count: usize,
+) -> Vec<ElementTree> {
+    let mut trees = Vec::new();
+    let mut remaining = count;
+    while remaining > 0 {
+        let Some((element, children_count)) = elements.next() else {
+            break;
+        };
+        remaining -= 1;
+        trees.push(match element.body {
+            Body::Master => {
+                remaining -= children_count;
+                ElementTree::Master(MasterElement {
+                    header: element.header,
+                    children: assemble_trees(elements, children_count),
+                })
+            }
+            _ => ElementTree::Normal(element),
+        });
+    }
     trees
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Unsigned;
+    use crate::{Binary, Unsigned};
 
     use super::*;
 
@@ -161,5 +629,185 @@ mod tests {
         })];
 
         assert_eq!(build_element_trees(&elements), expected);
+        assert_eq!(build_element_trees_owned(elements.to_vec()), expected);
+
+        let tree_refs = build_element_tree_refs(&elements);
+        assert_eq!(tree_refs.len(), 1);
+        assert_eq!(tree_refs[0].children().count(), 7);
+        assert_eq!(tree_refs[0].to_element_tree(), expected[0]);
+        assert_eq!(
+            serde_yaml::to_string(&tree_refs).unwrap(),
+            serde_yaml::to_string(&expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_navigate_element_trees() {
+        let elements = [
+            Element {
+                header: Header::new(Id::Segment, 5, 19),
+                body: Body::Master,
+            },
+            Element {
+                header: Header::new(Id::Info, 4, 11),
+                body: Body::Master,
+            },
+            Element {
+                header: Header::new(Id::TimestampScale, 4, 3),
+                body: Body::Unsigned(Unsigned::Standard(1_000_000)),
+            },
+            Element {
+                header: Header::new(Id::MuxingApp, 3, 1),
+                body: Body::Utf8("a".to_string()),
+            },
+            Element {
+                header: Header::new(Id::Void, 2, 0),
+                body: Body::Binary(Binary::Standard("[]".to_string())),
+            },
+            Element {
+                header: Header::new(Id::Void, 2, 0),
+                body: Body::Binary(Binary::Standard("[]".to_string())),
+            },
+        ];
+        let trees = build_element_trees(&elements);
+        let segment = &trees[0];
+
+        assert_eq!(segment.children().len(), 3);
+        assert_eq!(
+            segment
+                .descendants()
+                .map(|tree| tree.header().id.clone())
+                .collect::<Vec<_>>(),
+            [
+                Id::Info,
+                Id::TimestampScale,
+                Id::MuxingApp,
+                Id::Void,
+                Id::Void
+            ]
+        );
+        let info = segment.find(&Id::Info).unwrap();
+        assert_eq!(info.header().body_size, Some(11));
+        assert_eq!(info.as_unsigned(), None);
+        assert_eq!(
+            segment.find(&Id::TimestampScale).unwrap().as_unsigned(),
+            Some(1_000_000)
+        );
+        assert_eq!(segment.find(&Id::MuxingApp).unwrap().as_string(), Some("a"));
+        assert_eq!(segment.find_all(&Id::Void).count(), 2);
+        assert!(segment.find(&Id::Cluster).is_none());
+    }
+
+    #[test]
+    fn test_compute_unknown_sizes() {
+        let element = |header: Header, position, body| Element {
+            header: Header {
+                position: Some(position),
+                ..header
+            },
+            body,
+        };
+        let mut elements = [
+            element(Header::with_unknown_size(Id::Segment, 12), 0, Body::Master),
+            element(Header::with_unknown_size(Id::Cluster, 12), 12, Body::Master),
+            element(
+                Header::new(Id::Timestamp, 2, 1),
+                24,
+                Body::Unsigned(Unsigned::Standard(0)),
+            ),
+            element(Header::with_unknown_size(Id::Cluster, 12), 27, Body::Master),
+            element(Header::new(Id::Cues, 5, 0), 39, Body::Master),
+        ];
+        compute_unknown_sizes(&mut elements, 100);
+
+        let computed_sizes = elements
+            .iter()
+            .map(|element| element.header.computed_size)
+            .collect::<Vec<_>>();
+        assert_eq!(computed_sizes, [Some(100), Some(15), None, Some(12), None]);
+    }
+
+    #[test]
+    fn test_insert_implied_elements() {
+        let mut trees = vec![ElementTree::Master(MasterElement {
+            header: Header::new(Id::Info, 4, 0),
+            children: vec![],
+        })];
+        insert_implied_elements(&mut trees);
+
+        let ElementTree::Master(info) = &trees[0] else {
+            panic!("expected a Master element");
+        };
+        assert_eq!(
+            info.children,
+            [ElementTree::Normal(Element {
+                header: Header {
+                    implied: true,
+                    ..Header::new(Id::TimestampScale, 0, 0)
+                },
+                body: Body::Unsigned(Unsigned::Standard(1_000_000)),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_filter_elements() {
+        let element = |id, header_size, body_size, body| Element {
+            header: Header::new(id, header_size, body_size),
+            body,
+        };
+        let unsigned = |value| Body::Unsigned(Unsigned::Standard(value));
+        let elements = vec![
+            element(Id::Ebml, 5, 4, Body::Master),
+            element(Id::EbmlVersion, 3, 1, unsigned(1)),
+            element(Id::Segment, 12, 22, Body::Master),
+            element(Id::Info, 5, 7, Body::Master),
+            element(Id::TimestampScale, 4, 3, unsigned(1_000_000)),
+            element(Id::Tracks, 5, 5, Body::Master),
+            element(Id::TrackEntry, 2, 3, Body::Master),
+            element(Id::TrackNumber, 2, 1, unsigned(1)),
+        ];
+        let filtered = |include: &[Id], exclude: &[Id]| {
+            let mut filtered = elements.clone();
+            filter_elements(&mut filtered, include, exclude);
+            let mut trees = build_element_trees(&elements);
+            filter_element_trees(&mut trees, include, exclude);
+            assert_eq!(trees, build_element_trees(&filtered));
+            filtered
+                .into_iter()
+                .map(|element| element.header.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            filtered(&[Id::Tracks], &[]),
+            [Id::Segment, Id::Tracks, Id::TrackEntry, Id::TrackNumber]
+        );
+        assert_eq!(
+            filtered(&[Id::Tracks, Id::Ebml], &[Id::TrackNumber]),
+            [
+                Id::Ebml,
+                Id::EbmlVersion,
+                Id::Segment,
+                Id::Tracks,
+                Id::TrackEntry
+            ]
+        );
+        assert_eq!(
+            filtered(&[], &[Id::Ebml, Id::Info]),
+            [Id::Segment, Id::Tracks, Id::TrackEntry, Id::TrackNumber]
+        );
+        assert_eq!(filtered(&[Id::Cues], &[]), []);
+
+        let mut trees = build_element_trees(&elements);
+        truncate_element_trees(&mut trees, 1);
+        assert_eq!(
+            trees[1]
+                .descendants()
+                .map(|tree| tree.header().id.clone())
+                .collect::<Vec<_>>(),
+            [Id::Info, Id::Tracks]
+        );
+        assert_eq!(trees[1].children()[1].children(), []);
     }
 }
@@ -0,0 +1,327 @@
+//! Validation of element trees against the EBML Schema
+//!
+//! The constraints checked here come from the schemas the element
+//! definitions are generated from: mandatory children, maximum number of
+//! occurrences and ranges of numeric values. UIDs of tracks, editions,
+//! chapters and attachments are also checked to be unique, as they are
+//! meant to identify them.
+use serde::Serialize;
+
+use crate::{
+    elements::Id,
+    tree::{schema_children, ElementTree},
+    Body, Element, Header,
+};
+
+/// A violation of the EBML Schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    /// A mandatory element without a default value is missing
+    MissingElement {
+        /// The missing element
+        id: Id,
+        /// The Master element it should be in
+        parent: Id,
+        /// Position of the parent in the input
+        parent_position: Option<usize>,
+    },
+    /// An element occurs more times than allowed in its parent
+    TooManyOccurrences {
+        /// The repeated element
+        id: Id,
+        /// The Master element it is in
+        parent: Id,
+        /// Position of the parent in the input
+        parent_position: Option<usize>,
+        /// Number of occurrences
+        occurrences: usize,
+        /// Maximum number of occurrences allowed
+        max_occurs: usize,
+    },
+    /// A numeric value is out of the allowed range
+    OutOfRange {
+        /// The element with the invalid value
+        id: Id,
+        /// Position of the element in the input
+        position: Option<usize>,
+        /// The allowed range, as written in the schema
+        range: &'static str,
+    },
+    /// A UID is used by several tracks, editions, chapters or attachments
+    DuplicateUid {
+        /// The UID element, such as TrackUID
+        id: Id,
+        /// The duplicated value
+        uid: u64,
+        /// Position of the duplicate in the input
+        position: Option<usize>,
+        /// Position of the first element with this UID in the input
+        first_position: Option<usize>,
+    },
+}
+
+/// Validate element trees, returning all violations found
+pub fn validate(trees: &[ElementTree]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for tree in trees {
+        validate_tree(tree, &mut violations);
+        validate_uids(tree, &mut Vec::new(), &mut violations);
+    }
+    violations
+}
+
+// Check that UIDs are unique within a top-level element, given the UIDs
+// found so far and their positions
+fn validate_uids<'a>(
+    tree: &'a ElementTree,
+    uids: &mut Vec<(&'a Id, u64, Option<usize>)>,
+    violations: &mut Vec<Violation>,
+) {
+    let element = match tree {
+        ElementTree::Normal(element) => element,
+        ElementTree::Master(master) => {
+            for child in &master.children {
+                validate_uids(child, uids, violations);
+            }
+            return;
+        }
+    };
+    let (id, Body::Unsigned(uid)) = (&element.header.id, &element.body) else {
+        return;
+    };
+    if !matches!(
+        id,
+        Id::TrackUid | Id::EditionUid | Id::ChapterUid | Id::FileUid
+    ) {
+        return;
+    }
+    let uid = uid.value();
+    match uids
+        .iter()
+        .find(|(other, other_uid, _)| *other == id && *other_uid == uid)
+    {
+        Some((_, _, first_position)) => violations.push(Violation::DuplicateUid {
+            id: id.clone(),
+            uid,
+            position: element.header.position,
+            first_position: *first_position,
+        }),
+        None => uids.push((id, uid, element.header.position)),
+    }
+}
+
+fn validate_tree(tree: &ElementTree, violations: &mut Vec<Violation>) {
+    match tree {
+        ElementTree::Normal(element) => validate_range(element, violations),
+        ElementTree::Master(master) => {
+            validate_children(&master.header, &master.children, violations);
+            for child in &master.children {
+                validate_tree(child, violations);
+            }
+        }
+    }
+}
+
+fn validate_children(header: &Header, children: &[ElementTree], violations: &mut Vec<Violation>) {
+    let Some(schema) = header.id.schema() else {
+        return;
+    };
+
+    let child_ids = children
+        .iter()
+        .map(|child| match child {
+            ElementTree::Normal(element) => &element.header.id,
+            ElementTree::Master(master) => &master.header.id,
+        })
+        .collect::<Vec<_>>();
+
+    for (id, child_schema) in schema_children(schema) {
+        let occurrences = child_ids.iter().filter(|child_id| **child_id == id).count();
+        if occurrences == 0 && child_schema.min_occurs > 0 && child_schema.default.is_none() {
+            violations.push(Violation::MissingElement {
+                id: id.clone(),
+                parent: header.id.clone(),
+                parent_position: header.position,
+            });
+        }
+        if let Some(max_occurs) = child_schema.max_occurs.filter(|max| occurrences > *max) {
+            violations.push(Violation::TooManyOccurrences {
+                id: id.clone(),
+                parent: header.id.clone(),
+                parent_position: header.position,
+                occurrences,
+                max_occurs,
+            });
+        }
+    }
+}
+
+fn validate_range(element: &Element, violations: &mut Vec<Violation>) {
+    if let Some(range) = out_of_range(element) {
+        violations.push(Violation::OutOfRange {
+            id: element.header.id.clone(),
+            position: element.header.position,
+            range,
+        });
+    }
+}
+
+// The range from the schema that the value of an element is out of, if any
+pub(crate) fn out_of_range(element: &Element) -> Option<&'static str> {
+    let range = element.header.id.value_range()?;
+    let value = match &element.body {
+        Body::Unsigned(value) => value.value() as f64,
+        Body::Signed(value) => *value as f64,
+        Body::Float(value) => *value,
+        _ => return None,
+    };
+    (!in_range(range, value)).then_some(range)
+}
+
+// Parse a decimal or hexadecimal floating point number, such as 0x1.f4p+12
+pub(crate) fn parse_number(number: &str) -> Option<f64> {
+    let number = number.trim();
+    let (sign, number) = match number.strip_prefix('-') {
+        Some(number) => (-1.0, number),
+        None => (1.0, number),
+    };
+    let Some(hex) = number.strip_prefix("0x") else {
+        return number.parse::<f64>().ok().map(|number| sign * number);
+    };
+
+    let (mantissa, exponent) = hex.split_once('p')?;
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mut value = u64::from_str_radix(integer, 16).ok()? as f64;
+    for (index, digit) in fraction.chars().enumerate() {
+        value += digit.to_digit(16)? as f64 / 16f64.powi(index as i32 + 1);
+    }
+    Some(sign * value * 2f64.powi(exponent.parse().ok()?))
+}
+
+// Whether a value is within a range from the schema, such as "not 0", "1-8"
+// or ">= -0xB4p+0, <= 0xB4p+0". Unsupported range syntaxes always match.
+fn in_range(range: &str, value: f64) -> bool {
+    range.split(',').all(|constraint| {
+        let constraint = constraint.trim();
+        let matches = if let Some(number) = constraint.strip_prefix("not ") {
+            parse_number(number).map(|number| value != number)
+        } else if let Some(number) = constraint.strip_prefix(">=") {
+            parse_number(number).map(|number| value >= number)
+        } else if let Some(number) = constraint.strip_prefix('>') {
+            parse_number(number).map(|number| value > number)
+        } else if let Some(number) = constraint.strip_prefix("<=") {
+            parse_number(number).map(|number| value <= number)
+        } else if let Some(number) = constraint.strip_prefix('<') {
+            parse_number(number).map(|number| value < number)
+        } else if let Some((min, max)) = constraint
+            .split_once('-')
+            .filter(|(min, _)| !min.is_empty())
+        {
+            parse_number(min)
+                .zip(parse_number(max))
+                .map(|(min, max)| value >= min && value <= max)
+        } else {
+            parse_number(constraint).map(|number| value == number)
+        };
+        matches.unwrap_or(true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tree::build_element_trees, Unsigned};
+
+    #[test]
+    fn test_in_range() {
+        assert_eq!(parse_number("0x1.f4p+12"), Some(8000.0));
+        assert!(in_range("not 0", 1.0));
+        assert!(!in_range("not 0", 0.0));
+        assert!(in_range("1-8", 8.0));
+        assert!(!in_range("1-8", 9.0));
+        assert!(in_range(">= -0xB4p+0, <= 0xB4p+0", -180.0));
+        assert!(!in_range(">= -0xB4p+0, <= 0xB4p+0", 180.5));
+        assert!(!in_range("> 0x0p+0", 0.0));
+    }
+
+    #[test]
+    fn test_validate() {
+        let elements = [
+            Element {
+                header: Header::new(Id::Ebml, 5, 12),
+                body: Body::Master,
+            },
+            Element {
+                header: Header::new(Id::EbmlMaxSizeLength, 3, 1),
+                body: Body::Unsigned(Unsigned::Standard(0)),
+            },
+            Element {
+                header: Header::new(Id::DocTypeVersion, 3, 1),
+                body: Body::Unsigned(Unsigned::Standard(4)),
+            },
+            Element {
+                header: Header::new(Id::DocTypeVersion, 3, 1),
+                body: Body::Unsigned(Unsigned::Standard(4)),
+            },
+        ];
+        let violations = validate(&build_element_trees(&elements));
+        assert_eq!(
+            violations,
+            [
+                Violation::MissingElement {
+                    id: Id::DocType,
+                    parent: Id::Ebml,
+                    parent_position: None,
+                },
+                Violation::TooManyOccurrences {
+                    id: Id::DocTypeVersion,
+                    parent: Id::Ebml,
+                    parent_position: None,
+                    occurrences: 2,
+                    max_occurs: 1,
+                },
+                Violation::OutOfRange {
+                    id: Id::EbmlMaxSizeLength,
+                    position: None,
+                    range: "not 0",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_uids() {
+        let track = |position| {
+            [
+                Element {
+                    header: Header::new(Id::TrackEntry, 2, 4),
+                    body: Body::Master,
+                },
+                Element {
+                    header: Header {
+                        position: Some(position),
+                        ..Header::new(Id::TrackUid, 3, 1)
+                    },
+                    body: Body::Unsigned(Unsigned::Standard(5)),
+                },
+            ]
+        };
+        let mut elements = vec![Element {
+            header: Header::new(Id::Tracks, 5, 12),
+            body: Body::Master,
+        }];
+        elements.extend(track(7));
+        elements.extend(track(13));
+        let violations = validate(&build_element_trees(&elements));
+        assert_eq!(
+            violations.last(),
+            Some(&Violation::DuplicateUid {
+                id: Id::TrackUid,
+                uid: 5,
+                position: Some(13),
+                first_position: Some(7),
+            })
+        );
+    }
+}
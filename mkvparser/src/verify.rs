@@ -0,0 +1,1078 @@
+//! Verification of the structure of a file, reporting every problem found
+//! so that automated checks can reject broken files
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use crate::{
+    blocks::{blocks, clusters},
+    cues::cue_table,
+    diagnostics::{Diagnostic, Diagnostics},
+    elements::Id,
+    enumerations::TrackType,
+    format_time,
+    model::Document,
+    tree::build_element_trees,
+    validate::{validate, Violation},
+    Binary, Body, Element, Header,
+};
+
+/// How serious a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The file can be used, but may cause issues in some players
+    Warning,
+    /// The file is broken
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Kind of a problem found by [`verify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// A region of invalid data skipped to resynchronize
+    Corrupted,
+    /// An element cut short by the end of the file
+    Truncated,
+    /// A mandatory element without a default value is missing
+    MissingElement,
+    /// An element occurs more times than allowed in its parent
+    TooManyOccurrences,
+    /// A numeric value is out of the allowed range
+    OutOfRange,
+    /// A UID is used by several tracks, editions, chapters or attachments
+    DuplicateUid,
+    /// An element extends past the end of its parent
+    OverflowingElement,
+    /// An element is no longer part of the latest version of the DocType
+    DeprecatedElement,
+    /// A cue point points at a position where no Cluster starts
+    CueClusterNotFound,
+    /// A cue point points at a position where no block starts
+    CueBlockNotFound,
+    /// The block of a cue point doesn't have the time of the cue point
+    CueTimeMismatch,
+    /// The block of a cue point isn't a keyframe
+    CueNotKeyframe,
+    /// A SeekHead entry points at a position where no element starts
+    SeekPositionNotFound,
+    /// A SeekHead entry points at an element with another ID
+    SeekIdMismatch,
+    /// A Cluster has an earlier timestamp than the previous Cluster
+    ClusterTimestampRegression,
+    /// A block has an earlier timestamp than allowed by the previous blocks
+    /// of its track
+    BlockTimestampRegression,
+    /// A block of an audio or video track starts after the end of the
+    /// previous one
+    TimelineGap,
+    /// A block of an audio or video track starts before the end of the
+    /// previous one
+    TimelineOverlap,
+    /// The initialization segment doesn't follow the WebM Byte Stream
+    /// Format of Media Source Extensions
+    MseInitSegment,
+    /// A Cluster doesn't follow the WebM Byte Stream Format of Media Source
+    /// Extensions
+    MseMediaSegment,
+    /// An element other than a Segment or Cluster has an unknown size
+    MseUnknownSize,
+}
+
+/// Options of [`verify_with_options`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Gaps and overlaps between consecutive blocks of a track up to this
+    /// duration in nanoseconds are tolerated, such as rounding errors of
+    /// timestamps
+    pub gap_threshold_ns: i64,
+    /// Check the rules of the WebM Byte Stream Format of Media Source
+    /// Extensions, for files made of an initialization segment followed by
+    /// media segments, or of media segments only
+    pub mse: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            gap_threshold_ns: 1_000_000,
+            mse: false,
+        }
+    }
+}
+
+/// A problem found by [`verify`]
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    /// What kind of problem it is
+    pub kind: ProblemKind,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// Position of the offending element in the input, if known
+    pub position: Option<usize>,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+/// Problems found in a file, see [`verify`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    /// Number of problems with the [`Severity::Error`] severity
+    pub errors: usize,
+    /// Number of problems with the [`Severity::Warning`] severity
+    pub warnings: usize,
+    /// Problems sorted by position, with problems of unknown position last
+    pub problems: Vec<Problem>,
+}
+
+impl VerifyReport {
+    /// Whether no errors were found, warnings aside
+    pub fn is_valid(&self) -> bool {
+        self.errors == 0
+    }
+
+    /// Add a problem to the report
+    pub fn push(
+        &mut self,
+        kind: ProblemKind,
+        severity: Severity,
+        position: Option<usize>,
+        message: String,
+    ) {
+        match severity {
+            Severity::Warning => self.warnings += 1,
+            Severity::Error => self.errors += 1,
+        }
+        self.problems.push(Problem {
+            kind,
+            severity,
+            position,
+            message,
+        });
+    }
+
+    fn sort(&mut self) {
+        self.problems
+            .sort_by_key(|problem| (problem.position.is_none(), problem.position));
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{}: {}", problem.severity, problem.message)?;
+        }
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} error{}, {} warning{}",
+            self.errors,
+            plural(self.errors),
+            self.warnings,
+            plural(self.warnings)
+        )
+    }
+}
+
+// Suffix locating an element in messages, such as ` at offset 0x2a`
+fn at(position: Option<usize>) -> String {
+    position.map_or(String::new(), |position| {
+        format!(" at offset {position:#x}")
+    })
+}
+
+fn push_violation(report: &mut VerifyReport, violation: Violation) {
+    match violation {
+        Violation::MissingElement {
+            id,
+            parent,
+            parent_position,
+        } => report.push(
+            ProblemKind::MissingElement,
+            Severity::Error,
+            parent_position,
+            format!(
+                "missing mandatory element {id} in {parent}{}",
+                at(parent_position)
+            ),
+        ),
+        Violation::TooManyOccurrences {
+            id,
+            parent,
+            parent_position,
+            occurrences,
+            max_occurs,
+        } => report.push(
+            ProblemKind::TooManyOccurrences,
+            Severity::Error,
+            parent_position,
+            format!(
+                "{id} occurs {occurrences} times in {parent}{}, at most {max_occurs} allowed",
+                at(parent_position)
+            ),
+        ),
+        Violation::OutOfRange {
+            id,
+            position,
+            range,
+        } => report.push(
+            ProblemKind::OutOfRange,
+            Severity::Warning,
+            position,
+            format!(
+                "{id}{} is out of the allowed range \"{range}\"",
+                at(position)
+            ),
+        ),
+        Violation::DuplicateUid {
+            id,
+            uid,
+            position,
+            first_position,
+        } => report.push(
+            ProblemKind::DuplicateUid,
+            Severity::Error,
+            position,
+            format!(
+                "{id} {uid}{} is already used{}",
+                at(position),
+                at(first_position)
+            ),
+        ),
+    }
+}
+
+// Check that cue points point at Cluster starts, and at keyframes of their
+// track with the time of the cue point
+fn verify_cues(elements: &[Element], report: &mut VerifyReport) {
+    let cues = cue_table(elements);
+    if cues.entries.is_empty() {
+        return;
+    }
+    let blocks = blocks(elements.iter().map(Ok))
+        .filter_map(|block| {
+            let block = block.ok()?;
+            Some((block.position?, block))
+        })
+        .collect::<BTreeMap<_, _>>();
+    // Cue points may point at the BlockGroup of a Block rather than at the
+    // Block itself
+    let mut group_blocks = BTreeMap::new();
+    let mut clusters = BTreeSet::new();
+    let mut group_position = None;
+    for element in elements {
+        let position = element.header.position;
+        match element.header.id {
+            Id::Cluster => clusters.extend(position),
+            Id::BlockGroup => group_position = position,
+            Id::Block => {
+                if let (Some(group_position), Some(position)) = (group_position.take(), position) {
+                    group_blocks.insert(group_position, position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for entry in &cues.entries {
+        let Some(cluster_position) = entry.cluster_position.map(|position| position as usize)
+        else {
+            continue;
+        };
+        let cue = format!(
+            "cue point at {} of track {}",
+            entry.time,
+            entry
+                .track
+                .map_or("?".to_string(), |track| track.to_string())
+        );
+        if !entry.cluster_found {
+            report.push(
+                ProblemKind::CueClusterNotFound,
+                Severity::Error,
+                Some(cluster_position),
+                format!("{cue} points at offset {cluster_position:#x}, where no Cluster starts"),
+            );
+            continue;
+        }
+        let (block_position, block) = match entry.block_position {
+            Some(position) => {
+                let position = position as usize;
+                let block_position = group_blocks.get(&position).copied().unwrap_or(position);
+                let Some(block) = blocks.get(&block_position) else {
+                    report.push(
+                        ProblemKind::CueBlockNotFound,
+                        Severity::Error,
+                        Some(position),
+                        format!("{cue} points at offset {position:#x}, where no block starts"),
+                    );
+                    continue;
+                };
+                (block_position, block)
+            }
+            None => {
+                let end = clusters
+                    .range(cluster_position + 1..)
+                    .next()
+                    .copied()
+                    .unwrap_or(usize::MAX);
+                let block = blocks.range(cluster_position..end).find(|(_, block)| {
+                    entry.track == Some(block.track_number as u64)
+                        && block.time_ns == Some(entry.time_ns)
+                });
+                let Some((block_position, block)) = block else {
+                    report.push(
+                        ProblemKind::CueTimeMismatch,
+                        Severity::Error,
+                        Some(cluster_position),
+                        format!(
+                            "{cue} has no block of its track at its time in the Cluster at offset {cluster_position:#x}"
+                        ),
+                    );
+                    continue;
+                };
+                (*block_position, block)
+            }
+        };
+        if block.time_ns != Some(entry.time_ns) {
+            let time = block
+                .time_ns
+                .map_or("an unknown time".to_string(), format_time);
+            report.push(
+                ProblemKind::CueTimeMismatch,
+                Severity::Error,
+                Some(block_position),
+                format!("{cue} points at a block at offset {block_position:#x} with time {time}"),
+            );
+        }
+        if !block.keyframe {
+            report.push(
+                ProblemKind::CueNotKeyframe,
+                Severity::Warning,
+                Some(block_position),
+                format!(
+                    "{cue} points at a block at offset {block_position:#x} that is not a keyframe"
+                ),
+            );
+        }
+    }
+}
+
+// Check that SeekHead entries point at elements with their SeekID
+fn verify_seek_head(elements: &[Element], report: &mut VerifyReport) {
+    let seek_head = Document::from_elements(elements).seek_head;
+    if seek_head.is_empty() {
+        return;
+    }
+    let ids = elements
+        .iter()
+        .filter_map(|element| Some((element.header.position?, &element.header.id)))
+        .collect::<BTreeMap<_, _>>();
+    for entry in seek_head {
+        let Some(position) = entry.absolute_position.map(|position| position as usize) else {
+            continue;
+        };
+        match ids.get(&position) {
+            Some(id) if **id == entry.id => {}
+            Some(id) => report.push(
+                ProblemKind::SeekIdMismatch,
+                Severity::Error,
+                Some(position),
+                format!(
+                    "SeekHead entry of {} points at offset {position:#x}, where {id} starts",
+                    entry.id
+                ),
+            ),
+            None => report.push(
+                ProblemKind::SeekPositionNotFound,
+                Severity::Error,
+                Some(position),
+                format!(
+                    "SeekHead entry of {} points at offset {position:#x}, where no element starts",
+                    entry.id
+                ),
+            ),
+        }
+    }
+}
+
+// Whether blocks of a codec may be stored out of presentation order, as
+// with the B-frames of MPEG codecs
+fn may_reorder(codec_id: &str) -> bool {
+    codec_id.starts_with("V_MPEG") || codec_id == "V_MS/VFW/FOURCC"
+}
+
+// A time and the position of the element it comes from
+type Timed = (i64, usize);
+
+// Timestamps seen so far in a track
+#[derive(Default)]
+struct TrackTimes {
+    last: Option<Timed>,
+    max: Option<Timed>,
+    // Latest timestamp of the blocks before the last keyframe
+    before_keyframe: Option<Timed>,
+}
+
+// Check that Cluster timestamps and the block timestamps of each track don't
+// go back in time. Blocks of codecs with B-frames only have to be later than
+// the blocks before the last keyframe, and keyframes later than all the
+// blocks before them.
+fn verify_timestamps(elements: &[Element], report: &mut VerifyReport) {
+    let mut previous: Option<Timed> = None;
+    for cluster in clusters(elements.iter().map(Ok)).filter_map(|cluster| cluster.ok()) {
+        let (Some(time), Some(position)) = (cluster.time_ns, cluster.position) else {
+            continue;
+        };
+        if let Some((previous_time, previous_position)) = previous {
+            if time < previous_time {
+                report.push(
+                    ProblemKind::ClusterTimestampRegression,
+                    Severity::Error,
+                    Some(position),
+                    format!(
+                        "Cluster at offset {position:#x} has timestamp {}, {} before the Cluster at offset {previous_position:#x}",
+                        format_time(time),
+                        format_time(previous_time - time)
+                    ),
+                );
+            }
+        }
+        previous = Some((time, position));
+    }
+
+    let reordered = Document::from_elements(elements)
+        .tracks
+        .iter()
+        .filter(|track| track.codec_id.as_deref().is_some_and(may_reorder))
+        .filter_map(|track| track.track_number)
+        .collect::<BTreeSet<_>>();
+    let mut tracks = BTreeMap::<usize, TrackTimes>::new();
+    for block in blocks(elements.iter().map(Ok)).filter_map(|block| block.ok()) {
+        let (Some(time), Some(position)) = (block.time_ns, block.position) else {
+            continue;
+        };
+        let times = tracks.entry(block.track_number).or_default();
+        let bound = match (
+            reordered.contains(&(block.track_number as u64)),
+            block.keyframe,
+        ) {
+            (false, _) => times.last,
+            (true, true) => times.max,
+            (true, false) => times.before_keyframe,
+        };
+        if let Some((bound_time, bound_position)) = bound {
+            if time < bound_time {
+                report.push(
+                    ProblemKind::BlockTimestampRegression,
+                    Severity::Error,
+                    Some(position),
+                    format!(
+                        "block of track {} at offset {position:#x} has timestamp {}, {} before the block at offset {bound_position:#x}",
+                        block.track_number,
+                        format_time(time),
+                        format_time(bound_time - time)
+                    ),
+                );
+            }
+        }
+        if block.keyframe {
+            times.before_keyframe = times.max;
+        }
+        times.last = Some((time, position));
+        if times.max.is_none_or(|(max_time, _)| time > max_time) {
+            times.max = Some((time, position));
+        }
+    }
+}
+
+// Check that the blocks of each audio and video track follow each other
+// without gaps nor overlaps, in presentation order. Blocks last for their
+// BlockDuration, or for the DefaultDuration of their track per frame.
+fn verify_timelines(elements: &[Element], gap_threshold_ns: i64, report: &mut VerifyReport) {
+    let document = Document::from_elements(elements);
+    let timestamp_scale = document.timestamp_scale();
+    let default_durations = document
+        .tracks
+        .iter()
+        .filter(|track| {
+            matches!(
+                track.track_type,
+                Some(TrackType::Video) | Some(TrackType::Audio)
+            )
+        })
+        .filter_map(|track| Some((track.track_number? as usize, track.default_duration)))
+        .collect::<BTreeMap<_, _>>();
+    if default_durations.is_empty() {
+        return;
+    }
+
+    // BlockDurations by position of the Block of their BlockGroup
+    let mut block_durations = BTreeMap::new();
+    let mut group = (None, None);
+    for element in elements {
+        match (&element.header.id, &element.body) {
+            (Id::BlockGroup, _) => group = (None, None),
+            (Id::Block, _) => group.0 = element.header.position,
+            (Id::BlockDuration, Body::Unsigned(duration)) => group.1 = Some(duration.value()),
+            _ => continue,
+        }
+        if let (Some(position), Some(duration)) = group {
+            block_durations.insert(position, duration);
+        }
+    }
+
+    // Start, end and position of the blocks of each track
+    let mut timelines = BTreeMap::<usize, Vec<(i64, i64, usize)>>::new();
+    for block in blocks(elements.iter().map(Ok)).filter_map(|block| block.ok()) {
+        let (Some(time), Some(position)) = (block.time_ns, block.position) else {
+            continue;
+        };
+        let Some(default_duration) = default_durations.get(&block.track_number) else {
+            continue;
+        };
+        let frames = block.frame_sizes.len().max(1) as u64;
+        let duration = match block_durations.get(&position) {
+            Some(duration) => duration.saturating_mul(timestamp_scale),
+            None => match default_duration {
+                Some(default_duration) => default_duration.saturating_mul(frames),
+                None => continue,
+            },
+        };
+        let end = time.saturating_add(duration.min(i64::MAX as u64) as i64);
+        timelines
+            .entry(block.track_number)
+            .or_default()
+            .push((time, end, position));
+    }
+
+    for (track_number, mut timeline) in timelines {
+        timeline.sort_by_key(|(time, _, _)| *time);
+        for pair in timeline.windows(2) {
+            let [(_, end, previous_position), (time, _, position)] = pair else {
+                unreachable!()
+            };
+            let (kind, what, delta) = if time > end {
+                (ProblemKind::TimelineGap, "gap", time - end)
+            } else {
+                (ProblemKind::TimelineOverlap, "overlap", end - time)
+            };
+            if delta > gap_threshold_ns {
+                report.push(
+                    kind,
+                    Severity::Warning,
+                    Some(*position),
+                    format!(
+                        "{what} of {} in track {track_number} between the blocks at offsets {previous_position:#x} and {position:#x}",
+                        format_time(delta)
+                    ),
+                );
+            }
+        }
+    }
+}
+
+// Check the initialization segment of a WebM byte stream: an EBML header
+// with the webm DocType, followed by a Segment with Info and then Tracks
+// before the first Cluster
+fn verify_mse_init_segment(elements: &[Element], report: &mut VerifyReport) {
+    let mut push = |position, message: String| {
+        report.push(
+            ProblemKind::MseInitSegment,
+            Severity::Error,
+            position,
+            message,
+        )
+    };
+    let header_of = |id: Id| {
+        elements
+            .iter()
+            .find(|element| element.header.id == id)
+            .map(|element| &element.header)
+    };
+
+    if elements.first().map(|element| &element.header.id) != Some(&Id::Ebml) {
+        push(
+            Some(0),
+            "the stream doesn't start with an EBML header".to_string(),
+        );
+    }
+    let doc_type = elements
+        .iter()
+        .find_map(|element| match (&element.header.id, &element.body) {
+            (Id::DocType, Body::String(doc_type)) => Some(doc_type.as_str()),
+            _ => None,
+        });
+    if let Some(doc_type) = doc_type.filter(|doc_type| *doc_type != "webm") {
+        push(None, format!("the DocType is {doc_type} rather than webm"));
+    }
+    let Some(segment) = header_of(Id::Segment) else {
+        push(None, "the stream has no Segment".to_string());
+        return;
+    };
+    let in_ebml_header = |id: &Id| {
+        *id == Id::Ebml
+            || id.schema().is_some_and(|schema| {
+                schema.path.starts_with("\\EBML\\") || schema.path.starts_with("\\(")
+            })
+    };
+    let mut before_segment = elements
+        .iter()
+        .take_while(|element| element.header.id != Id::Segment);
+    if let Some(element) = before_segment.find(|element| !in_ebml_header(&element.header.id)) {
+        push(
+            element.header.position,
+            format!(
+                "{}{} comes between the EBML header and the Segment",
+                element.header.id,
+                at(element.header.position)
+            ),
+        );
+    }
+
+    // The initialization segment ends at the first Cluster or EBML header
+    // past the Segment
+    let end = elements
+        .iter()
+        .filter(|element| matches!(element.header.id, Id::Cluster | Id::Ebml))
+        .filter_map(|element| element.header.position)
+        .find(|position| Some(*position) > segment.position)
+        .unwrap_or(usize::MAX);
+    let info = header_of(Id::Info).filter(|info| info.position.is_some_and(|p| p < end));
+    let tracks = header_of(Id::Tracks).filter(|tracks| tracks.position.is_some_and(|p| p < end));
+    let (Some(info), Some(tracks)) = (info, tracks) else {
+        for (header, id) in [(info, Id::Info), (tracks, Id::Tracks)] {
+            if header.is_none() {
+                push(None, format!("{id} is missing before the first Cluster"));
+            }
+        }
+        return;
+    };
+    if info.position > tracks.position {
+        push(
+            info.position,
+            format!(
+                "Info{} comes after Tracks{}",
+                at(info.position),
+                at(tracks.position)
+            ),
+        );
+    }
+    let end_of = |header: &Header| {
+        header
+            .position
+            .zip(header.size)
+            .map(|(position, size)| position + size)
+    };
+    if let (Some(segment_end), Some(tracks_end)) = (end_of(segment), end_of(tracks)) {
+        if segment_end < tracks_end {
+            push(
+                segment.position,
+                format!(
+                    "Segment{} ends before the end of Tracks{}",
+                    at(segment.position),
+                    at(tracks.position)
+                ),
+            );
+        }
+    }
+}
+
+// Check the media segments of a WebM byte stream: Clusters with a Timestamp
+// before their blocks, blocks in increasing time order, and at least one
+// block of each audio and video track
+fn verify_mse_media_segments(elements: &[Element], report: &mut VerifyReport) {
+    let mut push = |position, message: String| {
+        report.push(
+            ProblemKind::MseMediaSegment,
+            Severity::Error,
+            position,
+            message,
+        )
+    };
+    let tracks = Document::from_elements(elements)
+        .tracks
+        .iter()
+        .filter(|track| {
+            matches!(
+                track.track_type,
+                Some(TrackType::Video) | Some(TrackType::Audio)
+            )
+        })
+        .filter_map(|track| track.track_number.map(|number| number as usize))
+        .collect::<BTreeSet<_>>();
+    for cluster in clusters(elements.iter().map(Ok)).filter_map(|cluster| cluster.ok()) {
+        let position = cluster.position;
+        if cluster.block_count == 0 {
+            push(position, format!("Cluster{} has no blocks", at(position)));
+            continue;
+        }
+        for track in tracks.difference(&cluster.track_numbers.into_iter().collect()) {
+            push(
+                position,
+                format!("Cluster{} has no blocks of track {track}", at(position)),
+            );
+        }
+    }
+
+    // Cluster being read, whether its Timestamp was found, and the time of
+    // its last block
+    let mut cluster = None;
+    let mut has_timestamp = false;
+    let mut last_time = None;
+    for element in elements {
+        let position = element.header.position;
+        match (&element.header.id, &element.body) {
+            (Id::Cluster, _) => {
+                cluster = Some(position);
+                has_timestamp = false;
+                last_time = None;
+            }
+            (Id::Timestamp, _) => has_timestamp = true,
+            (Id::SimpleBlock | Id::Block, Body::Binary(binary)) => {
+                if !has_timestamp {
+                    push(
+                        position,
+                        format!(
+                            "Cluster{} has a block{} before its Timestamp",
+                            at(cluster.flatten()),
+                            at(position)
+                        ),
+                    );
+                    // Only reported once per Cluster
+                    has_timestamp = true;
+                }
+                let time = binary.absolute_time_ns();
+                if time.is_some() && time < last_time {
+                    push(
+                        position,
+                        format!(
+                            "block{} is earlier than the previous block of its Cluster",
+                            at(position)
+                        ),
+                    );
+                }
+                last_time = time.or(last_time);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Check the WebM Byte Stream Format of Media Source Extensions. Streams
+// starting with a Cluster are taken as media segments only.
+fn verify_mse(elements: &[Element], report: &mut VerifyReport) {
+    let media_only = elements.first().map(|element| &element.header.id) == Some(&Id::Cluster);
+    if !media_only {
+        verify_mse_init_segment(elements, report);
+    }
+    verify_mse_media_segments(elements, report);
+    for element in elements {
+        let header = &element.header;
+        if header.body_size.is_none() && !matches!(header.id, Id::Segment | Id::Cluster) {
+            report.push(
+                ProblemKind::MseUnknownSize,
+                Severity::Error,
+                header.position,
+                format!(
+                    "{}{} has an unknown size, only allowed for Segment and Cluster",
+                    header.id,
+                    at(header.position)
+                ),
+            );
+        }
+    }
+}
+
+/// Verify the structure of parsed elements: corrupted regions, truncation,
+/// mandatory elements, occurrence counts, value ranges, element sizes
+/// overflowing their parents and deprecated elements. Cue points are
+/// checked to point at Cluster starts and at keyframes with their time, and
+/// SeekHead entries to point at elements with their SeekID. Timestamps of
+/// Clusters and of the blocks of each track must not go back in time, and
+/// the blocks of audio and video tracks must follow each other without gaps
+/// nor overlaps.
+///
+/// Elements must be in the order they were parsed, with their positions set,
+/// and parsed with [`ParserOptions::recover_corruption`](crate::ParserOptions)
+/// for corrupted regions to be reported rather than failing parsing.
+pub fn verify(elements: &[Element]) -> VerifyReport {
+    verify_with_options(elements, &VerifyOptions::default())
+}
+
+/// Verify the structure of parsed elements with custom options, see
+/// [`verify`]. With [`VerifyOptions::mse`], the rules of the WebM Byte
+/// Stream Format of Media Source Extensions are checked as well.
+pub fn verify_with_options(elements: &[Element], options: &VerifyOptions) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    let mut diagnostics = Diagnostics::new();
+    for element in elements {
+        let position = element.header.position;
+        match &element.body {
+            Body::Binary(Binary::Corrupted) => report.push(
+                ProblemKind::Corrupted,
+                Severity::Error,
+                position,
+                format!(
+                    "{} bytes of corrupted data{}",
+                    element.header.body_size.unwrap_or_default(),
+                    at(position)
+                ),
+            ),
+            Body::Binary(Binary::Truncated(truncated)) => report.push(
+                ProblemKind::Truncated,
+                Severity::Error,
+                position,
+                match truncated.declared_size {
+                    Some(declared_size) => format!(
+                        "{}{} is truncated, {} of {declared_size} bytes present",
+                        truncated.id,
+                        at(position),
+                        truncated.available
+                    ),
+                    None => format!(
+                        "{}{} is truncated, {} bytes present",
+                        truncated.id,
+                        at(position),
+                        truncated.available
+                    ),
+                },
+            ),
+            _ => {}
+        }
+        diagnostics.inspect(element);
+    }
+
+    for diagnostic in diagnostics.into_diagnostics() {
+        match diagnostic {
+            Diagnostic::OverflowingElement { position, .. } => report.push(
+                ProblemKind::OverflowingElement,
+                Severity::Error,
+                position,
+                diagnostic.to_string(),
+            ),
+            Diagnostic::DeprecatedElement { position, .. } => report.push(
+                ProblemKind::DeprecatedElement,
+                Severity::Warning,
+                position,
+                diagnostic.to_string(),
+            ),
+            // Reported by the validation of the element trees
+            Diagnostic::OutOfRange { .. } => {}
+        }
+    }
+    for violation in validate(&build_element_trees(elements)) {
+        push_violation(&mut report, violation);
+    }
+    verify_cues(elements, &mut report);
+    verify_seek_head(elements, &mut report);
+    verify_timestamps(elements, &mut report);
+    verify_timelines(elements, options.gap_threshold_ns, &mut report);
+    if options.mse {
+        verify_mse(elements, &mut report);
+    }
+    report.sort();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iter::ElementIterator, Result};
+
+    #[test]
+    fn test_verify() {
+        // Info with a TimestampScale of 0, 3 bytes of garbage, a track
+        // without UID, type and codec, and a Cluster cut short
+        const INPUT: &[u8] = &[
+            0x15, 0x49, 0xA9, 0x66, 0x85, 0x2A, 0xD7, 0xB1, 0x81, 0x00, 0x00, 0x00, 0x00, 0x16,
+            0x54, 0xAE, 0x6B, 0x85, 0xAE, 0x83, 0xD7, 0x81, 0x01, 0x1F, 0x43, 0xB6, 0x75, 0x88,
+            0xE7, 0x81,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let report = verify(&elements);
+        assert!(!report.is_valid());
+        assert_eq!((report.errors, report.warnings), (8, 1));
+        assert_eq!(
+            report.to_string(),
+            "error: missing mandatory element MuxingApp in Info at offset 0x0\n\
+             error: missing mandatory element WritingApp in Info at offset 0x0\n\
+             warning: TimestampScale at offset 0x5 is out of the allowed range \"not 0\"\n\
+             error: 3 bytes of corrupted data at offset 0xa\n\
+             error: missing mandatory element TrackUID in TrackEntry at offset 0x12\n\
+             error: missing mandatory element TrackType in TrackEntry at offset 0x12\n\
+             error: missing mandatory element CodecID in TrackEntry at offset 0x12\n\
+             error: missing mandatory element Timestamp in Cluster at offset 0x17\n\
+             error: Timestamp at offset 0x1c is truncated, 2 of 3 bytes present\n\
+             8 errors, 1 warning"
+        );
+    }
+
+    #[test]
+    fn test_verify_cues() {
+        // Segment without Info, with cue points of track 1 at time 0 and 7
+        // pointing at a keyframe at time 0, at time 5 pointing at the
+        // Cluster of a non-keyframe at time 5, and at time 9 pointing at a
+        // missing Cluster
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xD5, 0x1C, 0x53, 0xBB, 0x6B, 0xBA, 0xBB, 0x8E, 0xB3, 0x81,
+            0x00, 0xB7, 0x89, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x3F, 0xF0, 0x81, 0x03, 0xBB, 0x8B,
+            0xB3, 0x81, 0x05, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x3F, 0xBB, 0x8E, 0xB3,
+            0x81, 0x07, 0xB7, 0x89, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x3F, 0xF0, 0x81, 0x03, 0xBB,
+            0x8B, 0xB3, 0x81, 0x09, 0xB7, 0x86, 0xF7, 0x81, 0x01, 0xF1, 0x81, 0x7F, 0x1F, 0x43,
+            0xB6, 0x75, 0x91, 0xE7, 0x81, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x00, 0x80, 0xAA, 0xA3,
+            0x85, 0x81, 0x00, 0x05, 0x00, 0xBB,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            verify(&elements).to_string(),
+            "error: missing mandatory element Info in Segment at offset 0x0\n\
+             error: cue point at 00:00:00.007 of track 1 points at a block at offset 0x4c with time 00:00:00.000\n\
+             warning: cue point at 00:00:00.005 of track 1 points at a block at offset 0x53 that is not a keyframe\n\
+             error: cue point at 00:00:00.009 of track 1 points at offset 0x84, where no Cluster starts\n\
+             3 errors, 1 warning"
+        );
+    }
+
+    #[test]
+    fn test_verify_seek_head() {
+        // SeekHead with entries of Info and Tracks both pointing at Info, and
+        // of Cues pointing past the end of the Segment
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xBC, 0x11, 0x4D, 0x9B, 0x74, 0xAA, 0x4D, 0xBB, 0x8B, 0x53,
+            0xAB, 0x84, 0x15, 0x49, 0xA9, 0x66, 0x53, 0xAC, 0x81, 0x2F, 0x4D, 0xBB, 0x8B, 0x53,
+            0xAB, 0x84, 0x16, 0x54, 0xAE, 0x6B, 0x53, 0xAC, 0x81, 0x2F, 0x4D, 0xBB, 0x8B, 0x53,
+            0xAB, 0x84, 0x1C, 0x53, 0xBB, 0x6B, 0x53, 0xAC, 0x81, 0x70, 0x15, 0x49, 0xA9, 0x66,
+            0x88, 0x4D, 0x80, 0x81, 0x78, 0x57, 0x41, 0x81, 0x79,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            verify(&elements).to_string(),
+            "error: SeekHead entry of Tracks points at offset 0x34, where Info starts\n\
+             error: SeekHead entry of Cues points at offset 0x75, where no element starts\n\
+             2 errors, 0 warnings"
+        );
+    }
+
+    #[test]
+    fn test_verify_timestamps() {
+        // Segment without Info, with an AVC track 1 whose frames are at 10,
+        // 40 and 20 ms, a keyframe at 70 ms and frames at 60 and 30 ms, and
+        // an Opus track 2 at 10, 30 and 20 ms, followed by a Cluster at 5 ms
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0x40, 0x8C, 0x16, 0x54, 0xAE, 0x6B, 0xB1, 0xAE, 0x9B, 0xD7,
+            0x81, 0x01, 0x73, 0xC5, 0x81, 0x01, 0x83, 0x81, 0x01, 0x86, 0x8F, 0x56, 0x5F, 0x4D,
+            0x50, 0x45, 0x47, 0x34, 0x2F, 0x49, 0x53, 0x4F, 0x2F, 0x41, 0x56, 0x43, 0xAE, 0x92,
+            0xD7, 0x81, 0x02, 0x73, 0xC5, 0x81, 0x02, 0x83, 0x81, 0x02, 0x86, 0x86, 0x41, 0x5F,
+            0x4F, 0x50, 0x55, 0x53, 0x1F, 0x43, 0xB6, 0x75, 0xC2, 0xE7, 0x81, 0x0A, 0xA3, 0x85,
+            0x81, 0x00, 0x00, 0x80, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x1E, 0x00, 0x00, 0xA3, 0x85,
+            0x81, 0x00, 0x0A, 0x00, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x3C, 0x80, 0x00, 0xA3, 0x85,
+            0x81, 0x00, 0x32, 0x00, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x14, 0x00, 0x00, 0xA3, 0x85,
+            0x82, 0x00, 0x00, 0x80, 0x00, 0xA3, 0x85, 0x82, 0x00, 0x14, 0x80, 0x00, 0xA3, 0x85,
+            0x82, 0x00, 0x0A, 0x80, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x8A, 0xE7, 0x81, 0x05, 0xA3,
+            0x85, 0x82, 0x00, 0x00, 0x80, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            verify(&elements).to_string(),
+            "error: missing mandatory element Info in Segment at offset 0x0\n\
+             error: block of track 1 at offset 0x67 has timestamp 00:00:00.030, 00:00:00.010 before the block at offset 0x4b\n\
+             error: block of track 2 at offset 0x7c has timestamp 00:00:00.020, 00:00:00.010 before the block at offset 0x75\n\
+             error: Cluster at offset 0x83 has timestamp 00:00:00.005, 00:00:00.005 before the Cluster at offset 0x3c\n\
+             error: block of track 2 at offset 0x8b has timestamp 00:00:00.005, 00:00:00.015 before the block at offset 0x7c\n\
+             5 errors, 0 warnings"
+        );
+    }
+
+    #[test]
+    fn test_verify_timelines() {
+        // Segment without Info, with an Opus track of 20 ms frames at 0,
+        // 20, 50 and 60 ms, a BlockGroup at 80 ms lasting 30 ms, and a frame
+        // at 100 ms
+        const INPUT: &[u8] = &[
+            0x18, 0x53, 0x80, 0x67, 0xD8, 0x16, 0x54, 0xAE, 0x6B, 0x9C, 0xAE, 0x9A, 0xD7, 0x81,
+            0x01, 0x73, 0xC5, 0x81, 0x01, 0x83, 0x81, 0x02, 0x86, 0x86, 0x41, 0x5F, 0x4F, 0x50,
+            0x55, 0x53, 0x23, 0xE3, 0x83, 0x84, 0x01, 0x31, 0x2D, 0x00, 0x1F, 0x43, 0xB6, 0x75,
+            0xB2, 0xE7, 0x81, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x00, 0x80, 0x00, 0xA3, 0x85, 0x81,
+            0x00, 0x14, 0x80, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x32, 0x80, 0x00, 0xA3, 0x85, 0x81,
+            0x00, 0x3C, 0x80, 0x00, 0xA0, 0x8A, 0xA1, 0x85, 0x81, 0x00, 0x50, 0x00, 0x00, 0x9B,
+            0x81, 0x1E, 0xA3, 0x85, 0x81, 0x00, 0x64, 0x80, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            verify(&elements).to_string(),
+            "error: missing mandatory element Info in Segment at offset 0x0\n\
+             warning: gap of 00:00:00.010 in track 1 between the blocks at offsets 0x35 and 0x3c\n\
+             warning: overlap of 00:00:00.010 in track 1 between the blocks at offsets 0x3c and 0x43\n\
+             warning: overlap of 00:00:00.010 in track 1 between the blocks at offsets 0x4c and 0x56\n\
+             1 error, 3 warnings"
+        );
+        let options = VerifyOptions {
+            gap_threshold_ns: 10_000_000,
+            ..VerifyOptions::default()
+        };
+        assert_eq!(verify_with_options(&elements, &options).warnings, 0);
+    }
+
+    #[test]
+    fn test_verify_mse() {
+        // Matroska DocType, Segment of unknown size with Tracks of video
+        // track 1 and audio track 2 before Info, a Cluster of unknown size
+        // with a block of track 1 before its Timestamp, and a Cluster with a
+        // block of track 1 at 30 ms followed by a block of track 2 at 20 ms
+        const INPUT: &[u8] = &[
+            0x1A, 0x45, 0xDF, 0xA3, 0x8B, 0x42, 0x82, 0x88, 0x6D, 0x61, 0x74, 0x72, 0x6F, 0x73,
+            0x6B, 0x61, 0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x16, 0x54, 0xAE, 0x6B, 0xA7, 0xAE, 0x91, 0xD7, 0x81, 0x01, 0x73, 0xC5, 0x81, 0x01,
+            0x83, 0x81, 0x01, 0x86, 0x85, 0x56, 0x5F, 0x56, 0x50, 0x39, 0xAE, 0x92, 0xD7, 0x81,
+            0x02, 0x73, 0xC5, 0x81, 0x02, 0x83, 0x81, 0x02, 0x86, 0x86, 0x41, 0x5F, 0x4F, 0x50,
+            0x55, 0x53, 0x15, 0x49, 0xA9, 0x66, 0x88, 0x4D, 0x80, 0x81, 0x78, 0x57, 0x41, 0x81,
+            0x79, 0x1F, 0x43, 0xB6, 0x75, 0xFF, 0xA3, 0x85, 0x81, 0x00, 0x00, 0x80, 0x00, 0xE7,
+            0x81, 0x00, 0xA3, 0x85, 0x81, 0x00, 0x0A, 0x80, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x91,
+            0xE7, 0x81, 0x14, 0xA3, 0x85, 0x81, 0x00, 0x0A, 0x80, 0x00, 0xA3, 0x85, 0x82, 0x00,
+            0x00, 0x80, 0x00,
+        ];
+        let elements = ElementIterator::new(INPUT)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(verify(&elements).is_valid());
+        let options = VerifyOptions {
+            mse: true,
+            ..VerifyOptions::default()
+        };
+        assert_eq!(
+            verify_with_options(&elements, &options).to_string(),
+            "error: Info at offset 0x48 comes after Tracks at offset 0x1c\n\
+             error: Cluster at offset 0x55 has no blocks of track 2\n\
+             error: Cluster at offset 0x55 has a block at offset 0x5a before its Timestamp\n\
+             error: block at offset 0x7a is earlier than the previous block of its Cluster\n\
+             error: the DocType is matroska rather than webm\n\
+             5 errors, 0 warnings"
+        );
+    }
+}
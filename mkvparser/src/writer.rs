@@ -0,0 +1,309 @@
+//! Serialize elements back into EBML bytes
+//!
+//! Element sizes are recomputed from the bodies being written, so values
+//! can be modified before writing. Integers and sizes are written with the
+//! minimum number of bytes needed.
+use std::io::Write;
+
+use base64::prelude::*;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{
+    borrowed::{BodyRef, ElementRef},
+    elements::Id,
+    tree::ElementTree,
+    Binary, Body, Element, Error, Header, Result,
+};
+
+/// Unknown size marker with the maximum varint length
+const UNKNOWN_SIZE: [u8; 8] = [0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+fn varint_length(value: u64) -> usize {
+    // A varint of length N holds 7*N bits of data, and the value with all
+    // bits set is reserved for unknown sizes.
+    (1..=8)
+        .find(|length| value < (1 << (7 * length)) - 1)
+        .unwrap_or(9)
+}
+
+/// Write an element ID
+pub fn write_id(writer: &mut impl Write, id: &Id) -> Result<usize> {
+    let value = id.get_value().ok_or(Error::InvalidId)?;
+    let bytes = value.to_be_bytes();
+    let leading_zero_bytes = bytes.iter().take_while(|b| **b == 0).count();
+    writer.write_all(&bytes[leading_zero_bytes..])?;
+    Ok(bytes.len() - leading_zero_bytes)
+}
+
+/// Write an element data size. `None` is written as an unknown size.
+pub fn write_size(writer: &mut impl Write, size: Option<usize>) -> Result<usize> {
+    let Some(size) = size else {
+        writer.write_all(&UNKNOWN_SIZE)?;
+        return Ok(UNKNOWN_SIZE.len());
+    };
+    let length = varint_length(size as u64);
+    if length > 8 {
+        return Err(Error::InvalidVarint);
+    }
+    let value = (size as u64) | (1 << (7 * length));
+    writer.write_all(&value.to_be_bytes()[(8 - length)..])?;
+    Ok(length)
+}
+
+fn write_header(writer: &mut impl Write, id: &Id, body_size: Option<usize>) -> Result<usize> {
+    Ok(write_id(writer, id)? + write_size(writer, body_size)?)
+}
+
+fn unsigned_bytes(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let leading_zero_bytes = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    bytes[leading_zero_bytes..].to_vec()
+}
+
+fn signed_bytes(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // Strip redundant sign extension bytes while keeping the sign bit
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+fn float_bytes(header: &Header, value: f64) -> Vec<u8> {
+    // Keep single precision if that's how the value was originally stored
+    if header.body_size == Some(4) {
+        (value as f32).to_be_bytes().to_vec()
+    } else {
+        value.to_be_bytes().to_vec()
+    }
+}
+
+fn date_bytes(value: &DateTime<Utc>) -> Result<Vec<u8>> {
+    let epoch_2001 = NaiveDate::from_ymd_opt(2001, 1, 1)
+        .ok_or(Error::InvalidDate)?
+        .and_hms_opt(0, 0, 0)
+        .ok_or(Error::InvalidDate)?
+        .and_utc();
+    let nanos = (*value - epoch_2001)
+        .num_nanoseconds()
+        .ok_or(Error::InvalidDate)?;
+    Ok(nanos.to_be_bytes().to_vec())
+}
+
+fn binary_bytes(binary: &Binary) -> Result<Vec<u8>> {
+    match binary {
+        Binary::SeekId(id) => {
+            let mut bytes = Vec::new();
+            write_id(&mut bytes, id)?;
+            Ok(bytes)
+        }
+        Binary::Void(size) => Ok(vec![0; *size]),
+        // Short payloads are fully serialized as "[xx xx ...]"
+        Binary::Standard(preview) if preview.starts_with('[') => preview
+            .trim_matches(|c| c == '[' || c == ']')
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| Error::IncompleteBinary))
+            .collect(),
+        Binary::Uuid(uuid) => {
+            let hex = uuid.replace('-', "");
+            (0..hex.len())
+                .step_by(2)
+                .map(|index| {
+                    hex.get(index..index + 2)
+                        .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                        .ok_or(Error::IncompleteBinary)
+                })
+                .collect()
+        }
+        Binary::Base64(encoded) => BASE64_STANDARD
+            .decode(encoded)
+            .map_err(|_| Error::IncompleteBinary),
+        _ => Err(Error::IncompleteBinary),
+    }
+}
+
+fn body_bytes(header: &Header, body: &Body) -> Result<Vec<u8>> {
+    match body {
+        Body::Master => Ok(Vec::new()),
+        Body::Unsigned(value) => Ok(unsigned_bytes(value.value())),
+        Body::Signed(value) => Ok(signed_bytes(*value)),
+        Body::Float(value) => Ok(float_bytes(header, *value)),
+        Body::String(value) | Body::Utf8(value) => Ok(value.as_bytes().to_vec()),
+        Body::Date(value) => date_bytes(value),
+        Body::Binary(binary) => binary_bytes(binary),
+    }
+}
+
+/// Write an element.
+///
+/// Master elements are written with their original body size, as children
+/// are written separately. Binary payloads are only available for short
+/// binaries, SeekID and Void elements, other binaries fail with
+/// [`Error::IncompleteBinary`]. Use [`write_element_ref`] to write any
+/// binary payload.
+pub fn write_element(writer: &mut impl Write, element: &Element) -> Result<usize> {
+    let Element { header, body } = element;
+    if let Body::Master = body {
+        return write_header(writer, &header.id, header.body_size);
+    }
+    let bytes = body_bytes(header, body)?;
+    let header_size = write_header(writer, &header.id, Some(bytes.len()))?;
+    writer.write_all(&bytes)?;
+    Ok(header_size + bytes.len())
+}
+
+/// Write an element borrowing its payload.
+///
+/// Master elements are written with their original body size, as children
+/// are written separately.
+pub fn write_element_ref(writer: &mut impl Write, element: &ElementRef) -> Result<usize> {
+    let ElementRef { header, body } = element;
+    let bytes = match body {
+        BodyRef::Master => return write_header(writer, &header.id, header.body_size),
+        BodyRef::Unsigned(value) => unsigned_bytes(value.value()),
+        BodyRef::Signed(value) => signed_bytes(*value),
+        BodyRef::Float(value) => float_bytes(header, *value),
+        BodyRef::String(value) | BodyRef::Utf8(value) => value.as_bytes().to_vec(),
+        BodyRef::Date(value) => date_bytes(value)?,
+        BodyRef::Binary(value) => {
+            let header_size = write_header(writer, &header.id, Some(value.len()))?;
+            writer.write_all(value)?;
+            return Ok(header_size + value.len());
+        }
+    };
+    let header_size = write_header(writer, &header.id, Some(bytes.len()))?;
+    writer.write_all(&bytes)?;
+    Ok(header_size + bytes.len())
+}
+
+/// Write an element tree.
+///
+/// The sizes of Master elements are recomputed from their children, except
+/// for those with an unknown size, which keep it unknown.
+pub fn write_element_tree(writer: &mut impl Write, tree: &ElementTree) -> Result<usize> {
+    match tree {
+        ElementTree::Normal(element) => write_element(writer, element),
+        ElementTree::Master(master) => {
+            let mut body = Vec::new();
+            for child in &master.children {
+                write_element_tree(&mut body, child)?;
+            }
+            let body_size = master.header.body_size.map(|_| body.len());
+            let header_size = write_header(writer, &master.header.id, body_size)?;
+            writer.write_all(&body)?;
+            Ok(header_size + body.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        borrowed::ElementRefIterator, enumerations::Enumeration, enumerations::TrackType,
+        parse_element, tree::build_element_trees, Unsigned,
+    };
+
+    #[test]
+    fn test_write_size() {
+        let mut output = Vec::new();
+        write_size(&mut output, Some(0x7E)).unwrap();
+        write_size(&mut output, Some(0x7F)).unwrap();
+        write_size(&mut output, None).unwrap();
+        assert_eq!(
+            output,
+            [0xFE, 0x40, 0x7F, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_write_integers() {
+        assert_eq!(unsigned_bytes(0), [0x00]);
+        assert_eq!(unsigned_bytes(0x1234), [0x12, 0x34]);
+        assert_eq!(signed_bytes(-1), [0xFF]);
+        assert_eq!(signed_bytes(-129), [0xFF, 0x7F]);
+        assert_eq!(signed_bytes(128), [0x00, 0x80]);
+    }
+
+    #[test]
+    fn test_write_element() {
+        let mut output = Vec::new();
+        write_element(
+            &mut output,
+            &Element {
+                header: Header::new(Id::TrackType, 2, 8),
+                body: Body::Unsigned(Unsigned::Enumeration(Enumeration::TrackType(
+                    TrackType::Audio,
+                ))),
+            },
+        )
+        .unwrap();
+        assert_eq!(output, [0x83, 0x81, 0x02]);
+        assert_eq!(
+            parse_element(&output).unwrap().1.header,
+            Header::new(Id::TrackType, 2, 1)
+        );
+
+        assert_eq!(
+            write_element(
+                &mut output,
+                &Element {
+                    header: Header::new(Id::CodecPrivate, 3, 100),
+                    body: Body::Binary(Binary::Standard("100 bytes".into())),
+                }
+            ),
+            Err(Error::IncompleteBinary)
+        );
+
+        output.clear();
+        write_element(
+            &mut output,
+            &Element {
+                header: Header::new(Id::CodecPrivate, 3, 2),
+                body: Body::Binary(Binary::Base64("3q0=".into())),
+            },
+        )
+        .unwrap();
+        assert_eq!(output, [0x63, 0xA2, 0x82, 0xDE, 0xAD]);
+
+        output.clear();
+        write_element(
+            &mut output,
+            &Element {
+                header: Header::new(Id::SegmentUuid, 3, 16),
+                body: Body::Binary(Binary::Uuid("00010203-0405-0607-0809-0a0b0c0d0e0f".into())),
+            },
+        )
+        .unwrap();
+        assert_eq!(output[..3], [0x73, 0xA4, 0x90]);
+        assert_eq!(output[3..], (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        const INPUT: &[u8] = &[
+            0x1A, 0x45, 0xDF, 0xA3, 0x9F, 0x42, 0x86, 0x81, 0x01, 0x42, 0xF7, 0x81, 0x01, 0x42,
+            0xF2, 0x81, 0x04, 0x42, 0xF3, 0x81, 0x08, 0x42, 0x82, 0x84, 0x77, 0x65, 0x62, 0x6D,
+            0x42, 0x87, 0x81, 0x04, 0x42, 0x85, 0x81, 0x02, 0xBF, 0x84, 0xAF, 0x93, 0x97, 0x18,
+        ];
+
+        let mut output = Vec::new();
+        for element in ElementRefIterator::new(INPUT) {
+            write_element_ref(&mut output, &element.unwrap()).unwrap();
+        }
+        assert_eq!(output, INPUT);
+
+        let elements = ElementRefIterator::new(INPUT)
+            .map(|element| element.unwrap().to_element().unwrap())
+            .collect::<Vec<_>>();
+        let mut output = Vec::new();
+        for tree in build_element_trees(&elements) {
+            write_element_tree(&mut output, &tree).unwrap();
+        }
+        assert_eq!(output, INPUT);
+    }
+}
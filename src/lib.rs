@@ -1,237 +1,292 @@
 #![doc = include_str!("../README.md")]
 
+#[doc(hidden)]
+pub mod mkvinfo;
+#[doc(hidden)]
+pub mod pretty;
+mod text;
+
 use std::{
     fs::File,
     io::{Read, Seek},
+    ops::Range,
     path::Path,
+    time::Duration,
 };
 
 use mkvparser::{
-    elements::{Id, Type},
-    parse_body, parse_corrupt, parse_header, peek_binary, Binary, Body, Element, Error, Header,
+    diagnostics::{Diagnostic, Diagnostics},
+    elements::Id,
+    iter::ElementIterator,
+    parallel::parse_elements_parallel,
+    streaming::StreamingParser,
+    tree::compute_unknown_sizes,
+    Body, Element, ParserOptions,
 };
 
-const DEFAULT_BUFFER_SIZE: u64 = 8192;
+/// Where to stop parsing a file
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Head {
+    /// Stop after this number of elements
+    Elements(usize),
+    /// Stop before the Cluster following this number of Clusters
+    Clusters(usize),
+}
 
-fn insert_position(element: &mut Element, position: &mut Option<usize>) {
-    element.header.position = *position;
-    *position = position.map(|p| {
-        if let Body::Master = element.body {
-            p + element.header.header_size
-        } else {
-            // It's safe to unwrap because all non-Master elements have a set size
-            p + element.header.size.unwrap()
+impl Head {
+    // Whether parsing stops before an element, given the numbers of
+    // elements and Clusters parsed so far
+    fn stops_before(&self, element: &Element, elements: usize, clusters: usize) -> bool {
+        match *self {
+            Head::Elements(count) => elements >= count,
+            Head::Clusters(count) => element.header.id == Id::Cluster && clusters >= count,
         }
-    });
+    }
 }
 
-type IResult<T, O> = mkvparser::Result<(T, O)>;
-
-struct ShortParsed {
-    element: Element,
-    bytes_to_be_skipped: usize,
+// Stop iterating over parsed elements where the head stops
+fn take_head(
+    parsed: impl Iterator<Item = mkvparser::Result<Element>>,
+    head: Option<Head>,
+) -> impl Iterator<Item = mkvparser::Result<Element>> {
+    let mut elements = 0;
+    let mut clusters = 0;
+    parsed.map_while(move |element| {
+        if let Ok(element) = &element {
+            if head.is_some_and(|head| head.stops_before(element, elements, clusters)) {
+                return None;
+            }
+            elements += 1;
+            if element.header.id == Id::Cluster {
+                clusters += 1;
+            }
+        }
+        Some(element)
+    })
 }
 
-// For all element types except Binary, we can just parse the body, consuming all
-// bytes in it. Binary bodies can be rather large, but:
-// - we are not going to display their full payload in the dump anyways
-// - we don't want to load those large buffers in memory
-// so we just peek the first bytes in the beginning for some binary sub-types,
-// summarize the payload or serialize short ones.
-// For the binary bodies, since we're only peeking the buffer and not consuming it,
-// we return to the caller how many bytes should be skipped.
-fn parse_short(input: &[u8]) -> IResult<&[u8], ShortParsed> {
-    let (input, header) = parse_header(input)?;
-    if header.id.get_type() != Type::Binary {
-        let (input, body) = parse_body(&header, input)?;
-        Ok((
-            input,
-            ShortParsed {
-                element: Element { header, body },
-                bytes_to_be_skipped: 0,
-            },
-        ))
-    } else {
-        let (input, binary) = peek_binary(&header, input)?;
-        let body_size = header.body_size.ok_or(Error::ForbiddenUnknownSize)?;
-        Ok((
-            input,
-            ShortParsed {
-                element: Element {
-                    header,
-                    body: Body::Binary(binary),
-                },
-                bytes_to_be_skipped: body_size,
-            },
-        ))
+// Timestamp of the Cluster at a position, if it starts with one, read
+// separately from the elements being iterated over
+fn peek_cluster_timestamp<R: Read + Seek>(reader: R, position: usize) -> Option<u64> {
+    let mut parsed = ElementIterator::seekable(reader);
+    parsed.seek(position as u64).ok()?;
+    let mut elements = parsed.map_while(Result::ok);
+    if elements.next()?.header.id != Id::Cluster {
+        return None;
     }
+    elements
+        .take_while(|element| matches!(element.header.id, Id::Timestamp | Id::Crc32 | Id::Void))
+        .find_map(|element| match element.body {
+            Body::Unsigned(timestamp) if element.header.id == Id::Timestamp => {
+                Some(timestamp.value())
+            }
+            _ => None,
+        })
 }
 
-fn parse_short_corrupt<'a>(
-    input: &'a [u8],
-    is_corrupt: &mut bool,
-) -> IResult<&'a [u8], ShortParsed> {
-    let (input, corrupt_element) = parse_corrupt(input)?;
-    // If we fully consume the buffer as a corrupt region, we are still in
-    // a "corrupt state", so the caller should directly parse a
-    // corrupt region again until some valid element is found instead of
-    // attempting to parse an element (it could happen that parsing from
-    // the wrong start byte yields valid elements and the parser never
-    // returns to a valid state again).
-    if !input.is_empty() {
-        *is_corrupt = false;
-    }
-    Ok((
-        input,
-        ShortParsed {
-            element: corrupt_element,
-            bytes_to_be_skipped: 0,
-        },
-    ))
+// Skip the Clusters that are entirely outside of a time range in
+// nanoseconds, along with their bodies, as found from the Timestamps of each
+// Cluster and of the next one. Like in retain_clusters_in_time_range(), a
+// Cluster lasts until the next one starts. Clusters with unknown size or
+// without a Timestamp are kept, for retain_clusters_in_time_range() to tell.
+fn skip_clusters_outside<R: Read + Seek>(
+    mut parsed: ElementIterator<R>,
+    mut peeked: R,
+    time_range: Range<i64>,
+) -> impl Iterator<Item = mkvparser::Result<Element>> {
+    let mut timestamp_scale = 1_000_000;
+    let mut past_end = false;
+    std::iter::from_fn(move || loop {
+        let element = match parsed.next()? {
+            Ok(element) => element,
+            Err(e) => return Some(Err(e)),
+        };
+        match (&element.header.id, &element.body) {
+            (Id::TimestampScale, Body::Unsigned(value)) => timestamp_scale = value.value(),
+            (Id::Cluster, Body::Master) => {
+                let (Some(position), Some(size), Some(body_size)) = (
+                    element.header.position,
+                    element.header.size,
+                    element.header.body_size,
+                ) else {
+                    return Some(Ok(element));
+                };
+                let mut time_ns = |position| {
+                    peek_cluster_timestamp(&mut peeked, position)?
+                        .checked_mul(timestamp_scale)
+                        .and_then(|time_ns| i64::try_from(time_ns).ok())
+                };
+                // Clusters are in time order, so the ones after the end
+                // are all skipped
+                past_end =
+                    past_end || time_ns(position).is_some_and(|start| start >= time_range.end);
+                if past_end || time_ns(position + size).is_some_and(|next| next <= time_range.start)
+                {
+                    parsed.skip_body(body_size);
+                    continue;
+                }
+            }
+            _ => (),
+        }
+        return Some(Ok(element));
+    })
 }
 
-fn parse_short_or_corrupt<'a>(
-    input: &'a [u8],
-    is_corrupt: &mut bool,
-) -> IResult<&'a [u8], ShortParsed> {
-    let parsed_short = if *is_corrupt {
-        parse_short_corrupt(input, is_corrupt)
-    } else {
-        parse_short(input)
+/// Iterate over the elements of a file as they are parsed, skipping the
+/// Clusters outside of a time range in nanoseconds, if any
+#[doc(hidden)]
+pub fn iter_elements_from_file(
+    path: impl AsRef<Path>,
+    options: ParserOptions,
+    head: Option<Head>,
+    time_range: Option<Range<i64>>,
+) -> anyhow::Result<impl Iterator<Item = mkvparser::Result<Element>>> {
+    let parsed = ElementIterator::seekable(File::open(&path)?).with_options(options);
+    let parsed: Box<dyn Iterator<Item = _>> = match time_range {
+        Some(time_range) => Box::new(skip_clusters_outside(
+            parsed,
+            File::open(&path)?,
+            time_range,
+        )),
+        None => Box::new(parsed),
     };
-
-    match parsed_short {
-        Ok((input, short_parsed)) => Ok((input, short_parsed)),
-        Err(Error::NeedData) => Err(Error::NeedData),
-        Err(_) => {
-            *is_corrupt = true;
-            parse_short_corrupt(input, is_corrupt)
-        }
-    }
+    Ok(take_head(parsed, head))
 }
 
+/// Iterate over the elements of a file that is still being written, such as
+/// a live recording, like `tail -f`: once the end of the file is reached, it
+/// is polled every `interval` for new data, so the iterator only ends at the
+/// head or on an I/O error. Elements with unknown size, such as the Clusters
+/// of a recording, are emitted as soon as their header is read and their
+/// children as they are complete.
 #[doc(hidden)]
-pub fn parse_elements_from_file(
+pub fn follow_elements_from_file(
     path: impl AsRef<Path>,
-    show_positions: bool,
-) -> anyhow::Result<Vec<Element>> {
+    options: ParserOptions,
+    head: Option<Head>,
+    interval: Duration,
+) -> anyhow::Result<impl Iterator<Item = mkvparser::Result<Element>>> {
     let mut file = File::open(path)?;
-    let file_length = file.metadata()?.len();
-
-    let buffer_size = file_length.min(DEFAULT_BUFFER_SIZE).try_into().unwrap();
-    let mut buffer = vec![0; buffer_size];
-    let mut filled = 0;
-    let mut elements = Vec::<Element>::new();
-    let mut position = show_positions.then_some(0);
-    let mut is_corrupt = false;
-
-    loop {
-        let num_read = file.read(&mut buffer[filled..])?;
-        let mut parse_buffer = &buffer[..(filled + num_read)];
-
-        if num_read == 0 {
-            // If some bytes are still to be parsed but nothing was read,
-            // append a final corrupt element.
-            if !parse_buffer.is_empty() {
-                push_corrupt_element(
-                    &mut elements,
-                    Element {
-                        header: Header::new(Id::corrupted(), 0, parse_buffer.len()),
-                        body: Body::Binary(Binary::Corrupted),
-                    },
-                )
+    let mut parser = StreamingParser::new().with_options(options);
+    let mut chunk = vec![0; 64 * 1024];
+    let mut failed = false;
+    let parsed = std::iter::from_fn(move || loop {
+        if failed {
+            return None;
+        }
+        if let Some(element) = parser.next_element() {
+            return Some(element);
+        }
+        match file.read(&mut chunk) {
+            Ok(0) => std::thread::sleep(interval),
+            Ok(num_read) => parser.feed(&chunk[..num_read]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(e) => {
+                failed = true;
+                return Some(Err(e.into()));
             }
+        }
+    });
+    Ok(take_head(parsed, head))
+}
 
-            // we have nothing left to read or parse
+/// Parse all the elements of a file, skipping the Clusters outside of a time
+/// range in nanoseconds, if any, unless parsing in parallel
+#[doc(hidden)]
+pub fn parse_elements_from_file(
+    path: impl AsRef<Path>,
+    options: ParserOptions,
+    parallel: bool,
+    head: Option<Head>,
+    time_range: Option<Range<i64>>,
+) -> anyhow::Result<(Vec<Element>, Vec<Diagnostic>)> {
+    let parsed: Box<dyn Iterator<Item = Result<Element, _>>> = if parallel {
+        Box::new(
+            parse_elements_parallel(&std::fs::read(&path)?, &options)?
+                .into_iter()
+                .map(Ok),
+        )
+    } else {
+        let parsed = ElementIterator::seekable(File::open(&path)?).with_options(options);
+        match time_range {
+            Some(time_range) => Box::new(skip_clusters_outside(
+                parsed,
+                File::open(&path)?,
+                time_range,
+            )),
+            None => Box::new(parsed),
+        }
+    };
+    let mut elements = Vec::new();
+    let mut clusters = 0;
+    // The first element not to be emitted, if parsing stopped early
+    let mut next = None;
+    for element in parsed {
+        let element = element?;
+        if head.is_some_and(|head| head.stops_before(&element, elements.len(), clusters)) {
+            next = Some(element);
             break;
         }
-
-        while let Ok((
-            new_parse_buffer,
-            ShortParsed {
-                mut element,
-                bytes_to_be_skipped,
-            },
-        )) = parse_short_or_corrupt(parse_buffer, &mut is_corrupt)
-        {
-            insert_position(&mut element, &mut position);
-
-            if element.header.id == Id::corrupted() {
-                push_corrupt_element(&mut elements, element);
-            } else {
-                elements.push(element);
-            }
-
-            if new_parse_buffer.len() >= bytes_to_be_skipped {
-                // If the binary body is already in our buffer, just skip in
-                // the buffer
-                parse_buffer = &new_parse_buffer[bytes_to_be_skipped..];
-            } else {
-                // Else, skip the remaining bytes in the buffer and seek in the file.
-                file.seek(std::io::SeekFrom::Current(
-                    (bytes_to_be_skipped - new_parse_buffer.len()) as i64,
-                ))?;
-                parse_buffer = &[];
-            }
+        if element.header.id == Id::Cluster {
+            clusters += 1;
         }
+        elements.push(element);
+    }
+    let file_size = std::fs::metadata(path)?.len() as usize;
 
-        filled = parse_buffer.len();
-        let parse_buffer = Vec::from(parse_buffer);
-        buffer[..filled].copy_from_slice(&parse_buffer);
+    let mut diagnostics = Diagnostics::new();
+    for element in &elements {
+        diagnostics.inspect(element);
     }
-    Ok(elements)
-}
 
-// While pushing corrupt elements, we check whether the last element was also corrupt
-// to merge the corrupt area rather than appending a new element.
-fn push_corrupt_element(elements: &mut Vec<Element>, corrupt_element: Element) {
-    match elements.last_mut() {
-        Some(last_element) if last_element.header.id == Id::corrupted() => {
-            last_element.header = Header::new(
-                Id::corrupted(),
-                last_element.header.header_size + corrupt_element.header.header_size,
-                last_element.header.body_size.unwrap() + corrupt_element.header.body_size.unwrap(),
-            );
-        }
-        _ => elements.push(corrupt_element),
+    // The next element still closes the elements with unknown size that
+    // can't contain it
+    let stopped = next.is_some();
+    elements.extend(next);
+    compute_unknown_sizes(&mut elements, file_size);
+    if stopped {
+        elements.pop();
     }
+    Ok((elements, diagnostics.into_diagnostics()))
 }
 
 #[cfg(test)]
 mod tests {
-    use mkvparser::Binary;
+    use std::io::Cursor;
 
     use super::*;
 
     #[test]
-    fn sequential_corrupt_elements() {
-        let mut elements = vec![];
-        let example_element = Element {
-            header: Header {
-                id: Id::corrupted(),
-                header_size: 0,
-                body_size: Some(4),
-                size: Some(4),
-                position: None,
-            },
-            body: Body::Binary(Binary::Corrupted),
+    fn test_skip_clusters_outside() {
+        // Clusters at 0, 10 and 20 ms, followed by a Cues
+        const INPUT: &[u8] = &[
+            0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7, 0x81, 0x00, 0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7,
+            0x81, 0x0A, 0x1F, 0x43, 0xB6, 0x75, 0x83, 0xE7, 0x81, 0x14, 0x1C, 0x53, 0xBB, 0x6B,
+            0x80,
+        ];
+        let positions = |time_range: Range<i64>| {
+            let parsed = ElementIterator::seekable(Cursor::new(INPUT));
+            skip_clusters_outside(parsed, Cursor::new(INPUT), time_range)
+                .map(|element| {
+                    let header = element.unwrap().header;
+                    (header.id, header.position.unwrap())
+                })
+                .collect::<Vec<_>>()
         };
-        push_corrupt_element(&mut elements, example_element.clone());
-        push_corrupt_element(&mut elements, example_element);
-
-        assert_eq!(elements.len(), 1);
         assert_eq!(
-            elements[0],
-            Element {
-                header: Header {
-                    id: Id::corrupted(),
-                    header_size: 0,
-                    body_size: Some(8),
-                    size: Some(8),
-                    position: None,
-                },
-                body: Body::Binary(Binary::Corrupted),
-            }
-        )
+            positions(15_000_000..i64::MAX),
+            [
+                (Id::Cluster, 8),
+                (Id::Timestamp, 13),
+                (Id::Cluster, 16),
+                (Id::Timestamp, 21),
+                (Id::Cues, 24)
+            ]
+        );
+        assert_eq!(
+            positions(0..10_000_000),
+            [(Id::Cluster, 0), (Id::Timestamp, 5), (Id::Cues, 24)]
+        );
     }
 }
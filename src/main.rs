@@ -1,29 +1,353 @@
 #![doc = include_str!("../README.md")]
 
-use clap::{Parser, ValueEnum};
-use mkvdump::parse_elements_from_file;
-use mkvparser::tree::build_element_trees;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand, ValueEnum};
+use mkvdump::{
+    follow_elements_from_file, iter_elements_from_file, mkvinfo, parse_elements_from_file, pretty,
+    Head,
+};
+use mkvparser::{
+    attachments::attachment_table,
+    blocks::{
+        blocks, compose_block_groups, retain_blocks_of_tracks, retain_clusters_in_time_range,
+    },
+    chapters::chapter_list,
+    crc::check_crcs,
+    cues::cue_table,
+    diagnostics::Diagnostics,
+    elements::Id,
+    enumerations::ContentCompAlgo,
+    ffprobe::probe,
+    hdr::set_hdr_summaries,
+    model::{AttachedFile, ContentCompression, Document},
+    segments::group_by_segment,
+    sizes::{format_size, set_human_sizes},
+    split_frames,
+    stats::{track_statistics, void_statistics},
+    streamability::streamability,
+    summary::summarize,
+    tags::tag_groups,
+    times::set_human_times,
+    tracks::track_table,
+    tree::{
+        build_element_tree_refs, build_element_trees, build_element_trees_owned,
+        filter_element_trees, filter_elements, insert_implied_elements, truncate_element_trees,
+        ElementTree, ElementTreeRef,
+    },
+    verify::{verify_with_options, VerifyOptions},
+    BinaryEncoding, Element, ParserOptions,
+};
 use serde::Serialize;
-use std::io::Write;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{BufWriter, IsTerminal, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// How often a followed file is polled for new data once its end is reached
+#[doc(hidden)]
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(200);
 
 #[doc(hidden)]
 #[derive(Parser)]
-#[clap(author, version, about, long_about = None)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    /// Name of the MKV/WebM file to be parsed
-    filename: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
 
-    /// Output format
+    /// Names of the MKV/WebM files to be parsed. The output of several
+    /// files is a map from their names to their elements.
+    #[clap(required = true)]
+    filenames: Vec<String>,
+
+    /// Output format. The mkvinfo format is the indented text of mkvinfo
+    /// from MKVToolNix, regardless of the profile. The ndjson format is a
+    /// linear output with one element per line, printed as it is parsed
+    /// unless other options need the complete file first. The csv format
+    /// lists the SimpleBlocks and Blocks, one per row. The cbor and msgpack
+    /// formats are binary, with one document per file if several files
+    /// are dumped. The ffprobe format describes the tracks and the file as
+    /// the JSON output of ffprobe -show_streams -show_format. The pretty
+    /// format is an indented text with colors when printed to a terminal.
     #[clap(value_enum, short, long, default_value = "yaml")]
     format: Format,
 
+    /// Naming of the fields in the output
+    #[clap(value_enum, long, default_value = "mkvdump-v1")]
+    profile: Profile,
+
     /// Add element positions in the output
     #[clap(short = 'p', long)]
     show_element_positions: bool,
 
+    /// Add element paths in the output, such as \Segment\Info\TimestampScale
+    #[clap(short = 'P', long)]
+    show_element_paths: bool,
+
+    /// Add element nesting levels in the output
+    #[clap(long)]
+    show_element_levels: bool,
+
+    /// Add the sizes of the ID and of the size field of element headers in
+    /// the output
+    #[clap(long)]
+    show_header_field_sizes: bool,
+
+    /// Mark deprecated elements in the output
+    #[clap(long)]
+    show_deprecated: bool,
+
+    /// Show times such as Cluster Timestamps, Duration, CueTime, block and
+    /// chapter times as HH:MM:SS.mmm, alongside their values
+    #[clap(long)]
+    human_timestamps: bool,
+
+    /// Show element sizes and the sizes of summaries with binary units, such
+    /// as 1.5 MiB, alongside their exact byte counts
+    #[clap(long)]
+    human_sizes: bool,
+
     /// Show output as a sequence, rather than a tree
     #[clap(short = 'l', long)]
     linear_output: bool,
+
+    /// Add absent mandatory elements with their default values to the tree output
+    #[clap(long, conflicts_with = "linear_output")]
+    show_implied_defaults: bool,
+
+    /// Only show elements nested up to this depth in the tree output, such
+    /// as 1 for the children of top-level elements
+    #[clap(long, conflicts_with = "linear_output")]
+    max_depth: Option<usize>,
+
+    /// Show each BlockGroup of the tree output as a single record with its
+    /// Block, BlockDuration, ReferenceBlocks and DiscardPadding
+    #[clap(long, conflicts_with = "linear_output")]
+    compose_block_groups: bool,
+
+    /// Fail on malformed data, rather than reporting it as Corrupted elements
+    #[clap(long)]
+    strict: bool,
+
+    /// Accept unknown sizes in any element, rather than only in Segment and
+    /// Cluster. Other elements with unknown size extend up to the next
+    /// sibling or top-level element.
+    #[clap(long)]
+    lenient_unknown_sizes: bool,
+
+    /// Print warnings about deprecated elements, suspicious sizes and
+    /// out-of-range values to stderr
+    #[clap(short = 'w', long)]
+    show_warnings: bool,
+
+    /// Print the number and total size of Void elements to stderr
+    #[clap(long)]
+    show_void_stats: bool,
+
+    /// Encoding of fully serialized binary payloads
+    #[clap(value_enum, long, default_value = "hex")]
+    binary_encoding: Encoding,
+
+    /// Binary payloads up to this size in bytes are fully serialized, while
+    /// larger ones are summarized by their size
+    #[clap(long, default_value_t = ParserOptions::default().max_binary_preview)]
+    max_binary_preview: usize,
+
+    /// Group the output per Segment, with the index and byte range of each
+    /// Segment
+    #[clap(short = 's', long)]
+    per_segment: bool,
+
+    /// Only show these elements, such as Tracks,Info,Cues, along with the
+    /// elements containing them and the elements they contain
+    #[clap(long, value_delimiter = ',')]
+    include: Vec<Id>,
+
+    /// Hide these elements, such as SimpleBlock, along with the elements
+    /// they contain
+    #[clap(long, value_delimiter = ',')]
+    exclude: Vec<Id>,
+
+    /// Only show the SimpleBlocks and BlockGroups of these tracks, such as
+    /// 2 or 1,3
+    #[clap(long, value_delimiter = ',')]
+    track: Vec<usize>,
+
+    /// Only show the Clusters that end after this time, such as 00:12:00,
+    /// 12:00 or 720.5 seconds
+    #[clap(long, value_parser = parse_time)]
+    start_time: Option<i64>,
+
+    /// Only show the Clusters that start before this time, such as
+    /// 00:12:10
+    #[clap(long, value_parser = parse_time)]
+    end_time: Option<i64>,
+
+    /// Skip over the bodies of Clusters to only dump the other elements,
+    /// which is much faster for large files
+    #[clap(long)]
+    no_clusters: bool,
+
+    /// Stop after this number of elements
+    #[clap(long, conflicts_with = "head_clusters")]
+    head: Option<usize>,
+
+    /// Stop after this number of Clusters
+    #[clap(long)]
+    head_clusters: Option<usize>,
+
+    /// Keep the file open once its end is reached and print the elements
+    /// appended to it as they are complete, like tail -f, such as while a
+    /// live recording grows. Implies the ndjson format, and stops only with
+    /// --head or --head-clusters.
+    #[clap(
+        long,
+        conflicts_with_all = ["max_depth", "compose_block_groups", "show_implied_defaults"]
+    )]
+    follow: bool,
+
+    /// Load the file in memory and parse Clusters in parallel
+    #[clap(short = 'j', long)]
+    parallel: bool,
+
+    /// Write the CodecPrivate of each track to track-N.bin in this
+    /// directory, or in a subdirectory named after each file if several
+    /// files are dumped
+    #[clap(long)]
+    extract_codec_private: Option<PathBuf>,
+
+    /// Check the CRC-32 elements of the file and print each Master element
+    /// whose checksum doesn't match its body to stderr, with its path and
+    /// offset
+    #[clap(long)]
+    check_crc: bool,
+}
+
+#[doc(hidden)]
+#[derive(Subcommand)]
+enum Command {
+    /// Print an overview of a file: DocType, duration, applications,
+    /// tracks, chapters and attachments
+    Summary(ReportArgs),
+    /// Scan the Clusters of a file and print statistics of each track:
+    /// byte and frame counts, bitrates, frame sizes and keyframe interval
+    Stats(ReportArgs),
+    /// Print a table of the cue points of a file, with their positions
+    /// resolved to absolute offsets and checked against the Clusters
+    Cues(ReportArgs),
+    /// Print a table of the tracks of a file: number, UID, type, codec,
+    /// language, flags, dimensions and sampling frequency
+    Tracks(ReportArgs),
+    /// Print the editions and chapters of a file as an indented timeline
+    /// with their titles in each language
+    Chapters(ReportArgs),
+    /// Print the tags of a file grouped by the Segment, track, edition,
+    /// chapter or attachment they apply to
+    Tags(ReportArgs),
+    /// Print a table of the files attached to a file, and optionally
+    /// extract them
+    Attachments(AttachmentsArgs),
+    /// Write the frames of a track to a file as an elementary stream,
+    /// undoing lacing and header stripping
+    Extract(ExtractArgs),
+    /// Check the structure of a file: corrupted regions, truncation,
+    /// mandatory elements and sizes. Exits with status 1 if errors are
+    /// found, so that the report can gate files in CI.
+    Verify(VerifyArgs),
+    /// Report whether a file can be streamed by byte ranges, such as with
+    /// DASH: headers, SeekHead and Cues before the first Cluster, Clusters
+    /// starting with keyframes and uniform Cluster durations
+    Streamability(ReportArgs),
+}
+
+#[doc(hidden)]
+#[derive(clap::Args)]
+struct ReportArgs {
+    /// Name of the MKV/WebM file
+    filename: String,
+
+    /// Output format of the report
+    #[clap(value_enum, short, long, default_value = "text")]
+    format: ReportFormat,
+}
+
+#[doc(hidden)]
+#[derive(clap::Args)]
+struct AttachmentsArgs {
+    #[clap(flatten)]
+    report: ReportArgs,
+
+    /// Write the attached files to this directory, under their own names
+    #[clap(long)]
+    extract_to: Option<PathBuf>,
+}
+
+#[doc(hidden)]
+#[derive(clap::Args)]
+struct ExtractArgs {
+    /// Name of the MKV/WebM file
+    filename: String,
+
+    /// Number of the track to extract
+    #[clap(long)]
+    track: u64,
+
+    /// File to write the frames to
+    #[clap(short, long)]
+    output: PathBuf,
+}
+
+#[doc(hidden)]
+#[derive(clap::Args)]
+struct VerifyArgs {
+    #[clap(flatten)]
+    report: ReportArgs,
+
+    /// Report gaps and overlaps between the blocks of audio and video
+    /// tracks longer than this duration, such as 0.040 seconds
+    #[clap(long, value_parser = parse_time, default_value = "0.001")]
+    gap_threshold: i64,
+
+    /// Also check the rules of the WebM Byte Stream Format of Media Source
+    /// Extensions: composition of the initialization segment, Clusters
+    /// with a Timestamp and blocks of every track, and unknown sizes
+    #[clap(long)]
+    mse: bool,
+}
+
+#[doc(hidden)]
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+/// Parse a time as [[HH:]MM:]SS[.fraction] into nanoseconds
+#[doc(hidden)]
+fn parse_time(time: &str) -> Result<i64, String> {
+    let parts = time.split(':').collect::<Vec<_>>();
+    if parts.len() > 3 {
+        return Err(format!("invalid time: {time}"));
+    }
+    let seconds = parts.iter().try_fold(0.0, |seconds, part| {
+        part.parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .map(|value| seconds * 60.0 + value)
+            .ok_or_else(|| format!("invalid time: {time}"))
+    })?;
+    Ok((seconds * 1e9).round() as i64)
 }
 
 #[doc(hidden)]
@@ -31,18 +355,344 @@ struct Args {
 enum Format {
     Json,
     Yaml,
+    Mkvinfo,
+    Ndjson,
+    Csv,
+    Cbor,
+    Msgpack,
+    Ffprobe,
+    Pretty,
+}
+
+impl Format {
+    #[doc(hidden)]
+    fn is_binary(&self) -> bool {
+        matches!(self, Format::Cbor | Format::Msgpack)
+    }
 }
 
 #[doc(hidden)]
-fn print_serialized<T: Serialize>(elements: &[T], format: &Format) -> anyhow::Result<()> {
-    let serialized = match format {
-        Format::Json => serde_json::to_string_pretty(elements).unwrap(),
-        Format::Yaml => serde_yaml::to_string(elements).unwrap(),
+#[derive(ValueEnum, Clone, PartialEq, Eq)]
+enum Profile {
+    /// Field names of mkvdump, such as header_size
+    #[clap(name = "mkvdump-v1")]
+    MkvdumpV1,
+    /// Field names in camelCase, such as headerSize
+    CamelCase,
+    /// Field names of mkvinfo from MKVToolNix, such as element, at and data
+    Mkvtoolnix,
+}
+
+impl Profile {
+    #[doc(hidden)]
+    fn field_name(&self, name: &str) -> String {
+        match self {
+            Profile::MkvdumpV1 => name.to_string(),
+            Profile::CamelCase => {
+                let mut words = name.split('_');
+                let first = words.next().unwrap_or_default().to_string();
+                words.fold(first, |mut camel_case, word| {
+                    let mut chars = word.chars();
+                    camel_case.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                    camel_case.extend(chars);
+                    camel_case
+                })
+            }
+            Profile::Mkvtoolnix => match name {
+                "id" => "element",
+                "position" => "at",
+                "value" => "data",
+                "track_number" => "track",
+                name => name,
+            }
+            .to_string(),
+        }
+    }
+
+    /// Rename the fields of an output: the ones of elements, with the fields
+    /// of their values, and the ones of Segments in the per-segment output.
+    /// Keys of other mappings are kept, as they may be data such as names.
+    #[doc(hidden)]
+    fn rename_fields(&self, value: &mut serde_yaml::Value) {
+        match value {
+            serde_yaml::Value::Mapping(mapping) => {
+                let is_element = mapping.contains_key("id");
+                *mapping = std::mem::take(mapping)
+                    .into_iter()
+                    .map(|(key, mut value)| {
+                        let field = key
+                            .as_str()
+                            .filter(|key| is_element || SEGMENT_OUTPUT_FIELDS.contains(key));
+                        match field {
+                            Some(field) if is_element && field != "children" => {
+                                self.rename_all_fields(&mut value)
+                            }
+                            _ => self.rename_fields(&mut value),
+                        }
+                        let key = match field {
+                            Some(field) => serde_yaml::Value::String(self.field_name(field)),
+                            None => key,
+                        };
+                        (key, value)
+                    })
+                    .collect();
+            }
+            serde_yaml::Value::Sequence(sequence) => {
+                for value in sequence {
+                    self.rename_fields(value);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Rename all the keys of mappings in the fields of an element, which
+    /// are all fields of its header or of its value
+    #[doc(hidden)]
+    fn rename_all_fields(&self, value: &mut serde_yaml::Value) {
+        match value {
+            serde_yaml::Value::Mapping(mapping) => {
+                *mapping = std::mem::take(mapping)
+                    .into_iter()
+                    .map(|(key, mut value)| {
+                        self.rename_all_fields(&mut value);
+                        let key = match key {
+                            serde_yaml::Value::String(name) => {
+                                serde_yaml::Value::String(self.field_name(&name))
+                            }
+                            key => key,
+                        };
+                        (key, value)
+                    })
+                    .collect();
+            }
+            serde_yaml::Value::Sequence(sequence) => {
+                for value in sequence {
+                    self.rename_all_fields(value);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(ValueEnum, Clone, PartialEq, Eq)]
+enum Encoding {
+    Hex,
+    Base64,
+}
+
+#[doc(hidden)]
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Output<'a> {
+    Linear(&'a [Element]),
+    Trees(Vec<ElementTree>),
+    TreeRefs(Vec<ElementTreeRef<'a>>),
+}
+
+/// Fields of SegmentOutput, to be renamed by the profiles
+#[doc(hidden)]
+const SEGMENT_OUTPUT_FIELDS: &[&str] = &["segment", "byte_range", "human_size", "elements"];
+
+#[doc(hidden)]
+#[derive(Serialize)]
+struct SegmentOutput<'a> {
+    segment: usize,
+    byte_range: Option<Range<usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    human_size: Option<String>,
+    elements: Output<'a>,
+}
+
+#[doc(hidden)]
+fn transforms_trees(args: &Args) -> bool {
+    args.show_implied_defaults
+        || args.compose_block_groups
+        || args.max_depth.is_some()
+        || !args.include.is_empty()
+        || !args.exclude.is_empty()
+}
+
+#[doc(hidden)]
+fn transform_trees(mut element_trees: Vec<ElementTree>, args: &Args) -> Vec<ElementTree> {
+    // Filtering trees rather than elements keeps the sizes of Master
+    // elements consistent with their children when building the trees
+    filter_element_trees(&mut element_trees, &args.include, &args.exclude);
+    if args.show_implied_defaults {
+        insert_implied_elements(&mut element_trees);
+    }
+    if args.compose_block_groups {
+        compose_block_groups(&mut element_trees);
+    }
+    if let Some(max_depth) = args.max_depth {
+        truncate_element_trees(&mut element_trees, max_depth);
+    }
+    element_trees
+}
+
+#[doc(hidden)]
+fn build_output<'a>(elements: &'a [Element], args: &Args) -> Output<'a> {
+    if args.linear_output {
+        Output::Linear(elements)
+    } else if transforms_trees(args) {
+        Output::Trees(transform_trees(build_element_trees(elements), args))
+    } else {
+        Output::TreeRefs(build_element_tree_refs(elements))
+    }
+}
+
+#[doc(hidden)]
+fn serialize<T: Serialize + ?Sized>(elements: &T, format: &Format) -> Vec<u8> {
+    match format {
+        Format::Json | Format::Ffprobe => serde_json::to_vec_pretty(elements).unwrap(),
+        Format::Yaml => serde_yaml::to_string(elements).unwrap().into_bytes(),
+        Format::Mkvinfo => mkvinfo::render(&serde_yaml::to_value(elements).unwrap()).into_bytes(),
+        Format::Pretty => {
+            let colors = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+            pretty::render(&serde_yaml::to_value(elements).unwrap(), colors).into_bytes()
+        }
+        // Blocks are listed as they are parsed, see `stream_blocks()`
+        Format::Csv => Vec::new(),
+        Format::Ndjson => match serde_yaml::to_value(elements).unwrap() {
+            serde_yaml::Value::Sequence(values) => values
+                .iter()
+                .map(|value| serde_json::to_vec(value).unwrap())
+                .collect::<Vec<_>>()
+                .join(&b'\n'),
+            value => serde_json::to_vec(&value).unwrap(),
+        },
+        Format::Cbor => {
+            let mut bytes = Vec::new();
+            ciborium::into_writer(elements, &mut bytes).unwrap();
+            bytes
+        }
+        Format::Msgpack => rmp_serde::to_vec_named(elements).unwrap(),
+    }
+}
+
+/// Whether the field names of the output are those of the profile, rather
+/// than fixed by the format
+#[doc(hidden)]
+fn renames_fields(args: &Args) -> bool {
+    args.profile != Profile::MkvdumpV1
+        && !matches!(
+            args.format,
+            Format::Mkvinfo | Format::Ffprobe | Format::Pretty
+        )
+}
+
+#[doc(hidden)]
+fn serialize_with_profile<T: Serialize + ?Sized>(
+    elements: &T,
+    args: &Args,
+) -> anyhow::Result<Vec<u8>> {
+    if !renames_fields(args) {
+        return Ok(serialize(elements, &args.format));
+    }
+    Ok(serialize(&profiled_value(elements, args)?, &args.format))
+}
+
+/// Output as a value with the field names of the profile, as mappings of
+/// YAML values keep the order of the fields
+#[doc(hidden)]
+fn profiled_value<T: Serialize + ?Sized>(
+    elements: &T,
+    args: &Args,
+) -> anyhow::Result<serde_yaml::Value> {
+    let mut value = serde_yaml::to_value(elements)?;
+    if renames_fields(args) {
+        args.profile.rename_fields(&mut value);
+    }
+    Ok(value)
+}
+
+/// Output of a file, either serialized or kept as a value to be an entry of
+/// the top-level map of files once all files are dumped
+#[doc(hidden)]
+enum FileOutput {
+    Serialized(Vec<u8>),
+    Entry(serde_yaml::Value),
+}
+
+impl FileOutput {
+    #[doc(hidden)]
+    fn into_bytes(self, format: &Format) -> Vec<u8> {
+        match self {
+            FileOutput::Serialized(serialized) => serialized,
+            FileOutput::Entry(value) => serialize(&value, format),
+        }
+    }
+}
+
+/// Output of a file, as an entry of the top-level map of files if several
+/// files are dumped in a format with a map at the top
+#[doc(hidden)]
+fn file_output<T: Serialize + ?Sized>(
+    elements: &T,
+    args: &Args,
+    filename: Option<&str>,
+) -> anyhow::Result<FileOutput> {
+    if filename.is_none() || !matches!(args.format, Format::Json | Format::Ffprobe | Format::Yaml) {
+        return Ok(FileOutput::Serialized(serialize_output(
+            elements, args, filename,
+        )?));
+    }
+    Ok(FileOutput::Entry(profiled_value(elements, args)?))
+}
+
+/// Serialize the output of a file, with the name of the file if several
+/// files are dumped
+#[doc(hidden)]
+fn serialize_output<T: Serialize + ?Sized>(
+    elements: &T,
+    args: &Args,
+    filename: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    let Some(filename) = filename else {
+        return serialize_with_profile(elements, args);
     };
+    if args.format == Format::Ndjson {
+        // Each line gets the name of its file as first field
+        let mut value = serde_yaml::to_value(elements)?;
+        if let serde_yaml::Value::Sequence(values) = &mut value {
+            for value in values {
+                if let serde_yaml::Value::Mapping(fields) = value {
+                    let mut entry =
+                        serde_yaml::Mapping::from_iter([("file".into(), filename.into())]);
+                    entry.extend(std::mem::take(fields));
+                    *fields = entry;
+                }
+            }
+        }
+        return serialize_with_profile(&value, args);
+    }
+    if matches!(args.format, Format::Mkvinfo | Format::Pretty) {
+        let serialized = serialize_with_profile(elements, args)?;
+        return Ok([format!("{filename}:\n").into_bytes(), serialized].concat());
+    }
+    // Binary formats have a document per file, mapping its name to its
+    // output, whose fields are renamed first so that the name is kept
+    let value = profiled_value(elements, args)?;
+    Ok(serialize(
+        &BTreeMap::from([(filename, value)]),
+        &args.format,
+    ))
+}
+
+/// Print an output, followed by a new line for text formats
+#[doc(hidden)]
+fn print(serialized: &[u8], format: &Format) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    let mut result = stdout.write_all(serialized);
+    if !format.is_binary() {
+        result = result.and_then(|_| stdout.write_all(b"\n"));
+    }
     // BrokenPipe errors are ok, as they can come from piping the output
     // into other unix tools like less/head etc.
     // https://github.com/rust-lang/rust/issues/46016#issuecomment-1242039016
-    match writeln!(std::io::stdout(), "{}", serialized) {
+    match result {
         Ok(_) => Ok(()),
         Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
         Err(e) => Err(e),
@@ -50,17 +700,738 @@ fn print_serialized<T: Serialize>(elements: &[T], format: &Format) -> anyhow::Re
     Ok(())
 }
 
+/// Print a report of a subcommand as text or serialized
 #[doc(hidden)]
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let elements = parse_elements_from_file(&args.filename, args.show_element_positions)?;
+fn print_report<T: Serialize + fmt::Display>(
+    report: &T,
+    format: ReportFormat,
+) -> anyhow::Result<()> {
+    let serialized = match format {
+        ReportFormat::Text => report.to_string(),
+        ReportFormat::Json => serde_json::to_string_pretty(report)?,
+        ReportFormat::Yaml => serde_yaml::to_string(report)?.trim_end().to_string(),
+    };
+    print(serialized.as_bytes(), &Format::Yaml)
+}
 
-    if args.linear_output {
-        print_serialized(&elements, &args.format)?;
+/// Write attached files to a directory, under their own names
+#[doc(hidden)]
+fn extract_attachments(
+    filename: &str,
+    attachments: &[AttachedFile],
+    directory: &Path,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(directory)?;
+    let mut file = File::open(filename)?;
+    for (index, attachment) in attachments.iter().enumerate() {
+        let (Some(position), Some(size)) = (attachment.data_position, attachment.data_size) else {
+            continue;
+        };
+        // Only the last component of the name is kept, so that attached
+        // files can't be written outside of the directory
+        let name = Path::new(&attachment.file_name)
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| format!("attachment-{}", index + 1).into());
+        let path = directory.join(name);
+        std::fs::write(&path, read_at(&mut file, position, size)?)?;
+        eprintln!("extracted {}", path.display());
+    }
+    Ok(())
+}
+
+/// Write the CodecPrivate of each track to track-N.bin in a directory
+#[doc(hidden)]
+fn extract_codec_private(filename: &str, directory: &Path) -> anyhow::Result<()> {
+    let tracks = Document::from_elements(&parse_headers(filename)?).tracks;
+    std::fs::create_dir_all(directory)?;
+    let mut file = File::open(filename)?;
+    for track in tracks {
+        let (Some(track_number), Some(position), Some(size)) = (
+            track.track_number,
+            track.codec_private_position,
+            track.codec_private_size,
+        ) else {
+            continue;
+        };
+        let path = directory.join(format!("track-{track_number}.bin"));
+        std::fs::write(&path, read_at(&mut file, position, size)?)?;
+        eprintln!("extracted {}", path.display());
+    }
+    Ok(())
+}
+
+/// Print the Master elements whose CRC-32 doesn't match their body
+#[doc(hidden)]
+fn check_crc(filename: &str, several_files: bool) -> anyhow::Result<()> {
+    let options = ParserOptions {
+        element_paths: true,
+        ..ParserOptions::default()
+    };
+    let (elements, _) = parse_elements_from_file(filename, options, false, None, None)?;
+    let prefix = file_prefix(filename, several_files);
+    for mismatch in check_crcs(&mut File::open(filename)?, &elements)? {
+        eprintln!("{prefix}CRC-32 mismatch: {mismatch}");
+    }
+    Ok(())
+}
+
+/// Write the frames of a track to a file, one after the other
+#[doc(hidden)]
+fn extract_track(args: &ExtractArgs) -> anyhow::Result<()> {
+    let tracks = Document::from_elements(&parse_headers(&args.filename)?).tracks;
+    let Some(track) = tracks
+        .iter()
+        .find(|track| track.track_number == Some(args.track))
+    else {
+        anyhow::bail!("no track {} in {}", args.track, args.filename);
+    };
+    let mut file = File::open(&args.filename)?;
+    // Bytes stripped from the start of each frame, if any
+    let stripped = match &track.content_compression {
+        None => Vec::new(),
+        Some(ContentCompression {
+            algorithm: Some(ContentCompAlgo::HeaderStripping),
+            settings_size,
+            settings_position,
+        }) => match (settings_position, settings_size) {
+            (Some(position), Some(size)) => read_at(&mut file, *position, *size)?,
+            _ => Vec::new(),
+        },
+        Some(compression) => anyhow::bail!(
+            "frames of track {} are compressed with {:?}, which isn't supported",
+            args.track,
+            compression.algorithm
+        ),
+    };
+
+    let mut output = BufWriter::new(File::create(&args.output)?);
+    let mut frames = 0;
+    for element in iter_elements_from_file(&args.filename, ParserOptions::default(), None, None)? {
+        let header = element?.header;
+        if !matches!(header.id, Id::SimpleBlock | Id::Block) {
+            continue;
+        }
+        let (Some(position), Some(body_size)) = (header.position, header.body_size) else {
+            continue;
+        };
+        let body = read_at(&mut file, position + header.header_size, body_size)?;
+        let (track_number, payloads) = split_frames(&body)?;
+        if track_number as u64 != args.track {
+            continue;
+        }
+        for payload in payloads {
+            output.write_all(&stripped)?;
+            output.write_all(payload)?;
+            frames += 1;
+        }
+    }
+    output.flush()?;
+    eprintln!(
+        "extracted {frames} frames of track {} to {}",
+        args.track,
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Read a range of bytes of a file
+#[doc(hidden)]
+fn read_at(file: &mut File, position: usize, size: usize) -> std::io::Result<Vec<u8>> {
+    let mut data = vec![0; size];
+    file.seek(SeekFrom::Start(position as u64))?;
+    file.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Parse the elements of a file, skipping over the bodies of Clusters
+#[doc(hidden)]
+fn parse_headers(filename: &str) -> anyhow::Result<Vec<Element>> {
+    let options = ParserOptions {
+        skip_clusters: true,
+        ..ParserOptions::default()
+    };
+    Ok(parse_elements_from_file(filename, options, false, None, None)?.0)
+}
+
+#[doc(hidden)]
+fn run_command(command: &Command) -> anyhow::Result<()> {
+    match command {
+        Command::Summary(args) => {
+            print_report(&summarize(&parse_headers(&args.filename)?), args.format)
+        }
+        Command::Cues(args) => {
+            print_report(&cue_table(&parse_headers(&args.filename)?), args.format)
+        }
+        Command::Stats(args) => {
+            let elements =
+                iter_elements_from_file(&args.filename, ParserOptions::default(), None, None)?;
+            print_report(&track_statistics(elements)?, args.format)
+        }
+        Command::Tracks(args) => {
+            print_report(&track_table(&parse_headers(&args.filename)?), args.format)
+        }
+        Command::Chapters(args) => {
+            print_report(&chapter_list(&parse_headers(&args.filename)?), args.format)
+        }
+        Command::Tags(args) => {
+            print_report(&tag_groups(&parse_headers(&args.filename)?), args.format)
+        }
+        Command::Attachments(args) => {
+            let table = attachment_table(&parse_headers(&args.report.filename)?);
+            if let Some(directory) = &args.extract_to {
+                extract_attachments(&args.report.filename, &table.attachments, directory)?;
+            }
+            print_report(&table, args.report.format)
+        }
+        Command::Extract(args) => extract_track(args),
+        Command::Verify(args) => {
+            let filename = &args.report.filename;
+            let (elements, _) =
+                parse_elements_from_file(filename, ParserOptions::default(), false, None, None)?;
+            let options = VerifyOptions {
+                gap_threshold_ns: args.gap_threshold,
+                mse: args.mse,
+            };
+            let report = verify_with_options(&elements, &options);
+            print_report(&report, args.report.format)?;
+            if !report.is_valid() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Streamability(args) => {
+            let (elements, _) = parse_elements_from_file(
+                &args.filename,
+                ParserOptions::default(),
+                false,
+                None,
+                None,
+            )?;
+            print_report(&streamability(&elements), args.format)
+        }
+    }
+}
+
+#[doc(hidden)]
+fn head(args: &Args) -> Option<Head> {
+    args.head
+        .map(Head::Elements)
+        .or(args.head_clusters.map(Head::Clusters))
+}
+
+/// Whether elements can be printed as they are parsed, i.e. if no option
+/// needs the complete file first
+#[doc(hidden)]
+fn streams(args: &Args) -> bool {
+    args.format == Format::Ndjson
+        && !args.parallel
+        && !args.per_segment
+        && !args.show_void_stats
+        && !args.human_timestamps
+        && !args.human_sizes
+        && args.start_time.is_none()
+        && args.end_time.is_none()
+        && args.track.is_empty()
+        && args.include.is_empty()
+        && args.exclude.is_empty()
+}
+
+/// Print the elements of a file as they are parsed, one per line
+#[doc(hidden)]
+fn stream_file(
+    filename: &str,
+    options: ParserOptions,
+    args: &Args,
+    several_files: bool,
+) -> anyhow::Result<()> {
+    let mut diagnostics = Diagnostics::new();
+    let mut stdout = std::io::stdout().lock();
+    let elements: Box<dyn Iterator<Item = _>> = if args.follow {
+        Box::new(follow_elements_from_file(
+            filename,
+            options,
+            head(args),
+            FOLLOW_INTERVAL,
+        )?)
     } else {
-        let element_trees = build_element_trees(&elements);
-        print_serialized(&element_trees, &args.format)?;
+        Box::new(iter_elements_from_file(
+            filename,
+            options,
+            head(args),
+            None,
+        )?)
+    };
+    for element in elements {
+        let mut element = element?;
+        diagnostics.inspect(&element);
+        if !args.show_element_positions {
+            element.header.position = None;
+        }
+        let line = serialize_output(&[element], args, several_files.then_some(filename))?;
+        match stdout.write_all(&[line, b"\n".to_vec()].concat()) {
+            Ok(_) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
     }
+    print_warnings(diagnostics, filename, args, several_files);
+    Ok(())
+}
 
+/// Print the SimpleBlocks and Blocks of a file as CSV rows as they are
+/// parsed
+#[doc(hidden)]
+fn stream_blocks(
+    filename: &str,
+    options: ParserOptions,
+    args: &Args,
+    several_files: bool,
+) -> anyhow::Result<()> {
+    let mut diagnostics = Diagnostics::new();
+    let mut stdout = std::io::stdout().lock();
+    let elements = iter_elements_from_file(filename, options, head(args), time_range(args))?
+        .inspect(|element| {
+            if let Ok(element) = element {
+                diagnostics.inspect(element);
+            }
+        });
+    let time_range = time_range(args);
+    for block in blocks(elements) {
+        let block = block?;
+        if !args.track.is_empty() && !args.track.contains(&block.track_number) {
+            continue;
+        }
+        let in_time_range =
+            |time_range: &Range<i64>| block.time_ns.is_some_and(|time| time_range.contains(&time));
+        if !time_range.as_ref().is_none_or(in_time_range) {
+            continue;
+        }
+        let mut fields = several_files
+            .then(|| csv_field(filename))
+            .into_iter()
+            .collect::<Vec<_>>();
+        fields.extend([
+            block.track_number.to_string(),
+            block
+                .time_ns
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            block.keyframe.to_string(),
+            block
+                .lacing
+                .map(|lacing| format!("{lacing:?}"))
+                .unwrap_or_default(),
+            block
+                .position
+                .map(|position| position.to_string())
+                .unwrap_or_default(),
+            block.size.map(|size| size.to_string()).unwrap_or_default(),
+        ]);
+        match writeln!(stdout, "{}", fields.join(",")) {
+            Ok(_) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    print_warnings(diagnostics, filename, args, several_files);
     Ok(())
 }
+
+/// Quote a CSV field if needed
+#[doc(hidden)]
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[doc(hidden)]
+fn file_prefix(filename: &str, several_files: bool) -> String {
+    if several_files {
+        format!("{filename}: ")
+    } else {
+        String::new()
+    }
+}
+
+#[doc(hidden)]
+fn print_warnings(diagnostics: Diagnostics, filename: &str, args: &Args, several_files: bool) {
+    if args.show_warnings {
+        let prefix = file_prefix(filename, several_files);
+        for diagnostic in diagnostics.into_diagnostics() {
+            eprintln!("{prefix}warning: {diagnostic}");
+        }
+    }
+}
+
+#[doc(hidden)]
+fn dump_file(
+    filename: &str,
+    options: ParserOptions,
+    args: &Args,
+    several_files: bool,
+) -> anyhow::Result<FileOutput> {
+    let (mut elements, diagnostics) = parse_elements_from_file(
+        filename,
+        options,
+        args.parallel,
+        head(args),
+        time_range(args),
+    )?;
+    let prefix = file_prefix(filename, several_files);
+    if args.show_warnings {
+        for diagnostic in &diagnostics {
+            eprintln!("{prefix}warning: {diagnostic}");
+        }
+    }
+    if args.show_void_stats {
+        let statistics = void_statistics(&elements);
+        if args.human_sizes {
+            eprintln!("{prefix}padding: {statistics:#}");
+        } else {
+            eprintln!("{prefix}padding: {statistics}");
+        }
+    }
+    if let Some(time_range) = time_range(args) {
+        retain_clusters_in_time_range(&mut elements, time_range);
+    }
+    set_hdr_summaries(&mut elements);
+    if args.human_timestamps {
+        set_human_times(&mut elements);
+    }
+    if args.human_sizes {
+        set_human_sizes(&mut elements);
+    }
+    let key = several_files.then_some(filename);
+    if args.format == Format::Ffprobe {
+        let document = Document::from_elements(&elements);
+        let size = std::fs::metadata(filename)?.len();
+        return file_output(&probe(&document, filename, Some(size)), args, key);
+    }
+    if !args.track.is_empty() {
+        retain_blocks_of_tracks(&mut elements, &args.track);
+    }
+    if args.linear_output {
+        filter_elements(&mut elements, &args.include, &args.exclude);
+    }
+
+    let segments = args.per_segment.then(|| group_by_segment(&elements));
+    if !args.show_element_positions {
+        for element in &mut elements {
+            element.header.position = None;
+        }
+    }
+
+    if let Some(segments) = segments {
+        let segments = segments
+            .into_iter()
+            .map(|segment| SegmentOutput {
+                segment: segment.index,
+                human_size: segment
+                    .byte_range
+                    .as_ref()
+                    .filter(|_| args.human_sizes)
+                    .map(|range| format_size(range.len())),
+                byte_range: segment.byte_range,
+                elements: build_output(&elements[segment.elements], args),
+            })
+            .collect::<Vec<_>>();
+        file_output(&segments, args, key)
+    } else if transforms_trees(args) && !args.linear_output {
+        let element_trees = transform_trees(build_element_trees_owned(elements), args);
+        file_output(&element_trees, args, key)
+    } else {
+        file_output(&build_output(&elements, args), args, key)
+    }
+}
+
+/// Reject the options that don't apply to the output format, which clap
+/// can't tell from a value of --format: the options of the tree output with
+/// the ndjson format, which is always linear, and the time range with the
+/// ffprobe format, which describes whole files
+#[doc(hidden)]
+fn check_format_options(args: &Args) -> Result<(), clap::Error> {
+    let message = match args.format {
+        Format::Ndjson => {
+            let option = if args.max_depth.is_some() {
+                "--max-depth"
+            } else if args.compose_block_groups {
+                "--compose-block-groups"
+            } else if args.show_implied_defaults {
+                "--show-implied-defaults"
+            } else {
+                return Ok(());
+            };
+            format!("{option} only applies to the tree output, but the ndjson format is linear")
+        }
+        Format::Ffprobe => {
+            let option = if args.start_time.is_some() {
+                "--start-time"
+            } else if args.end_time.is_some() {
+                "--end-time"
+            } else {
+                return Ok(());
+            };
+            format!("{option} doesn't apply to the ffprobe format, which describes whole files")
+        }
+        _ => return Ok(()),
+    };
+    Err(Args::command().error(ErrorKind::ArgumentConflict, message))
+}
+
+/// Time range in nanoseconds of the Clusters to show, if any
+#[doc(hidden)]
+fn time_range(args: &Args) -> Option<Range<i64>> {
+    (args.start_time.is_some() || args.end_time.is_some())
+        .then(|| args.start_time.unwrap_or(i64::MIN)..args.end_time.unwrap_or(i64::MAX))
+}
+
+#[doc(hidden)]
+fn main() -> anyhow::Result<()> {
+    let mut args = Args::parse();
+    if let Some(command) = &args.command {
+        return run_command(command);
+    }
+    if let Err(e) = check_format_options(&args) {
+        e.exit();
+    }
+    if args.follow {
+        args.format = Format::Ndjson;
+        if args.filenames.len() > 1 || !streams(&args) {
+            anyhow::bail!("--follow takes a single file and no option needing the complete file");
+        }
+    }
+    // NDJSON has one line per element, so it's always a linear output
+    if args.format == Format::Ndjson {
+        args.linear_output = true;
+    }
+    let options = ParserOptions {
+        element_paths: args.show_element_paths,
+        element_levels: args.show_element_levels,
+        header_field_sizes: args.show_header_field_sizes,
+        deprecated_elements: args.show_deprecated,
+        skip_clusters: args.no_clusters,
+        recover_corruption: !args.strict,
+        lenient_unknown_size: args.lenient_unknown_sizes,
+        lenient_unknown_leaf_size: args.lenient_unknown_sizes,
+        max_binary_preview: args.max_binary_preview,
+        binary_encoding: match args.binary_encoding {
+            Encoding::Hex => BinaryEncoding::Hex,
+            Encoding::Base64 => BinaryEncoding::Base64,
+        },
+        ..ParserOptions::default()
+    };
+
+    if let Some(directory) = &args.extract_codec_private {
+        for filename in &args.filenames {
+            match Path::new(filename).file_name() {
+                Some(name) if args.filenames.len() > 1 => {
+                    extract_codec_private(filename, &directory.join(name))?
+                }
+                _ => extract_codec_private(filename, directory)?,
+            }
+        }
+    }
+
+    if args.check_crc {
+        for filename in &args.filenames {
+            check_crc(filename, args.filenames.len() > 1)?;
+        }
+    }
+
+    if args.format == Format::Csv {
+        let mut columns = vec![
+            "track_number",
+            "time_ns",
+            "keyframe",
+            "lacing",
+            "position",
+            "size",
+        ];
+        if args.filenames.len() > 1 {
+            columns.insert(0, "file");
+        }
+        print(columns.join(",").as_bytes(), &args.format)?;
+        for filename in &args.filenames {
+            stream_blocks(filename, options.clone(), &args, args.filenames.len() > 1)?;
+        }
+        return Ok(());
+    }
+    if streams(&args) {
+        for filename in &args.filenames {
+            stream_file(filename, options.clone(), &args, args.filenames.len() > 1)?;
+        }
+        return Ok(());
+    }
+    if let [filename] = &args.filenames[..] {
+        let output = dump_file(filename, options, &args, false)?;
+        return print(&output.into_bytes(&args.format), &args.format);
+    }
+    let mut entries = serde_yaml::Mapping::new();
+    let mut serialized = Vec::new();
+    for filename in &args.filenames {
+        match dump_file(filename, options.clone(), &args, true)? {
+            FileOutput::Entry(value) => {
+                entries.insert(filename.as_str().into(), value);
+            }
+            FileOutput::Serialized(output) => serialized.push(output),
+        }
+    }
+    if !entries.is_empty() {
+        return print(&serialize(&entries, &args.format), &args.format);
+    }
+    match args.format {
+        // Binary documents of each file are simply concatenated
+        Format::Cbor | Format::Msgpack => print(&serialized.concat(), &args.format),
+        _ => print(&serialized.join(&b'\n'), &args.format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_options_with_linear_output() {
+        let parse = |args: &[&str]| Args::try_parse_from([&["mkvdump"], args, &["a.mkv"]].concat());
+        for option in [
+            &["--max-depth", "1"][..],
+            &["--compose-block-groups"],
+            &["--show-implied-defaults"],
+        ] {
+            for linear in ["-l", "--follow"] {
+                let error = parse(&[option, &[linear]].concat()).err().unwrap();
+                assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+            }
+            let args = parse(&[option, &["-f", "ndjson"]].concat()).unwrap();
+            let error = check_format_options(&args).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+
+            let args = parse(option).unwrap();
+            assert!(check_format_options(&args).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_time_range_with_ffprobe() {
+        for option in ["--start-time", "--end-time"] {
+            let args = Args::try_parse_from(["mkvdump", "-f", "ffprobe", option, "10", "a.mkv"]);
+            let error = check_format_options(&args.unwrap()).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("90"), Ok(90_000_000_000));
+        assert_eq!(parse_time("1:30"), Ok(90_000_000_000));
+        assert_eq!(parse_time("1:02:03.5"), Ok(3_723_500_000_000));
+        assert_eq!(parse_time("0.001"), Ok(1_000_000));
+        for time in ["1:2:3:4", "-5", "", "1:", "inf", "abc"] {
+            assert_eq!(parse_time(time), Err(format!("invalid time: {time}")));
+        }
+    }
+
+    #[test]
+    fn test_field_name() {
+        assert_eq!(Profile::MkvdumpV1.field_name("header_size"), "header_size");
+        assert_eq!(Profile::CamelCase.field_name("header_size"), "headerSize");
+        assert_eq!(
+            Profile::CamelCase.field_name("absolute_time_ns"),
+            "absoluteTimeNs"
+        );
+        assert_eq!(Profile::CamelCase.field_name("id"), "id");
+        assert_eq!(Profile::Mkvtoolnix.field_name("position"), "at");
+        assert_eq!(Profile::Mkvtoolnix.field_name("header_size"), "header_size");
+    }
+
+    #[test]
+    fn test_rename_fields() {
+        let mut value = serde_yaml::from_str(
+            "- id: SimpleBlock
+  header_size: 2
+  value:
+    track_number: 1
+    frame_sizes: [1, 2]",
+        )
+        .unwrap();
+        Profile::CamelCase.rename_fields(&mut value);
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<serde_yaml::Value>(
+                "- id: SimpleBlock
+  headerSize: 2
+  value:
+    trackNumber: 1
+    frameSizes: [1, 2]"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rename_fields_of_segments() {
+        let mut value = serde_yaml::from_str(
+            "- segment: 0
+  byte_range: {start: 0, end: 8}
+  elements:
+    - id: Cluster
+      header_size: 8",
+        )
+        .unwrap();
+        Profile::CamelCase.rename_fields(&mut value);
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<serde_yaml::Value>(
+                "- segment: 0
+  byteRange: {start: 0, end: 8}
+  elements:
+    - id: Cluster
+      headerSize: 8"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rename_fields_with_file_names() {
+        // Files named like fields keep their names
+        let elements: serde_yaml::Value =
+            serde_yaml::from_str("- id: Void\n  header_size: 2").unwrap();
+        for (profile, filename) in [("camel-case", "header_size"), ("mkvtoolnix", "id")] {
+            let args = Args::try_parse_from([
+                "mkvdump",
+                "--profile",
+                profile,
+                "-f",
+                "msgpack",
+                filename,
+                "a.mkv",
+            ])
+            .unwrap();
+            let serialized = serialize_output(&elements, &args, Some(filename)).unwrap();
+            let value: serde_yaml::Value = rmp_serde::from_slice(&serialized).unwrap();
+            let mut renamed = elements.clone();
+            args.profile.rename_fields(&mut renamed);
+            assert_ne!(renamed, elements);
+            assert_eq!(
+                value,
+                serde_yaml::Value::Mapping(serde_yaml::Mapping::from_iter([(
+                    filename.into(),
+                    renamed
+                )]))
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("ebml"), "ebml");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,155 @@
+//! Rendering of the output as indented text in the style of mkvinfo from
+//! MKVToolNix, such as `|+ EBML version: 1`
+
+use serde_yaml::{Mapping, Value};
+
+use crate::text::{self, inline, scalar, LineFormat};
+
+// Names given by mkvinfo to elements, when they aren't just the element
+// name split into words
+fn known_name(id: &str) -> Option<&'static str> {
+    Some(match id {
+        "EBML" => "EBML head",
+        "EBMLMaxIDLength" => "Maximum EBML ID length",
+        "EBMLMaxSizeLength" => "Maximum EBML size length",
+        "DocType" => "Document type",
+        "DocTypeVersion" => "Document type version",
+        "DocTypeReadVersion" => "Document type read version",
+        "Void" => "EBML void",
+        "CRC-32" => "EBML CRC-32",
+        "Seek" => "Seek entry",
+        "Info" => "Segment information",
+        "MuxingApp" => "Multiplexing application",
+        "WritingApp" => "Writing application",
+        "SegmentUUID" => "Segment UID",
+        "DateUTC" => "Date",
+        "TrackEntry" => "Track",
+        "CodecPrivate" => "Codec's private data",
+        "Video" => "Video track",
+        "Audio" => "Audio track",
+        "Timestamp" => "Cluster timestamp",
+        "SimpleTag" => "Simple",
+        "TagName" => "Name",
+        "TagString" => "String",
+        "TagBinary" => "Binary",
+        "ChapterTimeStart" => "Start",
+        "ChapterTimeEnd" => "End",
+        "ChapterDisplay" => "Display",
+        "ChapString" => "String",
+        "ChapLanguage" => "Language",
+        "AttachedFile" => "Attached",
+        "FileMimeType" => "MIME type",
+        _ => return None,
+    })
+}
+
+// Name of an element as shown by mkvinfo, such as `Timestamp scale` for
+// TimestampScale
+fn element_name(id: &str) -> String {
+    if let Some(name) = known_name(id) {
+        return name.to_string();
+    }
+    // Split into words before each capital letter that starts a word, so
+    // that acronyms such as UID stay whole
+    let chars = id.chars().collect::<Vec<_>>();
+    let mut words = Vec::<String>::new();
+    for (index, &c) in chars.iter().enumerate() {
+        let starts_word = index > 0
+            && c.is_ascii_uppercase()
+            && (chars[index - 1].is_ascii_lowercase()
+                || chars.get(index + 1).is_some_and(|c| c.is_ascii_lowercase()));
+        match words.last_mut() {
+            Some(word) if !starts_word => word.push(c),
+            _ => words.push(c.to_string()),
+        }
+    }
+    let words = words.into_iter().enumerate().map(|(index, word)| {
+        let is_acronym = word.len() > 1 && word.chars().all(|c| !c.is_ascii_lowercase());
+        if index == 0 || is_acronym {
+            word
+        } else {
+            word.to_ascii_lowercase()
+        }
+    });
+    words.collect::<Vec<_>>().join(" ")
+}
+
+fn prefix(depth: usize) -> String {
+    match depth {
+        0 => "+ ".to_string(),
+        depth => format!("|{}+ ", " ".repeat(depth - 1)),
+    }
+}
+
+// Lines such as `|+ Timestamp scale: 1000000 at 42`
+struct Mkvinfo;
+
+impl LineFormat for Mkvinfo {
+    fn element(&self, element: &Mapping, id: &str, depth: usize) -> String {
+        let mut line = format!("{}{}", prefix(depth), element_name(id));
+        if let Some(value) = element.get("value").filter(|value| !value.is_null()) {
+            line.push_str(": ");
+            line.push_str(&scalar(value).unwrap_or_else(|| inline(value)));
+        }
+        if let Some(position) = element.get("position").and_then(scalar) {
+            line.push_str(&format!(" at {position}"));
+        }
+        line
+    }
+
+    fn field(&self, key: &str, value: &str, depth: usize) -> String {
+        format!("{}{}: {value}", prefix(depth), key.replace('_', " "))
+    }
+}
+
+/// Render a serialized output, such as element trees, as mkvinfo text
+pub fn render(value: &Value) -> String {
+    text::render(value, &Mkvinfo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_name() {
+        assert_eq!(element_name("SegmentUUID"), "Segment UID");
+        assert_eq!(element_name("TimestampScale"), "Timestamp scale");
+        assert_eq!(element_name("EBMLVersion"), "EBML version");
+        assert_eq!(element_name("TrackUID"), "Track UID");
+        assert_eq!(element_name("CodecID"), "Codec ID");
+        assert_eq!(element_name("Cluster"), "Cluster");
+    }
+
+    #[test]
+    fn test_render() {
+        const OUTPUT: &str = "- segment: 0
+  elements:
+  - id: Segment
+    position: 36
+    children:
+    - id: Info
+      children:
+      - id: TimestampScale
+        value: 1000000
+    - id: SimpleBlock
+      value:
+        track_number: 1
+        timestamp: 0
+        keyframe: true
+        invisible: false
+  - id: Void
+    level: 1
+    value: 4 bytes";
+        let value = serde_yaml::from_str(OUTPUT).unwrap();
+        assert_eq!(
+            render(&value),
+            "+ segment: 0\n\
+             + Segment at 36\n\
+             |+ Segment information\n\
+             | + Timestamp scale: 1000000\n\
+             |+ Simple block: track number 1, timestamp 0, keyframe\n\
+             |+ EBML void: 4 bytes"
+        );
+    }
+}
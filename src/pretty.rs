@@ -0,0 +1,141 @@
+//! Rendering of the output as indented text with ANSI colors, for
+//! interactive inspection in a terminal
+
+use mkvparser::elements::{Id, Type};
+use serde_yaml::{Mapping, Value};
+
+use crate::text::{self, inline, scalar, LineFormat};
+
+const RESET: &str = "\x1b[0m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+const BOLD_RED: &str = "\x1b[1;31m";
+
+// Text wrapped in an ANSI color, if colors are enabled
+struct Painter {
+    colors: bool,
+}
+
+impl Painter {
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.colors {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn type_name(id: &str) -> &'static str {
+    let Ok(id) = id.parse::<Id>() else {
+        return "unknown";
+    };
+    match id.get_type() {
+        Type::Unsigned => "uint",
+        Type::Signed => "int",
+        Type::Float => "float",
+        Type::String => "string",
+        Type::Utf8 => "utf-8",
+        Type::Date => "date",
+        Type::Master => "master",
+        Type::Binary => "binary",
+    }
+}
+
+impl LineFormat for Painter {
+    fn element(&self, element: &Mapping, id: &str, depth: usize) -> String {
+        let is_corrupted = id == Id::corrupted().to_string();
+        let mut line = "  ".repeat(depth);
+        line.push_str(&self.paint(if is_corrupted { BOLD_RED } else { BOLD_BLUE }, id));
+        line.push(' ');
+        line.push_str(&self.paint(DIM, type_name(id)));
+
+        let size = element
+            .get("size")
+            .filter(|size| size.is_u64())
+            .or_else(|| element.get("computed_size"))
+            .and_then(scalar);
+        // Sizes with binary units keep the exact byte count alongside
+        let size = match (size, element.get("human_size").and_then(scalar)) {
+            (Some(size), Some(human_size)) => Some(format!("{human_size}, {size}")),
+            (size, _) => size,
+        };
+        let position = element.get("position").and_then(scalar);
+        let details = match (size, position) {
+            (Some(size), Some(position)) => Some(format!("{size} bytes at {position}")),
+            (Some(size), None) => Some(format!("{size} bytes")),
+            (None, Some(position)) => Some(format!("at {position}")),
+            (None, None) => None,
+        };
+        if let Some(details) = details {
+            line.push_str(&self.paint(DIM, &format!(" ({details})")));
+        }
+
+        if let Some(value) = element.get("value").filter(|value| !value.is_null()) {
+            let color = match value {
+                _ if is_corrupted => BOLD_RED,
+                Value::Number(_) => YELLOW,
+                Value::Bool(_) => MAGENTA,
+                Value::String(_) => GREEN,
+                _ => CYAN,
+            };
+            line.push_str(": ");
+            line.push_str(&self.paint(color, &scalar(value).unwrap_or_else(|| inline(value))));
+        }
+        line
+    }
+
+    fn field(&self, key: &str, value: &str, depth: usize) -> String {
+        format!("{}{}: {value}", "  ".repeat(depth), self.paint(DIM, key))
+    }
+}
+
+/// Render a serialized output, such as element trees, as indented text
+/// with ANSI colors if `colors` is set
+pub fn render(value: &Value, colors: bool) -> String {
+    text::render(value, &Painter { colors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "- id: Segment
+  size: Unknown
+  position: 36
+  children:
+  - id: TimestampScale
+    size: 7
+    human_size: 7 B
+    value: 1000000
+  - id: Corrupted
+    value: 3 bytes";
+
+    #[test]
+    fn test_render_without_colors() {
+        let value = serde_yaml::from_str(OUTPUT).unwrap();
+        assert_eq!(
+            render(&value, false),
+            "Segment master (at 36)\n  \
+             TimestampScale uint (7 B, 7 bytes): 1000000\n  \
+             Corrupted binary: 3 bytes"
+        );
+    }
+
+    #[test]
+    fn test_render_with_colors() {
+        let value = serde_yaml::from_str(OUTPUT).unwrap();
+        let rendered = render(&value, true);
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "\x1b[1;34mSegment\x1b[0m \x1b[2mmaster\x1b[0m\x1b[2m (at 36)\x1b[0m"
+        );
+        assert!(lines[1].ends_with(": \x1b[33m1000000\x1b[0m"));
+        assert!(lines[2].starts_with("  \x1b[1;31mCorrupted\x1b[0m"));
+    }
+}
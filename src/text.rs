@@ -0,0 +1,89 @@
+//! Walk over a serialized output shared by the indented text formats, which
+//! only differ in how they format each line
+
+use serde_yaml::{Mapping, Value};
+
+// How a text format shows elements and other fields
+pub(crate) trait LineFormat {
+    // Line of an element, at the given depth in the output
+    fn element(&self, element: &Mapping, id: &str, depth: usize) -> String;
+
+    // Line of a field of a mapping that isn't an element, such as the
+    // index of a Segment, with its value already formatted
+    fn field(&self, key: &str, value: &str, depth: usize) -> String;
+}
+
+pub(crate) fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some("null".to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::String(value) => Some(value.clone()),
+        Value::Tagged(tagged) => scalar(&tagged.value),
+        Value::Sequence(_) | Value::Mapping(_) => None,
+    }
+}
+
+// Values with fields, such as the ones of SimpleBlocks, are shown on a
+// single line as `track number 1, timestamp 0, keyframe`
+pub(crate) fn inline(value: &Value) -> String {
+    match value {
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .filter(|(_, value)| !matches!(value, Value::Bool(false)))
+            .map(|(key, value)| {
+                let key = scalar(key).unwrap_or_default().replace('_', " ");
+                match value {
+                    Value::Bool(true) => key,
+                    value => format!("{key} {}", inline(value)),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Sequence(values) => values.iter().map(inline).collect::<Vec<_>>().join("/"),
+        value => scalar(value).unwrap_or_default(),
+    }
+}
+
+fn render_value(value: &Value, depth: usize, format: &impl LineFormat, lines: &mut Vec<String>) {
+    match value {
+        Value::Sequence(values) => {
+            for value in values {
+                render_value(value, depth, format, lines);
+            }
+        }
+        Value::Mapping(mapping) => {
+            if let Some(id) = mapping.get("id").and_then(Value::as_str) {
+                // Elements of the linear output are indented by their
+                // level, if known
+                let depth = match mapping.get("level").and_then(Value::as_u64) {
+                    Some(level) => level as usize,
+                    None => depth,
+                };
+                lines.push(format.element(mapping, id, depth));
+                if let Some(children) = mapping.get("children") {
+                    render_value(children, depth + 1, format, lines);
+                }
+                return;
+            }
+            // Other mappings, such as the ones of Segments in the
+            // per-segment output, are shown field by field
+            for (key, value) in mapping {
+                let key = scalar(key).unwrap_or_default();
+                match scalar(value) {
+                    Some(value) => lines.push(format.field(&key, &value, depth)),
+                    None if key == "elements" => render_value(value, depth, format, lines),
+                    None => lines.push(format.field(&key, &inline(value), depth)),
+                }
+            }
+        }
+        value => lines.extend(scalar(value)),
+    }
+}
+
+// Render a serialized output, such as element trees, one line per element
+pub(crate) fn render(value: &Value, format: &impl LineFormat) -> String {
+    let mut lines = Vec::new();
+    render_value(value, 0, format, &mut lines);
+    lines.join("\n")
+}